@@ -1,6 +1,6 @@
 use std::ops::{Add, Sub, Mul};
 use std::fmt::Debug;
-use pathfinder_content::outline::Contour;
+use pathfinder_content::outline::ContourIterFlags;
 use crate::prelude::*;
 use crate::parser::{number_list_4, one_or_two_numbers, one_or_three_numbers};
 
@@ -19,8 +19,10 @@ impl<T> Animate<T> where T: Parse + Clone {
         let mode = AnimationMode::parse_node(node, value, calc_mode)?;
         let fill = parse_attr_or(node, "fill", AnimationFill::Remove)?;
         let default_additive = match mode {
-            AnimationMode::Absolute { .. } | AnimationMode::Values { .. }=> Additive::Replace,
-            AnimationMode::Relative { .. } => Additive::Sum
+            AnimationMode::Absolute { .. } | AnimationMode::Values { .. } => Additive::Replace,
+            // a by animation adds to the underlying value, a from-by animation replaces it
+            AnimationMode::Relative { from: None, .. } => Additive::Sum,
+            AnimationMode::Relative { from: Some(_), .. } => Additive::Replace,
         };
         let additive = parse_attr_or(node, "additive", default_additive)?;
 
@@ -41,21 +43,140 @@ impl<T> Animate<T> where T: Parse + Clone + Default {
 
 #[derive(Debug, Clone)]
 pub struct Timing {
-    pub begin: Time,
+    /// the begin times, in ascending order
+    pub begin: Vec<Time>,
     pub scale: f32,
+    /// end of the active duration, in multiples of the simple duration (`dur`).
+    /// It is 1 unless `min` or `max` change the active duration.
+    pub active_end: f32,
+    pub restart: Restart,
    //repeat_until: Time,
 }
 impl ParseNode for Timing {
     fn parse_node(node: &Node) -> Result<Timing, Error> {
-        let begin = parse_attr_or(node, "begin", Time(0.0))?;
+        let mut begin = match node.attribute("begin") {
+            Some(list) => list.split(';').map(|s| Time::parse(s.trim())).collect::<Result<Vec<_>, _>>()?,
+            None => vec![Time(0.0)]
+        };
+        begin.sort_by(|a, b| a.partial_cmp(b).unwrap());
         let duration: Time = parse_attr(node, "dur")?;
-        Ok(Timing { begin, scale: 1.0 / duration.seconds() })
+        let min: Option<Time> = node.attribute("min").map(Time::parse).transpose()?;
+        let max: Option<Time> = node.attribute("max").map(Time::parse).transpose()?;
+        let restart = parse_attr_or(node, "restart", Restart::Always)?;
+
+        let active = match (min, max) {
+            // both are ignored if they contradict each other
+            (Some(min), Some(max)) if min > max => duration,
+            (min, max) => {
+                let d = max.map_or(duration, |max| if max < duration { max } else { duration });
+                min.map_or(d, |min| if min > d { min } else { d })
+            }
+        };
+        Ok(Timing {
+            begin,
+            scale: 1.0 / duration.seconds(),
+            active_end: active.seconds() / duration.seconds(),
+            restart,
+        })
+    }
+}
+
+/// when an animation may begin again
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Restart {
+    Always,
+    WhenNotActive,
+    Never,
+}
+impl Parse for Restart {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "always" => Ok(Restart::Always),
+            "whenNotActive" => Ok(Restart::WhenNotActive),
+            "never" => Ok(Restart::Never),
+            _ => Err(Error::InvalidAttributeValue(s.into()))
+        }
     }
 }
+/// `<animateMotion>`, moving the element along a path
+#[derive(Debug, Clone)]
 pub struct AnimateMotion {
-    pub path: Contour,
+    /// the path, flattened into line segments: the distance along the path and the point
+    pub points: Vec<(f32, Vector2F)>,
     pub path_len: f32,
     pub timing: Timing,
+    pub fill: AnimationFill,
+    pub rotate: MotionRotate,
+}
+/// number of line segments each curve of a motion path is flattened into
+const MOTION_CURVE_STEPS: usize = 16;
+
+impl ParseNode for AnimateMotion {
+    fn parse_node(node: &Node) -> Result<AnimateMotion, Error> {
+        let timing = Timing::parse_node(node)?;
+        let fill = parse_attr_or(node, "fill", AnimationFill::Remove)?;
+        let rotate = parse_attr_or(node, "rotate", MotionRotate::Angle(0.0))?;
+        let path = parse_path_data(get_attr(node, "path")?)?;
+
+        let mut vertices = vec![];
+        for contour in path.contours() {
+            for segment in contour.iter(ContourIterFlags::empty()) {
+                if vertices.is_empty() {
+                    vertices.push(segment.baseline.from());
+                }
+                if segment.is_line() {
+                    vertices.push(segment.baseline.to());
+                } else {
+                    let cubic = segment.to_cubic();
+                    let cubic = cubic.as_cubic_segment();
+                    vertices.extend((1 ..= MOTION_CURVE_STEPS).map(|i| cubic.sample(i as f32 / MOTION_CURVE_STEPS as f32)));
+                }
+            }
+        }
+
+        let mut path_len = 0.0;
+        let mut points = Vec::with_capacity(vertices.len());
+        for (i, &p) in vertices.iter().enumerate() {
+            if i > 0 {
+                path_len += (p - vertices[i - 1]).length();
+            }
+            points.push((path_len, p));
+        }
+
+        Ok(AnimateMotion { points, path_len, timing, fill, rotate })
+    }
+}
+
+/// the `rotate` attribute of `<animateMotion>`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MotionRotate {
+    /// follow the direction of the path
+    Auto,
+    /// follow the direction of the path, turned by 180°
+    AutoReverse,
+    /// fixed angle in radians
+    Angle(f32),
+}
+impl Parse for MotionRotate {
+    fn parse(s: &str) -> Result<Self, Error> {
+        match s {
+            "auto" => Ok(MotionRotate::Auto),
+            "auto-reverse" => Ok(MotionRotate::AutoReverse),
+            _ => Ok(MotionRotate::Angle(deg2rad(f32::parse(s)?)))
+        }
+    }
+}
+#[test]
+fn test_animate_motion() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="r" width="1" height="1">
+            <animateMotion path="M0,0 L100,0 L100,50" dur="2s" rotate="auto-reverse"/>
+        </rect>
+    </svg>"#).unwrap();
+    let motion = item!(svg, "r", Rect).attrs.motion.as_ref().unwrap();
+    assert_eq!(motion.rotate, MotionRotate::AutoReverse);
+    assert_eq!(motion.path_len, 150.0);
+    assert_eq!(motion.points.len(), 3);
 }
 
 
@@ -144,8 +265,25 @@ impl UnitSpline {
 }
 
 impl Timing {
+    /// the position within the simple duration at time `t`, relative to the begin of the current interval.
+    /// Negative before the first begin.
     pub fn pos(&self, t: Time) -> f32 {
-        (t - self.begin).seconds() * self.scale
+        match self.interval_begin(t) {
+            Some(begin) => (t - begin).seconds() * self.scale,
+            None => -1.0
+        }
+    }
+    /// the begin of the interval that is current at `t`, after applying `restart` to all begin times until then
+    fn interval_begin(&self, t: Time) -> Option<Time> {
+        let mut current: Option<Time> = None;
+        for &begin in self.begin.iter().take_while(|&&begin| begin <= t) {
+            current = match (current, self.restart) {
+                (None, _) | (Some(_), Restart::Always) => Some(begin),
+                (Some(c), Restart::WhenNotActive) if (begin - c).seconds() * self.scale >= self.active_end => Some(begin),
+                (Some(c), _) => Some(c),
+            };
+        }
+        current
     }
 }
 
@@ -296,21 +434,24 @@ impl Parse for Transform {
 #[derive(Debug, Clone)]
 pub enum AnimationMode<T> {
     Absolute { from: T, to: T },
-    Relative { by: T },
+    /// `by`, animating from `from` (or the underlying value without it) to that plus `by`
+    Relative { from: Option<T>, by: T },
     Values { pairs: Vec<(f32, T)>, splines: Vec<UnitSpline> },
 }
 impl<T> AnimationMode<T> where T: Parse + Clone {
     pub fn parse_node(node: &Node, value: &T, calc_mode: CalcMode) -> Result<Self, Error> {
-        let from = node.attribute("from");
+        let from = node.attribute("from").map(T::parse).transpose()?;
         let to = node.attribute("to");
+        let by = node.attribute("by");
 
-        if from.is_some() | to.is_some() {
-            let from = from.map(T::parse).transpose()?.unwrap_or_else(|| value.clone());
-            let to = to.map(T::parse).transpose()?.unwrap_or_else(|| value.clone());
-            Ok(AnimationMode::Absolute { from, to })
-        } else if let Some(by) = node.attribute("by") {
-            let by = T::parse(by)?;
-            Ok(AnimationMode::Relative { by })
+        // `to` takes precedence over `by`
+        if let Some(to) = to {
+            let from = from.unwrap_or_else(|| value.clone());
+            Ok(AnimationMode::Absolute { from, to: T::parse(to)? })
+        } else if let Some(by) = by {
+            Ok(AnimationMode::Relative { from, by: T::parse(by)? })
+        } else if let Some(from) = from {
+            Ok(AnimationMode::Absolute { from, to: value.clone() })
         } else if let Some(values) = node.attribute("values") {
             let values = values.split(";").map(str::trim);
             let key_times = get_attr(node, "keyTimes")?.split(";").map(str::trim);
@@ -371,3 +512,20 @@ impl Parse for Additive {
         }
     }
 }
+
+#[test]
+fn test_restart() {
+    let timing = |restart: &str| {
+        let svg = format!(r#"<svg xmlns="http://www.w3.org/2000/svg"><rect id="r" width="1" height="1">
+            <animate attributeName="opacity" from="0" to="1" begin="0s; 2s" dur="4s" restart="{}"/>
+        </rect></svg>"#, restart);
+        let svg = Svg::from_str(&svg).unwrap();
+        let timing = item!(svg, "r", Rect).attrs.opacity.animations[0].timing.clone();
+        timing
+    };
+    let t = Time::from_seconds(3.0);
+    assert_eq!(timing("always").pos(t), 0.25);
+    // the second begin falls into the active interval of the first
+    assert_eq!(timing("whenNotActive").pos(t), 0.75);
+    assert_eq!(timing("never").pos(t), 0.75);
+}
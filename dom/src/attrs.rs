@@ -1,7 +1,8 @@
 use crate::prelude::*;
 
 use pathfinder_content::{
-    fill::{FillRule}
+    fill::{FillRule},
+    effects::{BlendMode},
 };
 use svgtypes::{Length};
 use isolang::Language;
@@ -11,6 +12,7 @@ pub struct Attrs {
     pub clip_path: Option<ClipPathAttr>,
     pub clip_rule: Option<FillRule>,
     pub transform: Transform,
+    pub motion: Option<AnimateMotion>,
     pub opacity: Value<Option<f32>>,
     pub fill: Value<Fill>,
     pub fill_rule: Option<FillRule>,
@@ -20,11 +22,35 @@ pub struct Attrs {
     pub stroke_opacity: Value<Option<f32>>,
     pub stroke_dasharray: Value<Option<DashArray>>,
     pub stroke_dashoffset: Value<Option<Length>>,
+    pub stroke_linejoin: Option<StrokeLineJoin>,
+    pub stroke_linecap: Option<StrokeLineCap>,
+    pub stroke_miterlimit: Option<f32>,
+    pub paint_order: Option<PaintOrder>,
+    pub marker_start: Option<MarkerAttr>,
+    pub marker_mid: Option<MarkerAttr>,
+    pub marker_end: Option<MarkerAttr>,
     pub display: bool,
+    pub visibility: Option<Visibility>,
     pub filter: Option<Iri>,
-    pub font_size: Value<Option<LengthY>>,
+    pub color_interpolation_filters: Option<ColorInterpolation>,
+    pub mask: Option<Iri>,
+    pub mix_blend_mode: Option<BlendMode>,
+    pub font_size: Value<Option<FontSize>>,
+    pub font_variant: Option<FontVariant>,
+    pub dominant_baseline: Option<DominantBaseline>,
+    /// `alignment-baseline`, which is not inherited. `None` aligns by the `dominant-baseline`.
+    pub alignment_baseline: Option<DominantBaseline>,
+    pub text_decoration: Option<TextDecoration>,
+    pub text_decoration_style: Option<TextDecorationStyle>,
     pub direction: Option<TextFlow>,
+    /// `white-space`, or the equivalent of `xml:space` if it is not set
+    pub white_space: Option<WhiteSpace>,
+    pub text_anchor: Option<TextAnchor>,
     pub lang: Option<Language>,
+    /// the `color` property, which `currentColor` refers to
+    pub color: Option<Color>,
+    /// `systemLanguage`: the language tags of which one has to match a language of the user
+    pub system_language: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone)]
@@ -37,7 +63,7 @@ impl Parse for Fill {
 
 fn parse_paint(s: &str) -> Result<Option<Paint>, Error> {
     match s {
-        "inherit" | "currentColor" | "currentcolor" => Ok(None),
+        "inherit" => Ok(None),
         _ => Paint::parse(s).map(Some)
     }
 }
@@ -61,31 +87,105 @@ impl Parse for Language {
     }
 }
 
+/// `color: currentColor` is the same as `inherit`
+fn parse_color_attr(s: &str) -> Result<Option<Color>, Error> {
+    match s {
+        "inherit" | "currentColor" | "currentcolor" => Ok(None),
+        _ => Color::parse(s).map(Some)
+    }
+}
+
+/// `auto` and `baseline` leave the alignment to the `dominant-baseline`
+fn parse_alignment_baseline(s: &str) -> Result<Option<DominantBaseline>, Error> {
+    match s {
+        "auto" | "baseline" => Ok(None),
+        _ => DominantBaseline::parse(s).map(Some)
+    }
+}
+
+/// comma separated list of BCP 47 language tags
+fn parse_language_list(s: &str) -> Result<Option<Vec<String>>, Error> {
+    Ok(Some(s.split(',').map(|tag| tag.trim()).filter(|tag| !tag.is_empty()).map(String::from).collect()))
+}
+
+/// true if the language `tag` matches the language `preference` of the user:
+/// they are equal (ignoring case), or one is a prefix of the other, followed by a `-`.
+/// So `en` matches `en-US` and `en-US` matches `en`, but `e` does not match `en`.
+pub fn language_matches(tag: &str, preference: &str) -> bool {
+    let (short, long) = if tag.len() <= preference.len() { (tag, preference) } else { (preference, tag) };
+    !short.is_empty()
+        && long.get(.. short.len()).map_or(false, |prefix| prefix.eq_ignore_ascii_case(short))
+        && (long.len() == short.len() || long.as_bytes()[short.len()] == b'-')
+}
+
+#[test]
+fn test_language_matches() {
+    assert!(language_matches("en", "en-GB"));
+    assert!(language_matches("en-GB", "en"));
+    assert!(language_matches("EN-gb", "en-GB"));
+    assert!(!language_matches("e", "en"));
+    assert!(!language_matches("en-GB", "en-US"));
+    assert_eq!(parse_language_list("en, de-AT").unwrap(), Some(vec!["en".to_string(), "de-AT".to_string()]));
+}
+
 impl Attrs {
     pub fn parse<'i, 'a: 'i>(node: &Node<'i, 'a>) -> Result<Attrs, Error> {
         parse!(node => {
             var clip_path ("clip-path"): Option<ClipPathAttr> => ClipPathAttr::parse,
-            var clip_rule ("clip-rule"): Option<FillRule>,
+            var clip_rule ("clip-rule"): Option<FillRule> => inherit(FillRule::parse),
             anim transform: Transform,
             anim opacity: Value<Option<f32>>,
             anim fill: Value<Fill> = Value::new(Fill(None)),
-            var fill_rule ("fill-rule"): Option<FillRule> = Some(FillRule::Winding) => inherit(FillRule::parse),
+            var fill_rule ("fill-rule"): Option<FillRule> => inherit(FillRule::parse),
             anim fill_opacity ("fill-opacity"): Value<Option<f32>>,
             anim stroke: Value<Stroke> = Value::new(Stroke(None)),
             anim stroke_width ("stroke-width"): Value<Option<Length>>,
             anim stroke_opacity ("stroke-opacity"): Value<Option<f32>>,
-            anim stroke_dasharray ("stroke-dasharray"): Value<Option<DashArray>>,
+            anim stroke_dasharray ("stroke-dasharray"): Value<Option<DashArray>> => parse_dasharray_attr,
             anim stroke_dashoffset ("stroke-dashoffset"): Value<Option<Length>>,
+            var stroke_linejoin ("stroke-linejoin"): Option<StrokeLineJoin> => inherit(StrokeLineJoin::parse),
+            var stroke_linecap ("stroke-linecap"): Option<StrokeLineCap> => inherit(StrokeLineCap::parse),
+            var stroke_miterlimit ("stroke-miterlimit"): Option<f32> => parse_miterlimit,
+            var paint_order ("paint-order"): Option<PaintOrder> => inherit(PaintOrder::parse),
+            var marker_start ("marker-start"): Option<MarkerAttr> => MarkerAttr::parse,
+            var marker_mid ("marker-mid"): Option<MarkerAttr> => MarkerAttr::parse,
+            var marker_end ("marker-end"): Option<MarkerAttr> => MarkerAttr::parse,
             var display: bool = true => parse_display,
+            var visibility: Option<Visibility> => inherit(Visibility::parse),
             var filter: Option<Iri>,
-            anim font_size ("font-size"): Value<Option<LengthY>>,
+            var color_interpolation_filters ("color-interpolation-filters"): Option<ColorInterpolation> => inherit(ColorInterpolation::parse),
+            var mask: Option<Iri>,
+            var mix_blend_mode ("mix-blend-mode"): Option<BlendMode>,
+            anim font_size ("font-size"): Value<Option<FontSize>>,
+            var font_variant ("font-variant"): Option<FontVariant> => inherit(FontVariant::parse),
+            var dominant_baseline ("dominant-baseline"): Option<DominantBaseline> => inherit(DominantBaseline::parse),
+            var alignment_baseline ("alignment-baseline"): Option<DominantBaseline> => parse_alignment_baseline,
+            var text_decoration ("text-decoration"): Option<TextDecoration>,
+            var text_decoration_style ("text-decoration-style"): Option<TextDecorationStyle> => inherit(TextDecorationStyle::parse),
             var direction: Option<TextFlow>,
+            var white_space ("white-space"): Option<WhiteSpace> => inherit(WhiteSpace::parse),
+            var text_anchor ("text-anchor"): Option<TextAnchor> => inherit(TextAnchor::parse),
             var lang: Option<Language>,
+            var color: Option<Color> => parse_color_attr,
+            var system_language ("systemLanguage"): Option<Vec<String>> => parse_language_list,
+        });
+        // `lang` wins over `xml:lang`
+        let lang = lang.or_else(|| node.attribute((roxmltree::NS_XML_URI, "lang")).and_then(|s| Language::parse(s).ok()));
+        // `xml:space="preserve"` is `white-space: pre`
+        let white_space = white_space.or_else(|| match node.attribute((roxmltree::NS_XML_URI, "space")) {
+            Some("preserve") => Some(WhiteSpace::Pre),
+            Some("default") => Some(WhiteSpace::Normal),
+            _ => None
         });
+        let motion = node.children()
+            .find(|n| n.is_element() && n.tag_name().name() == "animateMotion")
+            .map(|n| AnimateMotion::parse_node(&n))
+            .transpose()?;
         Ok(Attrs {
             clip_path,
             clip_rule,
             transform,
+            motion,
             opacity,
             fill,
             fill_rule,
@@ -95,11 +195,31 @@ impl Attrs {
             stroke_opacity,
             stroke_dasharray,
             stroke_dashoffset,
+            stroke_linejoin,
+            stroke_linecap,
+            stroke_miterlimit,
+            paint_order,
+            marker_start,
+            marker_mid,
+            marker_end,
             display,
+            visibility,
             filter,
+            color_interpolation_filters,
+            mask,
+            mix_blend_mode,
             font_size,
+            font_variant,
+            dominant_baseline,
+            alignment_baseline,
+            text_decoration,
+            text_decoration_style,
             direction,
+            white_space,
+            text_anchor,
             lang,
+            color,
+            system_language,
         })
     }
 }
@@ -108,7 +228,6 @@ impl Attrs {
 pub struct DashArray(pub Vec<Length>);
 impl Parse for DashArray {
     fn parse(s: &str) -> Result<DashArray, Error> {
-        dbg!(s);
         let lengths = Vec::<Length>::parse(s)?;
         let lengths = if lengths.len() % 2 == 0 {
             lengths
@@ -122,6 +241,16 @@ impl Parse for DashArray {
     }
 }
 
+/// `none` is kept as an empty dash array, which draws a solid stroke even if the parent is dashed.
+/// Without the attribute (or with `inherit`) the dash array of the parent is used.
+fn parse_dasharray_attr(s: &str) -> Result<Value<Option<DashArray>>, Error> {
+    match s {
+        "none" => Ok(Value::new(Some(DashArray(vec![])))),
+        "inherit" => Ok(Value::new(None)),
+        _ => Value::parse(s)
+    }
+}
+
 impl Parse for FillRule {
     fn parse(s: &str) -> Result<FillRule, Error> {
         Ok(match s {
@@ -132,6 +261,166 @@ impl Parse for FillRule {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PaintLayer {
+    Fill,
+    Stroke,
+    Markers,
+}
+
+/// `paint-order`: the layers of a shape, from the bottom to the top
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PaintOrder(pub [PaintLayer; 3]);
+impl PaintOrder {
+    /// fill, then stroke, then markers
+    pub fn normal() -> PaintOrder {
+        PaintOrder([PaintLayer::Fill, PaintLayer::Stroke, PaintLayer::Markers])
+    }
+    pub fn stroke_below_fill(&self) -> bool {
+        let position = |layer| self.0.iter().position(|&l| l == layer);
+        position(PaintLayer::Stroke) < position(PaintLayer::Fill)
+    }
+}
+impl Parse for PaintOrder {
+    /// the listed layers come first, the others follow in their normal order
+    fn parse(s: &str) -> Result<PaintOrder, Error> {
+        if s == "normal" {
+            return Ok(PaintOrder::normal());
+        }
+        let mut layers = Vec::with_capacity(3);
+        for word in s.split_whitespace() {
+            let layer = match word {
+                "fill" => PaintLayer::Fill,
+                "stroke" => PaintLayer::Stroke,
+                "markers" => PaintLayer::Markers,
+                val => return Err(Error::InvalidAttributeValue(val.into()))
+            };
+            if layers.contains(&layer) {
+                return Err(Error::InvalidAttributeValue(s.into()));
+            }
+            layers.push(layer);
+        }
+        if layers.is_empty() {
+            return Err(Error::InvalidAttributeValue(s.into()));
+        }
+        for &layer in PaintOrder::normal().0.iter() {
+            if !layers.contains(&layer) {
+                layers.push(layer);
+            }
+        }
+        Ok(PaintOrder([layers[0], layers[1], layers[2]]))
+    }
+}
+#[test]
+fn test_paint_order() {
+    use PaintLayer::*;
+    assert_eq!(PaintOrder::parse("normal").unwrap(), PaintOrder::normal());
+    assert_eq!(PaintOrder::parse("stroke").unwrap(), PaintOrder([Stroke, Fill, Markers]));
+    assert_eq!(PaintOrder::parse("markers fill").unwrap(), PaintOrder([Markers, Fill, Stroke]));
+    assert!(PaintOrder::parse("stroke").unwrap().stroke_below_fill());
+    assert!(!PaintOrder::parse("markers").unwrap().stroke_below_fill());
+    assert!(PaintOrder::parse("fill fill").is_err());
+    assert!(PaintOrder::parse("outline").is_err());
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StrokeLineJoin {
+    Miter,
+    MiterClip,
+    Round,
+    Bevel,
+    Arcs,
+}
+impl Parse for StrokeLineJoin {
+    fn parse(s: &str) -> Result<StrokeLineJoin, Error> {
+        Ok(match s {
+            "miter" => StrokeLineJoin::Miter,
+            "miter-clip" => StrokeLineJoin::MiterClip,
+            "round" => StrokeLineJoin::Round,
+            "bevel" => StrokeLineJoin::Bevel,
+            "arcs" => StrokeLineJoin::Arcs,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StrokeLineCap {
+    Butt,
+    Round,
+    Square,
+}
+impl Parse for StrokeLineCap {
+    fn parse(s: &str) -> Result<StrokeLineCap, Error> {
+        Ok(match s {
+            "butt" => StrokeLineCap::Butt,
+            "round" => StrokeLineCap::Round,
+            "square" => StrokeLineCap::Square,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+#[test]
+fn test_stroke_linejoin() {
+    assert_eq!(StrokeLineJoin::parse("miter-clip").unwrap(), StrokeLineJoin::MiterClip);
+    assert_eq!(StrokeLineJoin::parse("arcs").unwrap(), StrokeLineJoin::Arcs);
+    assert!(StrokeLineJoin::parse("mitre").is_err());
+}
+
+/// the blend modes of `mix-blend-mode` and `<feBlend mode>`
+impl Parse for BlendMode {
+    fn parse(s: &str) -> Result<BlendMode, Error> {
+        Ok(match s {
+            "normal" => BlendMode::SrcOver,
+            "multiply" => BlendMode::Multiply,
+            "screen" => BlendMode::Screen,
+            "overlay" => BlendMode::Overlay,
+            "darken" => BlendMode::Darken,
+            "lighten" => BlendMode::Lighten,
+            "color-dodge" => BlendMode::ColorDodge,
+            "color-burn" => BlendMode::ColorBurn,
+            "hard-light" => BlendMode::HardLight,
+            "soft-light" => BlendMode::SoftLight,
+            "difference" => BlendMode::Difference,
+            "exclusion" => BlendMode::Exclusion,
+            "hue" => BlendMode::Hue,
+            "saturation" => BlendMode::Saturation,
+            "color" => BlendMode::Color,
+            "luminosity" => BlendMode::Luminosity,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+#[test]
+fn test_blend_mode() {
+    assert_eq!(BlendMode::parse("difference").unwrap(), BlendMode::Difference);
+    assert_eq!(BlendMode::parse("luminosity").unwrap(), BlendMode::Luminosity);
+    assert_eq!(BlendMode::parse("normal").unwrap(), BlendMode::SrcOver);
+}
+
+/// values below 1 are invalid and ignored, so the inherited (initially 4) limit applies
+fn parse_miterlimit(s: &str) -> Result<Option<f32>, Error> {
+    match s {
+        "inherit" => Ok(None),
+        _ => {
+            let limit = f32::parse(s)?;
+            if limit >= 1.0 {
+                Ok(Some(limit))
+            } else {
+                warn!("ignoring stroke-miterlimit {} < 1", limit);
+                Ok(None)
+            }
+        }
+    }
+}
+#[test]
+fn test_miterlimit() {
+    assert_eq!(parse_miterlimit("10").unwrap(), Some(10.0));
+    assert_eq!(parse_miterlimit("1").unwrap(), Some(1.0));
+    assert_eq!(parse_miterlimit("0.5").unwrap(), None);
+    assert_eq!(parse_miterlimit("inherit").unwrap(), None);
+}
+
 fn parse_display(s: &str) -> Result<bool, Error> {
     match s {
         "none" => Ok(false),
@@ -140,6 +429,96 @@ fn parse_display(s: &str) -> Result<bool, Error> {
     }
 }
 
+/// working color space, used by `color-interpolation-filters`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ColorInterpolation {
+    /// the user agent may choose, we use sRGB
+    Auto,
+    SRGB,
+    LinearRGB,
+}
+impl Parse for ColorInterpolation {
+    fn parse(s: &str) -> Result<ColorInterpolation, Error> {
+        Ok(match s {
+            "auto" => ColorInterpolation::Auto,
+            "sRGB" => ColorInterpolation::SRGB,
+            "linearRGB" => ColorInterpolation::LinearRGB,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Visibility {
+    Visible,
+    Hidden,
+    Collapse,
+}
+impl Visibility {
+    /// `collapse` only differs from `hidden` for table rows and columns, which SVG does not have.
+    pub fn is_visible(self) -> bool {
+        match self {
+            Visibility::Visible => true,
+            Visibility::Hidden | Visibility::Collapse => false,
+        }
+    }
+}
+impl Parse for Visibility {
+    fn parse(s: &str) -> Result<Visibility, Error> {
+        Ok(match s {
+            "visible" => Visibility::Visible,
+            "hidden" => Visibility::Hidden,
+            "collapse" => Visibility::Collapse,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+/// `overflow`: whether content outside of the box of an element is shown
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Overflow {
+    Visible,
+    Hidden,
+    Clip,
+    Scroll,
+    Auto,
+}
+impl Overflow {
+    /// there is no scrolling, so `scroll` clips like `hidden`. `auto` shows the content like `visible`.
+    pub fn clips(self) -> bool {
+        match self {
+            Overflow::Hidden | Overflow::Clip | Overflow::Scroll => true,
+            Overflow::Visible | Overflow::Auto => false,
+        }
+    }
+}
+impl Parse for Overflow {
+    fn parse(s: &str) -> Result<Overflow, Error> {
+        Ok(match s {
+            "visible" => Overflow::Visible,
+            "hidden" => Overflow::Hidden,
+            "clip" => Overflow::Clip,
+            "scroll" => Overflow::Scroll,
+            "auto" => Overflow::Auto,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+#[test]
+fn test_visibility() {
+    assert!(!Visibility::parse("collapse").unwrap().is_visible());
+    assert!(!Visibility::parse("hidden").unwrap().is_visible());
+    assert!(Visibility::parse("visible").unwrap().is_visible());
+}
+
+#[test]
+fn test_style_important() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="r" width="1" height="1" style="visibility: hidden !important; visibility: collapse" visibility="visible"/>
+    </svg>"#).unwrap();
+    assert_eq!(item!(svg, "r", Rect).attrs.visibility, Some(Visibility::Hidden));
+}
+
 #[derive(Debug, Clone)]
 pub enum ClipPathAttr {
     None,
@@ -177,4 +556,188 @@ impl Parse for TextFlow {
             val => return Err(Error::InvalidAttributeValue(val.into()))
         })
     }
-}
\ No newline at end of file
+}
+
+/// the value of `font-size`, which (unlike other lengths) may be given in `rem`
+#[derive(Debug, Copy, Clone)]
+pub enum FontSize {
+    Length(LengthY),
+    /// multiple of the root font size
+    Rem(f32),
+}
+impl Parse for FontSize {
+    fn parse(s: &str) -> Result<FontSize, Error> {
+        match s.strip_suffix("rem") {
+            Some(num) => Ok(FontSize::Rem(num.trim().parse()?)),
+            None => Ok(FontSize::Length(LengthY::parse(s)?))
+        }
+    }
+}
+#[test]
+fn test_font_size() {
+    assert!(matches!(FontSize::parse("2rem").unwrap(), FontSize::Rem(n) if n == 2.0));
+    assert!(matches!(FontSize::parse("1.5em").unwrap(), FontSize::Length(LengthY(l)) if l.unit == LengthUnit::Em));
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontVariant {
+    Normal,
+    SmallCaps,
+}
+
+impl Parse for FontVariant {
+    fn parse(s: &str) -> Result<FontVariant, Error> {
+        Ok(match s {
+            "normal" => FontVariant::Normal,
+            "small-caps" => FontVariant::SmallCaps,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+/// how spaces and line breaks in text are handled
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WhiteSpace {
+    /// runs of white space collapse into a single space
+    Normal,
+    /// all white space is kept
+    Pre,
+    PreWrap,
+    /// like `normal`, without wrapping
+    Nowrap,
+}
+
+impl Parse for WhiteSpace {
+    fn parse(s: &str) -> Result<WhiteSpace, Error> {
+        Ok(match s {
+            "normal" => WhiteSpace::Normal,
+            "pre" => WhiteSpace::Pre,
+            "pre-wrap" => WhiteSpace::PreWrap,
+            "nowrap" => WhiteSpace::Nowrap,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+impl WhiteSpace {
+    pub fn collapses(self) -> bool {
+        matches!(self, WhiteSpace::Normal | WhiteSpace::Nowrap)
+    }
+}
+
+/// which point of a text chunk is placed at its position
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextAnchor {
+    Start,
+    Middle,
+    End,
+}
+
+impl Parse for TextAnchor {
+    fn parse(s: &str) -> Result<TextAnchor, Error> {
+        Ok(match s {
+            "start" => TextAnchor::Start,
+            "middle" => TextAnchor::Middle,
+            "end" => TextAnchor::End,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+impl TextAnchor {
+    /// the part of the advance of a chunk that lies before its position
+    pub fn fraction(self) -> f32 {
+        match self {
+            TextAnchor::Start => 0.0,
+            TextAnchor::Middle => 0.5,
+            TextAnchor::End => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DominantBaseline {
+    Auto,
+    Alphabetic,
+    Ideographic,
+    Middle,
+    Central,
+    Mathematical,
+    Hanging,
+    TextBeforeEdge,
+    TextAfterEdge,
+}
+
+impl Parse for DominantBaseline {
+    fn parse(s: &str) -> Result<DominantBaseline, Error> {
+        Ok(match s {
+            "auto" => DominantBaseline::Auto,
+            "alphabetic" => DominantBaseline::Alphabetic,
+            "ideographic" => DominantBaseline::Ideographic,
+            "middle" => DominantBaseline::Middle,
+            "central" => DominantBaseline::Central,
+            "mathematical" => DominantBaseline::Mathematical,
+            "hanging" => DominantBaseline::Hanging,
+            "text-before-edge" | "text-top" => DominantBaseline::TextBeforeEdge,
+            "text-after-edge" | "text-bottom" => DominantBaseline::TextAfterEdge,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+/// the lines drawn by `text-decoration`
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct TextDecoration {
+    pub underline: bool,
+    pub overline: bool,
+    pub line_through: bool,
+}
+impl TextDecoration {
+    pub fn is_none(&self) -> bool {
+        !(self.underline || self.overline || self.line_through)
+    }
+    /// decorations of ancestors are drawn on descendants as well, they can't be removed
+    pub fn union(self, other: TextDecoration) -> TextDecoration {
+        TextDecoration {
+            underline: self.underline || other.underline,
+            overline: self.overline || other.overline,
+            line_through: self.line_through || other.line_through,
+        }
+    }
+}
+impl Parse for TextDecoration {
+    fn parse(s: &str) -> Result<TextDecoration, Error> {
+        let mut decoration = TextDecoration::default();
+        for part in s.split_whitespace() {
+            match part {
+                "none" => {}
+                "underline" => decoration.underline = true,
+                "overline" => decoration.overline = true,
+                "line-through" => decoration.line_through = true,
+                // only `text-decoration-style` sets the style, a style in the shorthand is ignored
+                val if TextDecorationStyle::parse(val).is_ok() => {}
+                val => return Err(Error::InvalidAttributeValue(val.into()))
+            }
+        }
+        Ok(decoration)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextDecorationStyle {
+    Solid,
+    Double,
+    Dotted,
+    Dashed,
+    Wavy,
+}
+impl Parse for TextDecorationStyle {
+    fn parse(s: &str) -> Result<TextDecorationStyle, Error> {
+        Ok(match s {
+            "solid" => TextDecorationStyle::Solid,
+            "double" => TextDecorationStyle::Double,
+            "dotted" => TextDecorationStyle::Dotted,
+            "dashed" => TextDecorationStyle::Dashed,
+            "wavy" => TextDecorationStyle::Wavy,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
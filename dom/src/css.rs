@@ -0,0 +1,259 @@
+//! `<style>` stylesheets with simple selectors: type, `.class`, `#id` and `*`,
+//! combined into compound selectors and joined by descendant or child (`>`) combinators.
+
+use crate::prelude::*;
+use roxmltree::Document;
+use std::cell::RefCell;
+
+thread_local! {
+    /// the stylesheet of the document that is being parsed
+    static STYLE_SHEET: RefCell<StyleSheet> = RefCell::new(StyleSheet::default());
+}
+
+#[derive(Debug, Default)]
+pub struct StyleSheet {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    selector: Selector,
+    declarations: Vec<(String, String, bool)>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Default)]
+struct Compound {
+    tag: Option<String>,
+    ids: Vec<String>,
+    classes: Vec<String>,
+}
+
+/// `compounds[0]` is the element itself, `combinators[i]` joins `compounds[i]` to `compounds[i + 1]`
+#[derive(Debug)]
+struct Selector {
+    compounds: Vec<Compound>,
+    combinators: Vec<Combinator>,
+}
+
+impl StyleSheet {
+    /// the rules of all `<style>` elements in `doc`
+    pub fn from_document(doc: &Document) -> StyleSheet {
+        let mut sheet = StyleSheet::default();
+        for node in doc.descendants().filter(|n| n.is_element() && n.tag_name().name() == "style") {
+            match node.attribute("type") {
+                None | Some("text/css") => {}
+                Some(t) => {
+                    warn!("unsupported style sheet type {}", t);
+                    continue;
+                }
+            }
+            let text: String = node.children().filter_map(|n| n.text()).collect();
+            sheet.parse(&text);
+        }
+        sheet
+    }
+
+    fn parse(&mut self, text: &str) {
+        let text = strip_comments(text);
+        let mut rest = text.as_str();
+        while let Some(open) = rest.find('{') {
+            // the block ends at the matching brace, so the nested blocks of at-rules are skipped as a whole
+            let mut depth = 0;
+            let mut end = rest.len();
+            for (i, c) in rest[open ..].char_indices() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = open + i;
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            // statements like `@import ...;` end with a semicolon
+            let prelude = rest[.. open].rsplit(';').next().unwrap().trim();
+            let block = &rest[open + 1 .. end];
+            rest = rest.get(end + 1 ..).unwrap_or("");
+
+            if prelude.starts_with('@') {
+                warn!("unsupported at-rule {}", prelude);
+                continue;
+            }
+            for selector in prelude.split(',') {
+                match Selector::parse(selector) {
+                    Some(selector) => self.rules.push(Rule {
+                        selector,
+                        declarations: style_declarations(block).map(|(k, v, important)| (k.into(), v.into(), important)).collect(),
+                    }),
+                    None => warn!("unsupported selector {:?}", selector.trim()),
+                }
+            }
+        }
+    }
+
+    /// make this the stylesheet of the elements parsed in `f`
+    pub fn scoped<R>(self, f: impl FnOnce() -> R) -> R {
+        let outer = STYLE_SHEET.with(|sheet| sheet.replace(self));
+        let r = f();
+        STYLE_SHEET.with(|sheet| sheet.replace(outer));
+        r
+    }
+}
+
+/// the declarations of the stylesheet and the `style` attribute of `node`, in the order they have to be applied
+/// (after the presentation attributes): the matching rules by specificity and then document order, `style`,
+/// and finally the `!important` ones in the same order.
+pub fn cascade(node: &Node) -> Vec<(String, String)> {
+    STYLE_SHEET.with(|sheet| {
+        let sheet = sheet.borrow();
+        let mut rules: Vec<&Rule> = sheet.rules.iter().filter(|r| r.selector.matches(node)).collect();
+        rules.sort_by_key(|r| r.selector.specificity());
+        let style: Vec<_> = node.attribute("style").into_iter().flat_map(style_declarations).collect();
+
+        let mut list = vec![];
+        for &important in &[false, true] {
+            for rule in &rules {
+                list.extend(rule.declarations.iter().filter(|d| d.2 == important).map(|(k, v, _)| (k.clone(), v.clone())));
+            }
+            list.extend(style.iter().filter(|d| d.2 == important).map(|&(k, v, _)| (k.into(), v.into())));
+        }
+        list
+    })
+}
+
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[.. start]);
+        rest = match rest[start + 2 ..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2 ..],
+            None => ""
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+impl Selector {
+    fn parse(s: &str) -> Option<Selector> {
+        let mut compounds = vec![];
+        let mut combinators = vec![];
+        let mut child = false;
+        for token in s.replace('>', " > ").split_whitespace() {
+            if token == ">" {
+                if compounds.is_empty() || child {
+                    return None;
+                }
+                child = true;
+                continue;
+            }
+            if !compounds.is_empty() {
+                combinators.push(if child { Combinator::Child } else { Combinator::Descendant });
+            }
+            child = false;
+            compounds.push(Compound::parse(token)?);
+        }
+        if compounds.is_empty() || child {
+            return None;
+        }
+        compounds.reverse();
+        combinators.reverse();
+        Some(Selector { compounds, combinators })
+    }
+
+    /// (ids, classes, types)
+    fn specificity(&self) -> (usize, usize, usize) {
+        self.compounds.iter().fold((0, 0, 0), |(a, b, c), compound| {
+            (a + compound.ids.len(), b + compound.classes.len(), c + compound.tag.is_some() as usize)
+        })
+    }
+
+    fn matches(&self, node: &Node) -> bool {
+        self.compounds[0].matches(node) && self.matches_ancestors(1, node)
+    }
+
+    /// whether `compounds[i ..]` match the ancestors of `node`, which matched `compounds[i - 1]`
+    fn matches_ancestors(&self, i: usize, node: &Node) -> bool {
+        if i == self.compounds.len() {
+            return true;
+        }
+        let mut ancestors = node.ancestors().skip(1).filter(|n| n.is_element());
+        match self.combinators[i - 1] {
+            Combinator::Child => ancestors.next().map_or(false, |parent| {
+                self.compounds[i].matches(&parent) && self.matches_ancestors(i + 1, &parent)
+            }),
+            Combinator::Descendant => ancestors.any(|ancestor| {
+                self.compounds[i].matches(&ancestor) && self.matches_ancestors(i + 1, &ancestor)
+            }),
+        }
+    }
+}
+
+impl Compound {
+    fn parse(s: &str) -> Option<Compound> {
+        let mut compound = Compound::default();
+        let start = s.find(|c: char| c == '.' || c == '#').unwrap_or(s.len());
+        match &s[.. start] {
+            "" | "*" => {}
+            tag => compound.tag = Some(name(tag)?.into()),
+        }
+        let mut rest = &s[start ..];
+        while let Some(kind) = rest.chars().next() {
+            let end = rest[1 ..].find(|c: char| c == '.' || c == '#').map_or(rest.len(), |i| i + 1);
+            let value = name(&rest[1 .. end])?.into();
+            match kind {
+                '.' => compound.classes.push(value),
+                _ => compound.ids.push(value),
+            }
+            rest = &rest[end ..];
+        }
+        Some(compound)
+    }
+
+    fn matches(&self, node: &Node) -> bool {
+        self.tag.as_ref().map_or(true, |tag| node.tag_name().name() == tag)
+            && self.ids.iter().all(|id| node.attribute("id") == Some(id))
+            && self.classes.iter().all(|class| node.attribute("class").map_or(false, |c| c.split_whitespace().any(|c| c == class)))
+    }
+}
+
+/// `s` if it is a plain identifier, without pseudo-classes, attribute selectors and the like
+fn name(s: &str) -> Option<&str> {
+    if !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        Some(s)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_style_sheet_important() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <style>
+            /* overrides the inline style */
+            #r { fill: red !important }
+            rect.b { fill: green }
+            svg > .b { fill: yellow }
+        </style>
+        <rect id="r" width="1" height="1" style="fill:blue"/>
+        <rect id="s" class="a b" width="1" height="1" fill="blue"/>
+        <rect id="t" class="b" width="1" height="1" style="fill:blue"/>
+    </svg>"#).unwrap();
+    let fill = |id: &str| item!(svg, id, Rect).attrs.fill.value.0.clone();
+    let paint = |s: &str| Fill::parse(s).unwrap().0;
+    assert_eq!(fill("r"), paint("red"));
+    // the more specific rule wins over the presentation attribute, regardless of order
+    assert_eq!(fill("s"), paint("green"));
+    // normal rules lose to `style`
+    assert_eq!(fill("t"), paint("blue"));
+}
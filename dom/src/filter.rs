@@ -1,10 +1,20 @@
 use crate::prelude::*;
 use pathfinder_simd::default::F32x4;
+use pathfinder_content::effects::BlendMode;
 use svgtypes::NumberListParser;
 
+/// `<filter>`. `x`, `y`, `width` and `height` define the filter region, in `filterUnits`.
+/// The region defaults to the bounding box of the element, extended by 10% on each side.
+/// The lengths of the primitives (like `dx` or `stdDeviation`) are in `primitiveUnits`.
 #[derive(Debug)]
 pub struct TagFilter {
     pub filters: Vec<Filter>,
+    pub filter_units: Option<GradientUnits>,
+    pub primitive_units: Option<GradientUnits>,
+    pub x: Option<LengthX>,
+    pub y: Option<LengthY>,
+    pub width: Option<LengthX>,
+    pub height: Option<LengthY>,
     pub id: Option<String>,
 }
 impl Tag for TagFilter {
@@ -19,6 +29,15 @@ impl ParseNode for TagFilter {
             let filter = match elem.tag_name().name() {
                 "feGaussianBlur" => Filter::GaussianBlur(FeGaussianBlur::parse_node(&elem)?),
                 "feColorMatrix" => Filter::ColorMatrix(FeColorMatrix::parse_node(&elem)?),
+                "feBlend" => Filter::Blend(FeBlend::parse_node(&elem)?),
+                "feImage" => Filter::Image(FeImage::parse_node(&elem)?),
+                "feFlood" => Filter::Flood(FeFlood::parse_node(&elem)?),
+                "feTile" => Filter::Tile(FeTile::parse_node(&elem)?),
+                "feComposite" => Filter::Composite(FeComposite::parse_node(&elem)?),
+                "feComponentTransfer" => Filter::ComponentTransfer(FeComponentTransfer::parse_node(&elem)?),
+                "feDropShadow" => Filter::DropShadow(FeDropShadow::parse_node(&elem)?),
+                "feOffset" => Filter::Offset(FeOffset::parse_node(&elem)?),
+                "feMerge" => Filter::Merge(FeMerge::parse_node(&elem)?),
                 name => {
                     print!("unimplemented filter: {}", name);
                     continue;
@@ -28,8 +47,16 @@ impl ParseNode for TagFilter {
         }
         
         let id = node.attribute("id").map(|s| s.to_owned());
+        parse!(node => {
+            var filter_units ("filterUnits"): Option<GradientUnits>,
+            var primitive_units ("primitiveUnits"): Option<GradientUnits>,
+            var x: Option<LengthX>,
+            var y: Option<LengthY>,
+            var width: Option<LengthX>,
+            var height: Option<LengthY>,
+        });
 
-        Ok(TagFilter { id, filters })
+        Ok(TagFilter { id, filters, filter_units, primitive_units, x, y, width, height })
     }
 }
 
@@ -37,37 +64,164 @@ impl ParseNode for TagFilter {
 pub enum Filter {
     GaussianBlur(FeGaussianBlur),
     ColorMatrix(FeColorMatrix),
+    Blend(FeBlend),
+    Image(FeImage),
+    Flood(FeFlood),
+    Tile(FeTile),
+    Composite(FeComposite),
+    ComponentTransfer(FeComponentTransfer),
+    DropShadow(FeDropShadow),
+    Offset(FeOffset),
+    Merge(FeMerge),
 }
 
-#[derive(Debug)]
+/// `<feGaussianBlur>`. `stdDeviation` is one value for both axes or separate values for x and y.
+#[derive(Debug, Clone)]
 pub struct FeGaussianBlur {
-    pub std_deviation: f32
+    pub std_deviation: Vector2F,
+    pub input: Option<String>,
+    pub region: PrimitiveRegion,
+    pub result: Option<String>,
 }
 impl ParseNode for FeGaussianBlur {
     fn parse_node(node: &Node) -> Result<FeGaussianBlur, Error> {
-        let std_deviation = node.attribute("stdDeviation").map(f32::from_str).transpose()?.unwrap_or_default();
-        Ok(FeGaussianBlur { std_deviation })
+        let std_deviation = std_deviation(node)?.unwrap_or_default();
+        let input = node.attribute("in").map(|s| s.into());
+        let region = PrimitiveRegion::parse_node(node)?;
+        let result = node.attribute("result").map(|s| s.into());
+        Ok(FeGaussianBlur { std_deviation, input, region, result })
+    }
+}
+/// `stdDeviation`: one value for both axes, or one for x and one for y
+fn std_deviation(node: &Node) -> Result<Option<Vector2F>, Error> {
+    let val = match node.attribute("stdDeviation") {
+        Some(val) => val,
+        None => return Ok(None)
+    };
+    let values: Vec<f32> = NumberListParser::from(val).map(|r| r.map(|v| v as f32)).collect::<Result<Vec<_>, _>>()?;
+    match values[..] {
+        [d] if d >= 0.0 => Ok(Some(Vector2F::splat(d))),
+        [dx, dy] if dx >= 0.0 && dy >= 0.0 => Ok(Some(vec2f(dx, dy))),
+        _ => Err(Error::InvalidAttributeValue(val.into()))
+    }
+}
+
+/// `<feOffset>`: the input moved by `dx`/`dy`
+#[derive(Debug, Clone)]
+pub struct FeOffset {
+    pub dx: f32,
+    pub dy: f32,
+    pub input: Option<String>,
+    pub region: PrimitiveRegion,
+    pub result: Option<String>,
+}
+impl ParseNode for FeOffset {
+    fn parse_node(node: &Node) -> Result<FeOffset, Error> {
+        parse!(node => {
+            var dx: f32,
+            var dy: f32,
+        });
+        let input = node.attribute("in").map(|s| s.into());
+        let region = PrimitiveRegion::parse_node(node)?;
+        let result = node.attribute("result").map(|s| s.into());
+        Ok(FeOffset { dx, dy, input, region, result })
     }
 }
 
+/// `<feMerge>`: the inputs of the `<feMergeNode>` children, stacked in order (the first at the bottom)
+#[derive(Debug, Clone)]
+pub struct FeMerge {
+    pub inputs: Vec<Option<String>>,
+    pub region: PrimitiveRegion,
+    pub result: Option<String>,
+}
+impl ParseNode for FeMerge {
+    fn parse_node(node: &Node) -> Result<FeMerge, Error> {
+        let inputs = node.children()
+            .filter(|n| n.is_element() && n.tag_name().name() == "feMergeNode")
+            .map(|n| n.attribute("in").map(|s| s.into()))
+            .collect();
+        let region = PrimitiveRegion::parse_node(node)?;
+        let result = node.attribute("result").map(|s| s.into());
+        Ok(FeMerge { inputs, region, result })
+    }
+}
+
+/// `<feDropShadow>`: the alpha of the input in `flood-color`, offset by `dx`/`dy` and blurred, below the input
+#[derive(Debug, Clone)]
+pub struct FeDropShadow {
+    pub dx: f32,
+    pub dy: f32,
+    pub std_deviation: Vector2F,
+    pub color: Color,
+    pub opacity: f32,
+    pub input: Option<String>,
+    pub region: PrimitiveRegion,
+    pub result: Option<String>,
+}
+impl ParseNode for FeDropShadow {
+    fn parse_node(node: &Node) -> Result<FeDropShadow, Error> {
+        parse!(node => {
+            var dx: f32 = 2.0,
+            var dy: f32 = 2.0,
+            var flood_color ("flood-color"): Color = Color::black(),
+            var flood_opacity ("flood-opacity"): f32 = 1.0 => opacity,
+        });
+        let std_deviation = std_deviation(node)?.unwrap_or(Vector2F::splat(2.0));
+        let input = node.attribute("in").map(|s| s.into());
+        let region = PrimitiveRegion::parse_node(node)?;
+        let result = node.attribute("result").map(|s| s.into());
+        Ok(FeDropShadow { dx, dy, std_deviation, color: flood_color, opacity: flood_opacity, input, region, result })
+    }
+}
+
+/// `<feColorMatrix>`
+#[derive(Debug, Clone)]
+pub struct FeColorMatrix {
+    pub matrix: ColorMatrixType,
+    pub input: Option<String>,
+    pub region: PrimitiveRegion,
+    pub result: Option<String>,
+}
+impl ParseNode for FeColorMatrix {
+    fn parse_node(node: &Node) -> Result<FeColorMatrix, Error> {
+        let matrix = ColorMatrixType::parse_node(node)?;
+        let input = node.attribute("in").map(|s| s.into());
+        let region = PrimitiveRegion::parse_node(node)?;
+        let result = node.attribute("result").map(|s| s.into());
+        Ok(FeColorMatrix { matrix, input, region, result })
+    }
+}
+
+/// the `type` and `values` of a `<feColorMatrix>`
 #[derive(Debug, Copy, Clone)]
-pub enum FeColorMatrix {
+pub enum ColorMatrixType {
     Matrix([F32x4; 5]),
     HueRotate(f32),
     Saturate(f32),
     LuminanceToAlpha,
 }
-impl ParseNode for FeColorMatrix {
-    fn parse_node(node: &Node) -> Result<FeColorMatrix, Error> {
+/// Without `values`, each type defaults to the identity: the identity matrix, `saturate` 1 and `hueRotate` 0.
+impl ParseNode for ColorMatrixType {
+    fn parse_node(node: &Node) -> Result<ColorMatrixType, Error> {
         let typ = node.attribute("type").unwrap_or("matrix");
         match typ {
             "matrix" => {
-                let values = node.attribute("values").ok_or_else(|| Error::MissingAttribute("values".into()))?;
+                let values = match node.attribute("values") {
+                    Some(values) => values,
+                    None => return Ok(ColorMatrixType::Matrix([
+                        F32x4::new(1.0, 0.0, 0.0, 0.0),
+                        F32x4::new(0.0, 1.0, 0.0, 0.0),
+                        F32x4::new(0.0, 0.0, 1.0, 0.0),
+                        F32x4::new(0.0, 0.0, 0.0, 1.0),
+                        F32x4::splat(0.0),
+                    ]))
+                };
                 let values: Vec<f32> = NumberListParser::from(values).map(|r| r.map(|v| v as f32)).collect::<Result<Vec<_>, _>>()?;
                 if values.len() != 20 {
                     return Err(Error::InvalidAttributeValue(format!("expected 20 values, got {}", values.len())));
                 }
-                Ok(FeColorMatrix::Matrix([
+                Ok(ColorMatrixType::Matrix([
                     F32x4::new(values[0], values[5], values[10], values[15]),
                     F32x4::new(values[1], values[6], values[11], values[16]),
                     F32x4::new(values[2], values[7], values[12], values[17]),
@@ -76,17 +230,442 @@ impl ParseNode for FeColorMatrix {
                 ]))
             }
             "saturate"=> {
-                let values = node.attribute("values").ok_or_else(|| Error::MissingAttribute("values".into()))?;
-                let value: f32 = values.parse()?;
-                Ok(FeColorMatrix::Saturate(value))
+                let value: f32 = node.attribute("values").map(str::parse).transpose()?.unwrap_or(1.0);
+                Ok(ColorMatrixType::Saturate(value))
             }
             "hueRotate" => {
-                let values = node.attribute("values").ok_or_else(|| Error::MissingAttribute("values".into()))?;
-                let deg: f32 = values.parse()?;
-                Ok(FeColorMatrix::HueRotate(deg2rad(deg)))
+                let deg: f32 = node.attribute("values").map(str::parse).transpose()?.unwrap_or(0.0);
+                Ok(ColorMatrixType::HueRotate(deg2rad(deg)))
             },
-            "luminanceToAlpha" => Ok(FeColorMatrix::LuminanceToAlpha),
+            "luminanceToAlpha" => Ok(ColorMatrixType::LuminanceToAlpha),
             _ => Err(Error::InvalidAttributeValue("type".into()))
         }
     }
 }
+
+/// `<feBlend>`: `in` is blended onto `in2` with `mode`
+#[derive(Debug, Clone)]
+pub struct FeBlend {
+    pub mode: BlendMode,
+    pub input: Option<String>,
+    pub input2: Option<String>,
+    pub region: PrimitiveRegion,
+    pub result: Option<String>,
+}
+impl ParseNode for FeBlend {
+    fn parse_node(node: &Node) -> Result<FeBlend, Error> {
+        let mode = parse_attr_or(node, "mode", BlendMode::SrcOver)?;
+        let input = node.attribute("in").map(|s| s.into());
+        let input2 = node.attribute("in2").map(|s| s.into());
+        let region = PrimitiveRegion::parse_node(node)?;
+        let result = node.attribute("result").map(|s| s.into());
+        Ok(FeBlend { mode, input, input2, region, result })
+    }
+}
+
+/// `<feImage>`. Only references to elements of the same document are supported.
+#[derive(Debug, Clone)]
+pub struct FeImage {
+    pub href: Option<String>,
+    pub x: Option<LengthX>,
+    pub y: Option<LengthY>,
+}
+impl ParseNode for FeImage {
+    fn parse_node(node: &Node) -> Result<FeImage, Error> {
+        parse!(node => {
+            var x: Option<LengthX>,
+            var y: Option<LengthY>,
+        });
+        let href = href(node);
+        Ok(FeImage { href, x, y })
+    }
+}
+/// `x`, `y`, `width` and `height` of a primitive (the primitive subregion), in the user space of the filtered element.
+/// Missing values default to those of the inputs, or of the filter region for primitives without inputs.
+#[derive(Debug, Clone, Default)]
+pub struct PrimitiveRegion {
+    pub x: Option<LengthX>,
+    pub y: Option<LengthY>,
+    pub width: Option<LengthX>,
+    pub height: Option<LengthY>,
+}
+impl ParseNode for PrimitiveRegion {
+    fn parse_node(node: &Node) -> Result<PrimitiveRegion, Error> {
+        parse!(node => {
+            var x: Option<LengthX>,
+            var y: Option<LengthY>,
+            var width: Option<LengthX>,
+            var height: Option<LengthY>,
+        });
+        Ok(PrimitiveRegion { x, y, width, height })
+    }
+}
+impl PrimitiveRegion {
+    /// none of the values is given, so the region is that of the inputs
+    pub fn is_unset(&self) -> bool {
+        self.x.is_none() && self.y.is_none() && self.width.is_none() && self.height.is_none()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FeFlood {
+    pub color: Color,
+    pub opacity: f32,
+    pub region: PrimitiveRegion,
+    pub result: Option<String>,
+}
+impl ParseNode for FeFlood {
+    fn parse_node(node: &Node) -> Result<FeFlood, Error> {
+        parse!(node => {
+            var flood_color ("flood-color"): Color = Color::black(),
+            var flood_opacity ("flood-opacity"): f32 = 1.0 => opacity,
+        });
+        let region = PrimitiveRegion::parse_node(node)?;
+        let result = node.attribute("result").map(|s| s.into());
+        Ok(FeFlood { color: flood_color, opacity: flood_opacity, region, result })
+    }
+}
+
+/// `<feTile>`: fills its subregion with copies of the subregion of the input
+#[derive(Debug, Clone)]
+pub struct FeTile {
+    pub input: Option<String>,
+    pub region: PrimitiveRegion,
+    pub result: Option<String>,
+}
+impl ParseNode for FeTile {
+    fn parse_node(node: &Node) -> Result<FeTile, Error> {
+        let input = node.attribute("in").map(|s| s.into());
+        let region = PrimitiveRegion::parse_node(node)?;
+        let result = node.attribute("result").map(|s| s.into());
+        Ok(FeTile { input, region, result })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CompositeOperator {
+    Over,
+    In,
+    Out,
+    Atop,
+    Xor,
+    Arithmetic { k1: f32, k2: f32, k3: f32, k4: f32 },
+}
+
+/// `<feComposite>`: `in` is composited onto `in2` with a Porter-Duff operator
+#[derive(Debug, Clone)]
+pub struct FeComposite {
+    pub operator: CompositeOperator,
+    pub input: Option<String>,
+    pub input2: Option<String>,
+    pub region: PrimitiveRegion,
+    pub result: Option<String>,
+}
+impl ParseNode for FeComposite {
+    fn parse_node(node: &Node) -> Result<FeComposite, Error> {
+        parse!(node => {
+            var k1: f32,
+            var k2: f32,
+            var k3: f32,
+            var k4: f32,
+        });
+        let operator = match node.attribute("operator").unwrap_or("over") {
+            "over" => CompositeOperator::Over,
+            "in" => CompositeOperator::In,
+            "out" => CompositeOperator::Out,
+            "atop" => CompositeOperator::Atop,
+            "xor" => CompositeOperator::Xor,
+            "arithmetic" => CompositeOperator::Arithmetic { k1, k2, k3, k4 },
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        };
+        let input = node.attribute("in").map(|s| s.into());
+        let input2 = node.attribute("in2").map(|s| s.into());
+        let region = PrimitiveRegion::parse_node(node)?;
+        let result = node.attribute("result").map(|s| s.into());
+        Ok(FeComposite { operator, input, input2, region, result })
+    }
+}
+
+/// `<feComponentTransfer>`. The functions are defined on colors that are not premultiplied.
+#[derive(Debug, Clone)]
+pub struct FeComponentTransfer {
+    pub func_r: TransferFunction,
+    pub func_g: TransferFunction,
+    pub func_b: TransferFunction,
+    pub func_a: TransferFunction,
+    pub input: Option<String>,
+    pub region: PrimitiveRegion,
+    pub result: Option<String>,
+}
+impl ParseNode for FeComponentTransfer {
+    fn parse_node(node: &Node) -> Result<FeComponentTransfer, Error> {
+        let mut funcs = [TransferFunction::Identity, TransferFunction::Identity, TransferFunction::Identity, TransferFunction::Identity];
+        for elem in node.children().filter(|n| n.is_element()) {
+            let idx = match elem.tag_name().name() {
+                "feFuncR" => 0,
+                "feFuncG" => 1,
+                "feFuncB" => 2,
+                "feFuncA" => 3,
+                _ => continue
+            };
+            funcs[idx] = TransferFunction::parse_node(&elem)?;
+        }
+        let [func_r, func_g, func_b, func_a] = funcs;
+        let input = node.attribute("in").map(|s| s.into());
+        let region = PrimitiveRegion::parse_node(node)?;
+        let result = node.attribute("result").map(|s| s.into());
+        Ok(FeComponentTransfer { func_r, func_g, func_b, func_a, input, region, result })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferFunction {
+    Identity,
+    Table(Vec<f32>),
+    Discrete(Vec<f32>),
+    Linear { slope: f32, intercept: f32 },
+    Gamma { amplitude: f32, exponent: f32, offset: f32 },
+}
+impl TransferFunction {
+    /// apply the function to a component in 0 ..= 1
+    pub fn apply(&self, c: f32) -> f32 {
+        let c = c.max(0.0).min(1.0);
+        let out = match *self {
+            TransferFunction::Identity => c,
+            TransferFunction::Table(ref values) => match values.len() {
+                0 => c,
+                1 => values[0],
+                n => {
+                    let pos = c * (n - 1) as f32;
+                    let k = (pos as usize).min(n - 2);
+                    values[k] + (pos - k as f32) * (values[k + 1] - values[k])
+                }
+            },
+            TransferFunction::Discrete(ref values) => match values.len() {
+                0 => c,
+                n => values[((c * n as f32) as usize).min(n - 1)],
+            },
+            TransferFunction::Linear { slope, intercept } => slope * c + intercept,
+            TransferFunction::Gamma { amplitude, exponent, offset } => amplitude * c.powf(exponent) + offset,
+        };
+        out.max(0.0).min(1.0)
+    }
+}
+impl ParseNode for TransferFunction {
+    fn parse_node(node: &Node) -> Result<TransferFunction, Error> {
+        parse!(node => {
+            var slope: f32 = 1.0,
+            var intercept: f32,
+            var amplitude: f32 = 1.0,
+            var exponent: f32 = 1.0,
+            var offset: f32,
+        });
+        let table_values = || -> Result<Vec<f32>, Error> {
+            match node.attribute("tableValues") {
+                Some(values) => Ok(NumberListParser::from(values).map(|r| r.map(|v| v as f32)).collect::<Result<Vec<_>, _>>()?),
+                None => Ok(vec![])
+            }
+        };
+        Ok(match node.attribute("type").unwrap_or("identity") {
+            "identity" => TransferFunction::Identity,
+            "table" => TransferFunction::Table(table_values()?),
+            "discrete" => TransferFunction::Discrete(table_values()?),
+            "linear" => TransferFunction::Linear { slope, intercept },
+            "gamma" => TransferFunction::Gamma { amplitude, exponent, offset },
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+#[test]
+fn test_fe_image() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+        <filter id="f"><feImage xlink:href="#shape" x="5"/></filter>
+        <rect id="shape" width="10" height="10" transform="rotate(45)"/>
+    </svg>"#).unwrap();
+    match item!(svg, "f", Filter).filters[0] {
+        Filter::Image(ref image) => {
+            assert_eq!(image.href.as_deref(), Some("#shape"));
+            assert!(image.x.is_some() && image.y.is_none());
+        }
+        ref f => panic!("expected feImage, got {:?}", f)
+    }
+}
+
+#[test]
+fn test_flood_tile_composite() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <filter id="f">
+            <feFlood flood-color="red" height="2" result="stripe"/>
+            <feTile in="stripe" result="stripes"/>
+            <feComposite in="stripes" in2="SourceAlpha" operator="in"/>
+        </filter>
+    </svg>"#).unwrap();
+    let filter = item!(svg, "f", Filter);
+    match filter.filters[..] {
+        [Filter::Flood(ref flood), Filter::Tile(ref tile), Filter::Composite(ref composite)] => {
+            assert_eq!(flood.color, Color::from_srgb_u8(255, 0, 0));
+            assert!(flood.region.height.is_some() && flood.region.width.is_none());
+            assert_eq!(tile.input.as_deref(), Some("stripe"));
+            assert_eq!(composite.operator, CompositeOperator::In);
+            assert_eq!(composite.input2.as_deref(), Some("SourceAlpha"));
+        }
+        ref filters => panic!("unexpected primitives {:?}", filters)
+    }
+}
+
+#[test]
+fn test_component_transfer() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <filter id="f">
+            <feComponentTransfer>
+                <feFuncR type="gamma" exponent="2"/>
+                <feFuncA type="table" tableValues="0 0.5"/>
+            </feComponentTransfer>
+        </filter>
+    </svg>"#).unwrap();
+    let transfer = match item!(svg, "f", Filter).filters[0] {
+        Filter::ComponentTransfer(ref transfer) => transfer.clone(),
+        ref f => panic!("expected feComponentTransfer, got {:?}", f)
+    };
+    assert_eq!(transfer.func_r, TransferFunction::Gamma { amplitude: 1.0, exponent: 2.0, offset: 0.0 });
+    assert_eq!(transfer.func_g, TransferFunction::Identity);
+    assert_eq!(transfer.func_r.apply(0.5), 0.25);
+    assert_eq!(transfer.func_a.apply(0.5), 0.25);
+}
+
+#[test]
+fn test_color_matrix_defaults() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <filter id="f">
+            <feColorMatrix type="saturate"/>
+            <feColorMatrix type="hueRotate" values="90"/>
+            <feColorMatrix/>
+        </filter>
+    </svg>"#).unwrap();
+    let filter = item!(svg, "f", Filter);
+    match filter.filters[..] {
+        [Filter::ColorMatrix(FeColorMatrix { matrix: ColorMatrixType::Saturate(s), .. }), Filter::ColorMatrix(FeColorMatrix { matrix: ColorMatrixType::HueRotate(r), .. }), Filter::ColorMatrix(FeColorMatrix { matrix: ColorMatrixType::Matrix(m), .. })] => {
+            assert_eq!(s, 1.0);
+            assert_eq!(r, deg2rad(90.));
+            assert_eq!(m[0], F32x4::new(1.0, 0.0, 0.0, 0.0));
+            assert_eq!(m[4], F32x4::splat(0.0));
+        }
+        ref filters => panic!("unexpected primitives {:?}", filters)
+    }
+}
+
+#[test]
+fn test_merge() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <filter id="f">
+            <feFlood flood-color="blue" result="background"/>
+            <feMerge><feMergeNode in="background"/><feMergeNode/><feMergeNode in="SourceGraphic"/></feMerge>
+        </filter>
+    </svg>"#).unwrap();
+    match item!(svg, "f", Filter).filters[1] {
+        Filter::Merge(ref merge) => {
+            assert_eq!(merge.inputs, [Some("background".into()), None, Some("SourceGraphic".into())]);
+        }
+        ref f => panic!("expected feMerge, got {:?}", f)
+    }
+}
+
+#[test]
+fn test_blur_color_matrix_inputs() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <filter id="f">
+            <feGaussianBlur in="SourceAlpha" stdDeviation="2" result="blur"/>
+            <feColorMatrix in="blur" type="saturate" values="0" x="5" result="gray"/>
+        </filter>
+    </svg>"#).unwrap();
+    let filter = item!(svg, "f", Filter);
+    match filter.filters[..] {
+        [Filter::GaussianBlur(ref blur), Filter::ColorMatrix(ref matrix)] => {
+            assert_eq!(blur.input.as_deref(), Some("SourceAlpha"));
+            assert_eq!(blur.result.as_deref(), Some("blur"));
+            assert!(blur.region.is_unset());
+            assert_eq!(matrix.input.as_deref(), Some("blur"));
+            assert_eq!(matrix.result.as_deref(), Some("gray"));
+            assert!(matrix.region.x.is_some() && matrix.region.y.is_none());
+        }
+        ref filters => panic!("unexpected primitives {:?}", filters)
+    }
+}
+
+#[test]
+fn test_blend_composite() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <filter id="f">
+            <feBlend mode="screen" in2="BackgroundImage" result="screened"/>
+            <feComposite operator="arithmetic" in="screened" k1="0.5" k3="-1" k4="0.25"/>
+        </filter>
+    </svg>"#).unwrap();
+    let filter = item!(svg, "f", Filter);
+    match filter.filters[0] {
+        Filter::Blend(ref blend) => {
+            assert_eq!(blend.mode, BlendMode::Screen);
+            assert_eq!(blend.result.as_deref(), Some("screened"));
+        }
+        ref f => panic!("expected feBlend, got {:?}", f)
+    }
+    match filter.filters[1] {
+        Filter::Composite(ref composite) => {
+            assert_eq!(composite.operator, CompositeOperator::Arithmetic { k1: 0.5, k2: 0.0, k3: -1.0, k4: 0.25 });
+            assert_eq!(composite.input.as_deref(), Some("screened"));
+        }
+        ref f => panic!("expected feComposite, got {:?}", f)
+    }
+}
+
+#[test]
+fn test_offset() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <filter id="f" primitiveUnits="objectBoundingBox"><feOffset dx="0.5" in="SourceAlpha" result="moved"/></filter>
+    </svg>"#).unwrap();
+    let filter = item!(svg, "f", Filter);
+    assert_eq!(filter.primitive_units, Some(GradientUnits::ObjectBoundingBox));
+    match filter.filters[0] {
+        Filter::Offset(ref offset) => {
+            assert_eq!((offset.dx, offset.dy), (0.5, 0.0));
+            assert_eq!(offset.input.as_deref(), Some("SourceAlpha"));
+            assert_eq!(offset.result.as_deref(), Some("moved"));
+        }
+        ref f => panic!("expected feOffset, got {:?}", f)
+    }
+}
+
+#[test]
+fn test_drop_shadow() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <filter id="f"><feDropShadow dx="3" stdDeviation="1 0.5" flood-color="navy" flood-opacity="0.5"/></filter>
+    </svg>"#).unwrap();
+    match item!(svg, "f", Filter).filters[0] {
+        Filter::DropShadow(ref shadow) => {
+            assert_eq!((shadow.dx, shadow.dy), (3.0, 2.0));
+            assert_eq!(shadow.std_deviation, vec2f(1.0, 0.5));
+            assert_eq!(shadow.color, Color::from_srgb_u8(0, 0, 128));
+            assert_eq!(shadow.opacity, 0.5);
+        }
+        ref f => panic!("expected feDropShadow, got {:?}", f)
+    }
+}
+
+#[test]
+fn test_gaussian_blur() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <filter id="f" filterUnits="userSpaceOnUse" x="0" y="0" width="20" height="10">
+            <feGaussianBlur stdDeviation="2 0.5"/>
+        </filter>
+        <filter id="g"><feGaussianBlur stdDeviation="3"/></filter>
+    </svg>"#).unwrap();
+    let filter = |id| {
+        let filter = item!(svg, id, Filter);
+        match filter.filters[0] {
+            Filter::GaussianBlur(ref blur) => (filter.filter_units, blur.std_deviation),
+            ref f => panic!("expected feGaussianBlur, got {:?}", f)
+        }
+    };
+    assert_eq!(filter("f"), (Some(GradientUnits::UserSpaceOnUse), vec2f(2.0, 0.5)));
+    assert_eq!(filter("g"), (None, Vector2F::splat(3.0)));
+}
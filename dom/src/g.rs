@@ -7,6 +7,9 @@ pub struct TagG {
     pub items: Vec<Arc<Item>>,
     pub attrs: Attrs,
     pub id: Option<String>,
+
+    /// `inkscape:label`, used by editors to name layers
+    pub label: Option<String>,
 }
 impl Tag for TagG {
     fn id(&self) -> Option<&str> {
@@ -21,7 +24,77 @@ impl ParseNode for TagG {
         let attrs = Attrs::parse(node)?;
         let items = parse_node_list(node.children())?;
         let id = node.attribute("id").map(|s| s.into());
-        Ok(TagG { items, attrs, id })
+        let label = node.attribute((INKSCAPE_NS, "label")).map(|s| s.into());
+        Ok(TagG { items, attrs, id, label })
+    }
+}
+
+const INKSCAPE_NS: &str = "http://www.inkscape.org/namespaces/inkscape";
+
+#[test]
+fn test_layer_label() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape">
+        <g id="layer1" inkscape:label="background" inkscape:groupmode="layer"/>
+    </svg>"#).unwrap();
+    assert_eq!(item!(svg, "layer1", G).label.as_deref(), Some("background"));
+}
+
+/// `<a>` renders like a group, but keeps the link target around.
+#[derive(Debug)]
+pub struct TagA {
+    pub items: Vec<Arc<Item>>,
+    pub attrs: Attrs,
+    pub id: Option<String>,
+    pub href: Option<String>,
+}
+impl Tag for TagA {
+    fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|s| s.as_str())
+    }
+    fn children(&self) -> &[Arc<Item>] {
+        &*self.items
+    }
+}
+impl ParseNode for TagA {
+    fn parse_node(node: &Node) -> Result<TagA, Error> {
+        let attrs = Attrs::parse(node)?;
+        let items = parse_node_list(node.children())?;
+        let id = node.attribute("id").map(|s| s.into());
+        let href = href(node);
+        Ok(TagA { items, attrs, id, href })
+    }
+}
+#[test]
+fn test_anchor() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+        <a id="link" xlink:href="https://example.com"><rect width="10" height="10"/></a>
+    </svg>"#).unwrap();
+    let a = item!(svg, "link", A);
+    assert_eq!(a.href.as_deref(), Some("https://example.com"));
+    assert_eq!(a.items.len(), 1);
+}
+
+/// `<switch>` renders only the first direct child whose conditions (`systemLanguage`) are met.
+#[derive(Debug)]
+pub struct TagSwitch {
+    pub items: Vec<Arc<Item>>,
+    pub attrs: Attrs,
+    pub id: Option<String>,
+}
+impl Tag for TagSwitch {
+    fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|s| s.as_str())
+    }
+    fn children(&self) -> &[Arc<Item>] {
+        &*self.items
+    }
+}
+impl ParseNode for TagSwitch {
+    fn parse_node(node: &Node) -> Result<TagSwitch, Error> {
+        let attrs = Attrs::parse(node)?;
+        let items = parse_node_list(node.children())?;
+        let id = node.attribute("id").map(|s| s.into());
+        Ok(TagSwitch { items, attrs, id })
     }
 }
 
@@ -31,6 +104,7 @@ pub struct TagSymbol {
     pub attrs: Attrs,
     pub id: Option<String>,
     pub view_box: Option<Rect>,
+    pub preserve_aspect_ratio: Option<AspectRatio>,
 }
 impl Tag for TagSymbol {
     fn id(&self) -> Option<&str> {
@@ -46,8 +120,9 @@ impl ParseNode for TagSymbol {
         let items = parse_node_list(node.children())?;
         let id = node.attribute("id").map(|s| s.into());
         let view_box = node.attribute("viewBox").map(Rect::parse).transpose()?;
+        let preserve_aspect_ratio = node.attribute("preserveAspectRatio").map(AspectRatio::parse).transpose()?;
 
-        Ok(TagSymbol { items, attrs, id,view_box })
+        Ok(TagSymbol { items, attrs, id, view_box, preserve_aspect_ratio })
     }
 }
 
@@ -10,6 +10,8 @@ pub struct TagLinearGradient {
     pub from: (Option<LengthX>, Option<LengthY>),
     pub to: (Option<LengthX>, Option<LengthY>),
     pub gradient_transform: Option<Transform2F>,
+    pub gradient_units: Option<GradientUnits>,
+    pub spread_method: Option<SpreadMethod>,
     pub stops: Vec<TagStop>,
     pub id: Option<String>,
     pub href: Option<String>,
@@ -21,12 +23,72 @@ pub struct TagRadialGradient {
     pub focus: (Option<LengthX>, Option<LengthY>),
     pub radius: Option<Length>,
     pub gradient_transform: Option<Transform2F>,
+    pub gradient_units: Option<GradientUnits>,
+    pub spread_method: Option<SpreadMethod>,
     pub stops: Vec<TagStop>,
     pub id: Option<String>,
     pub href: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GradientUnits {
+    UserSpaceOnUse,
+    ObjectBoundingBox,
+}
+impl Parse for GradientUnits {
+    fn parse(s: &str) -> Result<GradientUnits, Error> {
+        Ok(match s {
+            "userSpaceOnUse" => GradientUnits::UserSpaceOnUse,
+            "objectBoundingBox" => GradientUnits::ObjectBoundingBox,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+#[test]
+fn test_gradient_units() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <linearGradient id="a" gradientUnits="userSpaceOnUse" gradientTransform="rotate(45)"/>
+        <linearGradient id="b"/>
+    </svg>"#).unwrap();
+    let g = item!(svg, "a", LinearGradient);
+    assert_eq!(g.gradient_units, Some(GradientUnits::UserSpaceOnUse));
+    assert!(g.gradient_transform.is_some());
+    assert_eq!(item!(svg, "b", LinearGradient).gradient_units, None);
+}
+
+/// what is drawn outside of the gradient vector
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SpreadMethod {
+    /// the colors of the first and last stop are extended
+    Pad,
+    /// the gradient is repeated, every other time in reverse
+    Reflect,
+    /// the gradient is repeated
+    Repeat,
+}
+impl Parse for SpreadMethod {
+    fn parse(s: &str) -> Result<SpreadMethod, Error> {
+        Ok(match s {
+            "pad" => SpreadMethod::Pad,
+            "reflect" => SpreadMethod::Reflect,
+            "repeat" => SpreadMethod::Repeat,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+#[test]
+fn test_spread_method() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <linearGradient id="a" spreadMethod="reflect"/>
+        <radialGradient id="b" spreadMethod="repeat"/>
+        <linearGradient id="c"/>
+    </svg>"#).unwrap();
+    assert_eq!(item!(svg, "a", LinearGradient).spread_method, Some(SpreadMethod::Reflect));
+    assert_eq!(item!(svg, "b", RadialGradient).spread_method, Some(SpreadMethod::Repeat));
+    assert_eq!(item!(svg, "c", LinearGradient).spread_method, None);
+}
+
+#[derive(Debug, Clone)]
 pub struct TagStop {
     pub offset: f32,
     pub color: Color,
@@ -50,23 +112,21 @@ impl ParseNode for TagLinearGradient {
             var y1: Option<LengthY>,
             var x2: Option<LengthX>,
             var y2: Option<LengthY>,
+            var gradient_units ("gradientUnits"): Option<GradientUnits>,
+            var spread_method ("spreadMethod"): Option<SpreadMethod>,
             var id,
         });
         let gradient_transform = node.attribute("gradientTransform").map(transform_list).transpose()?;
         let href = href(node);
     
-        let mut stops = Vec::new();
-        for elem in node.children().filter(|n| n.is_element()) {
-            match elem.tag_name().name() {
-                "stop" => stops.push(TagStop::parse_node(&elem)?),
-                _ => {}
-            }
-        }
+        let stops = parse_stops(node)?;
     
         Ok(TagLinearGradient {
             from: (x1, y1),
             to: (x2, y2),
             gradient_transform,
+            gradient_units,
+            spread_method,
             stops,
             id,
             href
@@ -81,24 +141,22 @@ impl ParseNode for TagRadialGradient {
             var fx: Option<LengthX>,
             var fy: Option<LengthY>,
             var r: Option<Length>,
+            var gradient_units ("gradientUnits"): Option<GradientUnits>,
+            var spread_method ("spreadMethod"): Option<SpreadMethod>,
             var id,
         });
         let gradient_transform = node.attribute("gradientTransform").map(transform_list).transpose()?;
         let href = href(node);
     
-        let mut stops = Vec::new();
-        for elem in node.children().filter(|n| n.is_element()) {
-            match elem.tag_name().name() {
-                "stop" => stops.push(TagStop::parse_node(&elem)?),
-                _ => {}
-            }
-        }
+        let stops = parse_stops(node)?;
     
         Ok(TagRadialGradient {
             center: (cx, cy),
             focus: (fx, fy),
             radius: r,
             gradient_transform,
+            gradient_units,
+            spread_method,
             stops,
             id,
             href,
@@ -107,20 +165,19 @@ impl ParseNode for TagRadialGradient {
 }
 
 impl TagStop {
+    /// `stop-color` defaults to black and `stop-opacity` to 1
     fn new() -> TagStop {
         TagStop { offset: 0.0, color: Color::black(), opacity: 1.0 }
     }
 
-    fn apply<'a>(&mut self, key: &'a str, val: &'a str) -> Result<(), Error> {
-        match key {
-            "offset" => self.offset = number_or_percent(val)?,
-            "stop-opacity" => self.opacity = opacity(val)?,
-            "stop-color" => self.color = Color::from_str(val)?,
-            "style" => {
-                for (key, val) in style_list(val) {
-                    self.apply(key, val)?;
-                }
-            }
+    /// `inherited` holds the values of the parent gradient element, used for `inherit`
+    fn apply<'a>(&mut self, key: &'a str, val: &'a str, inherited: &TagStop) -> Result<(), Error> {
+        match (key, val) {
+            ("offset", _) => self.offset = number_or_percent(val)?,
+            ("stop-opacity", "inherit") => self.opacity = inherited.opacity,
+            ("stop-opacity", _) => self.opacity = opacity(val)?,
+            ("stop-color", "inherit") => self.color = inherited.color,
+            ("stop-color", _) => self.color = Color::from_str(val)?,
             _ => {}
         }
         Ok(())
@@ -131,19 +188,55 @@ impl TagStop {
         let alpha = (opacity * self.opacity * 255.) as u8;
         ColorU::new(red, green, blue, alpha)
     }
-}
-impl ParseNode for TagStop {
-    fn parse_node(node: &Node) -> Result<TagStop, Error> {
+
+    fn parse_with_parent(node: &Node, inherited: &TagStop) -> TagStop {
         let mut stop = TagStop::new();
 
         for attr in node.attributes() {
-            stop.apply(attr.name(), attr.value());
+            stop.apply(attr.name(), attr.value(), inherited);
+        }
+        for (key, val) in crate::css::cascade(node) {
+            stop.apply(&key, &val, inherited);
         }
 
-        Ok(stop)
+        stop
+    }
+}
+impl ParseNode for TagStop {
+    fn parse_node(node: &Node) -> Result<TagStop, Error> {
+        Ok(TagStop::parse_with_parent(node, &TagStop::new()))
     }
 }
 
+/// parse the `<stop>` children of the gradient `node`
+fn parse_stops(node: &Node) -> Result<Vec<TagStop>, Error> {
+    // stop-color and stop-opacity on the gradient itself, which stops can `inherit`
+    let inherited = TagStop::parse_with_parent(node, &TagStop::new());
+
+    let mut stops = Vec::new();
+    for elem in node.children().filter(|n| n.is_element()) {
+        match elem.tag_name().name() {
+            "stop" => stops.push(TagStop::parse_with_parent(&elem, &inherited)),
+            _ => {}
+        }
+    }
+    Ok(stops)
+}
+#[test]
+fn test_stop_inherit() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <linearGradient id="g" stop-color="red" stop-opacity="0.5">
+            <stop offset="0"/>
+            <stop offset="1" stop-color="inherit" style="stop-opacity: inherit"/>
+        </linearGradient>
+    </svg>"#).unwrap();
+    let g = item!(svg, "g", LinearGradient);
+    assert_eq!(g.stops[0].color, Color::black());
+    assert_eq!(g.stops[0].opacity, 1.0);
+    assert_eq!(g.stops[1].color, Color::new(255, 0, 0));
+    assert_eq!(g.stops[1].opacity, 0.5);
+}
+
 fn number_or_percent(s: &str) -> Result<f32, Error> {
     match Length::from_str(s)? {
         Length { num, unit: LengthUnit::None } => Ok(num as f32),
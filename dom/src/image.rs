@@ -0,0 +1,44 @@
+use crate::prelude::*;
+
+/// `<image>`. The referenced file is not loaded while parsing; `href` is kept as written.
+#[derive(Debug)]
+pub struct TagImage {
+    pub x: Option<LengthX>,
+    pub y: Option<LengthY>,
+    pub width: Option<LengthX>,
+    pub height: Option<LengthY>,
+    pub href: Option<String>,
+    pub id: Option<String>,
+    pub attrs: Attrs,
+}
+impl Tag for TagImage {
+    fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|s| s.as_str())
+    }
+}
+impl ParseNode for TagImage {
+    fn parse_node(node: &Node) -> Result<TagImage, Error> {
+        parse!(node => {
+            var x: Option<LengthX>,
+            var y: Option<LengthY>,
+            var width: Option<LengthX>,
+            var height: Option<LengthY>,
+            var id,
+        });
+        let href = href(node);
+        let attrs = Attrs::parse(node)?;
+        Ok(TagImage { x, y, width, height, href, id, attrs })
+    }
+}
+
+#[test]
+fn test_image() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+        <image id="a" href="icon.png" x="1" y="2" width="16" height="8"/>
+        <image id="b" xlink:href="other.png"/>
+    </svg>"#).unwrap();
+    let image = item!(svg, "a", Image);
+    assert_eq!(image.href.as_deref(), Some("icon.png"));
+    assert!(image.width.is_some() && image.height.is_some());
+    assert_eq!(item!(svg, "b", Image).href.as_deref(), Some("other.png"));
+}
@@ -2,6 +2,7 @@
 
 #[macro_use] extern crate log;
 use std::sync::Arc;
+use std::cell::Cell;
 use roxmltree::NodeType;
 
 pub mod prelude {
@@ -19,8 +20,12 @@ pub mod prelude {
         filter::*,
         g::*,
         gradient::*,
+        image::*,
+        marker::*,
+        mask::*,
         paint::*,
         path::*,
+        pattern::*,
         polygon::*,
         rect::*,
         svg::*,
@@ -29,7 +34,7 @@ pub mod prelude {
         value::*,
     };
     pub use roxmltree::Node;
-    pub use svgtypes::{Length, LengthUnit};
+    pub use svgtypes::{Length, LengthUnit, AspectRatio, Align};
     pub use std::str::FromStr;
     pub use crate::util::Parse;
 
@@ -41,14 +46,19 @@ pub mod prelude {
 #[macro_use] mod macros;
 mod animate;
 mod attrs;
+mod css;
 mod ellipse;
 mod error;
 mod filter;
 mod g;
 mod gradient;
+mod image;
+mod marker;
+mod mask;
 mod paint;
 mod parser;
 mod path;
+mod pattern;
 mod polygon;
 mod rect;
 mod svg;
@@ -58,6 +68,17 @@ mod value;
 
 pub use prelude::*;
 
+const SVG_NS: &str = "http://www.w3.org/2000/svg";
+
+/// maximum nesting depth of the elements that are parsed.
+/// Parsing (and dropping) the items recurses, so deeper content is skipped with a warning instead of overflowing the stack.
+pub const MAX_DEPTH: usize = 256;
+
+thread_local! {
+    /// number of elements enclosing the one that is being parsed
+    static DEPTH: Cell<usize> = Cell::new(0);
+}
+
 // enum_dispatch breaks RLS, so we do it manually
 macro_rules! items {
     ($(#[$meta:meta])* pub enum $name:ident { $($($e:pat )|* => $variant:ident($data:ty), )* } { $($other:ident($other_data:ty),)* }) => {
@@ -80,10 +101,15 @@ macro_rules! items {
                 }
             }
         }
-        fn parse_element(node: &Node) -> Result<Option<Item>, Error> {
+        fn parse_tag(node: &Node) -> Result<Option<Item>, Error> {
             //println!("<{:?}:{} id={:?}, ...>", node.tag_name().namespace(), node.tag_name().name(), node.attribute("id"));
             let item = match node.tag_name().name() {
                 $( $($e )|* => Item::$variant(<$data>::parse_node(node)?), )*
+                // content that is not drawn
+                "title" | "desc" | "metadata" | "style" | "script" | "foreignObject" => return Ok(None),
+                // unknown elements of other namespaces with element children (e.g. vendor wrappers) are treated like groups,
+                // so that the known content inside still renders. Unsupported SVG elements (like `<animateMotion>`) are skipped.
+                _ if node.tag_name().namespace() != Some(SVG_NS) && node.children().any(|n| n.is_element()) => Item::G(TagG::parse_node(node)?),
                 tag => {
                     println!("unimplemented: {}", tag);
                     return Ok(None);
@@ -99,6 +125,8 @@ items!(
     pub enum Item {
         "path" => Path(TagPath),
         "g" => G(TagG),
+        "a" => A(TagA),
+        "switch" => Switch(TagSwitch),
         "defs" => Defs(TagDefs),
         "rect" => Rect(TagRect),
         "polygon" => Polygon(TagPolygon),
@@ -108,7 +136,10 @@ items!(
         "ellipse" => Ellipse(TagEllipse),
         "linearGradient" => LinearGradient(TagLinearGradient),
         "radialGradient" => RadialGradient(TagRadialGradient),
+        "pattern" => Pattern(TagPattern),
         "clipPath" => ClipPath(TagClipPath),
+        "mask" => Mask(TagMask),
+        "marker" => Marker(TagMarker),
         "filter" => Filter(TagFilter),
         "svg" => Svg(TagSvg),
         "use" => Use(TagUse),
@@ -116,12 +147,36 @@ items!(
         "text" => Text(TagText),
         "tspan" => TSpan(TagTSpan),
         "tref" => TRef(TagTRef),
+        "image" => Image(TagImage),
     }
     {
         String(String),
     }
 );
 
+impl Item {
+    /// the presentation attributes of a rendered element: a shape, container, `<text>` or `<image>`
+    pub fn attrs(&self) -> Option<&Attrs> {
+        match *self {
+            Item::Path(TagPath { ref attrs, .. }) |
+            Item::G(TagG { ref attrs, .. }) |
+            Item::A(TagA { ref attrs, .. }) |
+            Item::Switch(TagSwitch { ref attrs, .. }) |
+            Item::Rect(TagRect { ref attrs, .. }) |
+            Item::Polygon(TagPolygon { ref attrs, .. }) |
+            Item::Polyline(TagPolyline { ref attrs, .. }) |
+            Item::Line(TagLine { ref attrs, .. }) |
+            Item::Ellipse(TagEllipse { ref attrs, .. }) |
+            Item::Circle(TagCircle { ref attrs, .. }) |
+            Item::Svg(TagSvg { ref attrs, .. }) |
+            Item::Use(TagUse { ref attrs, .. }) |
+            Item::Image(TagImage { ref attrs, .. }) |
+            Item::Text(TagText { ref attrs, .. }) => Some(attrs),
+            _ => None
+        }
+    }
+}
+
 pub trait ParseNode: Sized {
     fn parse_node(node: &Node) -> Result<Self, Error>;
 }
@@ -156,6 +211,18 @@ fn link(ids: &mut ItemCollection, item: &Arc<Item>) {
     }
 }
 
+fn parse_element(node: &Node) -> Result<Option<Item>, Error> {
+    let depth = DEPTH.with(|d| d.get());
+    if depth >= MAX_DEPTH {
+        warn!("maximum nesting depth of {} exceeded, skipping <{}>", MAX_DEPTH, node.tag_name().name());
+        return Ok(None);
+    }
+    DEPTH.with(|d| d.set(depth + 1));
+    let item = parse_tag(node);
+    DEPTH.with(|d| d.set(depth));
+    item
+}
+
 fn parse_node(node: &Node, first: bool, last: bool) -> Result<Option<Item>, Error> {
     match node.node_type() {
         NodeType::Element => parse_element(node),
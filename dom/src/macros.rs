@@ -61,8 +61,8 @@ macro_rules! parse {
     (@ $node:ident, [ var $var:ident $( ($name:pat) )? $(: $ty:ty)? $(= $default:expr)? $(=> $parser:expr)?, $( $rest:tt )* ] [$($list1:tt)*] [$($list2:tt)*] $($args:tt)* ) => (
         parse!(@ $node, [$($rest)*] [$($list1)* $var $( ($name) )? $(: $ty)? $(= $default)? $(=> $parser)?,] [$($list2)*] $($args)*)
     );
-    (@ $node:ident, [ anim $var:ident $( ($name:pat) )? $(: $ty:ty)? $(= $default:expr)?, $( $rest:tt )* ] [$($list1:tt)*] [$($list2:tt)*] $($args:tt)* ) => (
-        parse!(@ $node, [ $($rest)*] [$($list1)* $var $( ($name) )? $(: $ty)? $(= $default)?,] [$($list2)* $var $( ($name) )?,] $($args)* )
+    (@ $node:ident, [ anim $var:ident $( ($name:pat) )? $(: $ty:ty)? $(= $default:expr)? $(=> $parser:expr)?, $( $rest:tt )* ] [$($list1:tt)*] [$($list2:tt)*] $($args:tt)* ) => (
+        parse!(@ $node, [ $($rest)*] [$($list1)* $var $( ($name) )? $(: $ty)? $(= $default)? $(=> $parser)?,] [$($list2)* $var $( ($name) )?,] $($args)* )
     );
     (@ $node:ident, [_ => $items:ident,] [$($list1:tt)*] [$($list2:tt)*] $($args:tt)*) => (
         parse!(@ $node, [] [$($list1)*] [$($list2)*] $($args)* items=$items)
@@ -76,14 +76,14 @@ macro_rules! parse {
             let val = attribute.value();
             match attribute.name() {
                 $( parse!(@name $var $( ($name) )?) => $var = parse!(@parse val $(,$parser)? )?, )*
-                "style" => {
-                    for (key, val) in $crate::util::style_list(val) {
-                        match key {
-                            $( parse!(@name $var $( ($name) )?) => $var = parse!(@parse val $(,$parser)? )?, )*
-                            _ => {}
-                        }
-                    }
-                }
+                _ => {}
+            }
+        }
+        // stylesheet rules and `style` override the presentation attributes, regardless of attribute order
+        for (key, val) in $crate::css::cascade(&$node) {
+            let val = val.as_str();
+            match key.as_str() {
+                $( parse!(@name $var $( ($name) )?) => $var = parse!(@parse val $(,$parser)? )?, )*
                 _ => {}
             }
         }
@@ -91,7 +91,7 @@ macro_rules! parse {
         for (first, last, n) in crate::first_or_last_node($node.children()) {
             if n.is_element() {
                 match n.tag_name().name() {
-                    "animate" | "animateColor" => match n.attribute("attributeName").unwrap() {
+                    "animate" | "animateColor" | "animateTransform" => match n.attribute("attributeName").unwrap() {
                         $( parse!(@name $var2 $( ($name2) )?) => $var2.parse_animate_node(&n)?, )*
                         _ => continue,
                     }
@@ -146,3 +146,14 @@ macro_rules! items {
         }
     };
 }
+
+/// the element with the given id, which has to be an `Item::$kind`
+#[cfg(test)]
+macro_rules! item {
+    ($svg:expr, $id:expr, $kind:ident) => (
+        match &**$svg.get_item($id).unwrap() {
+            Item::$kind(item) => item,
+            item => panic!("expected {} for {:?}, got {:?}", stringify!($kind), $id, item)
+        }
+    )
+}
@@ -0,0 +1,113 @@
+use crate::prelude::*;
+use crate::parse_node_list;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct TagMarker {
+    pub items: Vec<Arc<Item>>,
+    pub attrs: Attrs,
+    pub id: Option<String>,
+    pub ref_x: LengthX,
+    pub ref_y: LengthY,
+    pub marker_width: LengthX,
+    pub marker_height: LengthY,
+    pub marker_units: MarkerUnits,
+    pub orient: MarkerOrient,
+    /// if set, the content (and `refX`/`refY`) are in these coordinates, fit into `markerWidth` x `markerHeight`
+    pub view_box: Option<Rect>,
+    pub preserve_aspect_ratio: Option<AspectRatio>,
+}
+impl Tag for TagMarker {
+    fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|s| s.as_str())
+    }
+    fn children(&self) -> &[Arc<Item>] {
+        &*self.items
+    }
+}
+impl ParseNode for TagMarker {
+    fn parse_node(node: &Node) -> Result<TagMarker, Error> {
+        parse!(node => {
+            var ref_x ("refX"): LengthX,
+            var ref_y ("refY"): LengthY,
+            var marker_width ("markerWidth"): LengthX = LengthX(Length::new_number(3.0)),
+            var marker_height ("markerHeight"): LengthY = LengthY(Length::new_number(3.0)),
+            var marker_units ("markerUnits"): MarkerUnits = MarkerUnits::StrokeWidth,
+            var orient: MarkerOrient = MarkerOrient::Angle(0.0),
+            var id,
+        });
+        let attrs = Attrs::parse(node)?;
+        let items = parse_node_list(node.children())?;
+        let view_box = node.attribute("viewBox").map(Rect::parse).transpose()?;
+        let preserve_aspect_ratio = node.attribute("preserveAspectRatio").map(AspectRatio::parse).transpose()?;
+
+        Ok(TagMarker { items, attrs, id, ref_x, ref_y, marker_width, marker_height, marker_units, orient, view_box, preserve_aspect_ratio })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MarkerUnits {
+    /// the marker content is scaled by the stroke width of the referencing element
+    StrokeWidth,
+    UserSpaceOnUse,
+}
+impl Parse for MarkerUnits {
+    fn parse(s: &str) -> Result<MarkerUnits, Error> {
+        Ok(match s {
+            "strokeWidth" => MarkerUnits::StrokeWidth,
+            "userSpaceOnUse" => MarkerUnits::UserSpaceOnUse,
+            val => return Err(Error::InvalidAttributeValue(val.into()))
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MarkerOrient {
+    /// along the direction of the path (the bisector at interior vertices)
+    Auto,
+    /// like `Auto`, but turned by 180° for the start marker
+    AutoStartReverse,
+    /// fixed angle in radians
+    Angle(f32),
+}
+impl Parse for MarkerOrient {
+    fn parse(s: &str) -> Result<MarkerOrient, Error> {
+        Ok(match s {
+            "auto" => MarkerOrient::Auto,
+            "auto-start-reverse" => MarkerOrient::AutoStartReverse,
+            _ => MarkerOrient::Angle(deg2rad(f32::parse(s)?)),
+        })
+    }
+}
+
+/// the value of `marker-start`, `marker-mid` and `marker-end`
+#[derive(Debug, Clone)]
+pub enum MarkerAttr {
+    None,
+    Ref(String)
+}
+impl MarkerAttr {
+    pub fn parse(s: &str) -> Result<Option<MarkerAttr>, Error> {
+        match s {
+            "none" => Ok(Some(MarkerAttr::None)),
+            "inherit" => Ok(None),
+            _ => Ok(Some(MarkerAttr::Ref(Iri::parse(s)?.0)))
+        }
+    }
+}
+
+#[test]
+fn test_marker() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <marker id="m" refX="1" orient="auto-start-reverse"><circle r="1"/></marker>
+        <path id="p" d="M0,0 L10,0" style="marker-mid: url(#m)" marker-end="none"/>
+    </svg>"#).unwrap();
+    let marker = item!(svg, "m", Marker);
+    assert_eq!(marker.orient, MarkerOrient::AutoStartReverse);
+    assert_eq!(marker.marker_units, MarkerUnits::StrokeWidth);
+    assert_eq!(marker.items.len(), 1);
+    let path = item!(svg, "p", Path);
+    assert!(path.attrs.marker_start.is_none());
+    assert!(matches!(path.attrs.marker_mid, Some(MarkerAttr::Ref(ref id)) if id == "m"));
+    assert!(matches!(path.attrs.marker_end, Some(MarkerAttr::None)));
+}
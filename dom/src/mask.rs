@@ -0,0 +1,89 @@
+use crate::prelude::*;
+use crate::parse_node_list;
+use std::sync::Arc;
+
+/// what is taken from the rendered mask content as the opacity of the masked element
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MaskType {
+    Luminance,
+    Alpha,
+}
+impl Parse for MaskType {
+    fn parse(s: &str) -> Result<MaskType, Error> {
+        match s {
+            "luminance" => Ok(MaskType::Luminance),
+            "alpha" => Ok(MaskType::Alpha),
+            val => Err(Error::InvalidAttributeValue(val.into()))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TagMask {
+    pub items: Vec<Arc<Item>>,
+    pub id: Option<String>,
+    /// units of `x`, `y`, `width` and `height`. `objectBoundingBox` if not set.
+    pub mask_units: Option<GradientUnits>,
+    /// units of the content. `userSpaceOnUse` if not set.
+    pub mask_content_units: Option<GradientUnits>,
+    pub x: Option<LengthX>,
+    pub y: Option<LengthY>,
+    pub width: Option<LengthX>,
+    pub height: Option<LengthY>,
+    pub mask_type: MaskType,
+}
+impl Tag for TagMask {
+    fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|s| s.as_str())
+    }
+    fn children(&self) -> &[Arc<Item>] {
+        &*self.items
+    }
+}
+impl ParseNode for TagMask {
+    fn parse_node(node: &Node) -> Result<TagMask, Error> {
+        let id = node.attribute("id").map(|s| s.into());
+        let items = parse_node_list(node.children())?;
+        parse!(node => {
+            var mask_units ("maskUnits"): Option<GradientUnits>,
+            var mask_content_units ("maskContentUnits"): Option<GradientUnits>,
+            var x: Option<LengthX>,
+            var y: Option<LengthY>,
+            var width: Option<LengthX>,
+            var height: Option<LengthY>,
+        });
+        let mask_type = parse_attr_or(node, "mask-type", MaskType::Luminance)?;
+        Ok(TagMask { items, id, mask_units, mask_content_units, x, y, width, height, mask_type })
+    }
+}
+
+#[test]
+fn test_mask_and_clip() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <mask id="m"><rect width="10" height="10" fill="white"/></mask>
+        <g id="g" clip-path="url(#c)" mask="url(#m)"/>
+    </svg>"#).unwrap();
+    assert_eq!(item!(svg, "m", Mask).items.len(), 1);
+    let g = item!(svg, "g", G);
+    assert!(matches!(g.attrs.clip_path, Some(ClipPathAttr::Ref(ref id)) if id == "c"));
+    assert!(matches!(g.attrs.mask, Some(Iri(ref id)) if id == "m"));
+}
+
+#[test]
+fn test_mask_units() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <mask id="a"/>
+        <mask id="b" maskUnits="userSpaceOnUse" maskContentUnits="objectBoundingBox" x="5" y="0" width="10" height="50%" mask-type="alpha"/>
+    </svg>"#).unwrap();
+    let mask = item!(svg, "a", Mask);
+    assert_eq!(mask.mask_units, None);
+    assert_eq!(mask.mask_content_units, None);
+    assert!(mask.x.is_none() && mask.width.is_none());
+    assert_eq!(mask.mask_type, MaskType::Luminance);
+    let mask = item!(svg, "b", Mask);
+    assert_eq!(mask.mask_units, Some(GradientUnits::UserSpaceOnUse));
+    assert_eq!(mask.mask_content_units, Some(GradientUnits::ObjectBoundingBox));
+    assert_eq!(mask.x.map(|x| x.0), Some(Length::new(5., LengthUnit::None)));
+    assert_eq!(mask.height.map(|h| h.0), Some(Length::new(50., LengthUnit::Percent)));
+    assert_eq!(mask.mask_type, MaskType::Alpha);
+}
@@ -2,29 +2,40 @@ use crate::prelude::*;
 use crate::parser::{parse_color, parse_paint};
 use pathfinder_color::{ColorF, ColorU};
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Color {
     pub red: f32,
     pub green: f32,
-    pub blue: f32
+    pub blue: f32,
+    /// the alpha of `rgba()` and friends, multiplied into the opacity the color is used with
+    pub alpha: f32,
 }
 impl Color {
     pub fn from_srgb_u8(r: u8, g: u8, b: u8) -> Color {
         Color {
             red: r as f32 * (1.0/255.),
             green: g as f32 * (1.0/255.),
-            blue: b as f32 * (1.0/255.)
+            blue: b as f32 * (1.0/255.),
+            alpha: 1.0,
         }
     }
     pub fn black() -> Color {
         Color {
             red: 0.,
             green: 0.,
-            blue: 0.
+            blue: 0.,
+            alpha: 1.0,
         }
     }
+    /// `transparent`: black with zero alpha
+    pub fn transparent() -> Color {
+        Color::black().with_alpha(0.0)
+    }
+    pub fn with_alpha(self, alpha: f32) -> Color {
+        Color { alpha, .. self }
+    }
     pub fn color_f(&self, alpha: f32) -> ColorF {
-        ColorF::new(self.red, self.green, self.blue, alpha)
+        ColorF::new(self.red, self.green, self.blue, self.alpha * alpha)
     }
     pub fn color_u(&self, alpha: f32) -> ColorU {
         self.color_f(alpha).to_u8()
@@ -45,8 +56,17 @@ pub enum Paint {
     None,
     Color(Color),
     Ref(String),
+    /// the value of the `color` property
+    CurrentColor,
 }
 impl Paint {
+    /// replace `currentColor` by `color`
+    pub fn with_current_color(self, color: Color) -> Paint {
+        match self {
+            Paint::CurrentColor => Paint::Color(color),
+            paint => paint
+        }
+    }
     pub fn is_none(&self) -> bool {
         matches!(*self, Paint::None)
     }
@@ -68,4 +88,6 @@ impl Parse for Paint {
 #[test]
 fn test_paint() {
     assert_eq!(Paint::parse("#aabbcc").unwrap(), Paint::Color(Color::from_srgb_u8(0xaa, 0xbb, 0xcc)));
+    // a color, so it can be animated to and from other colors
+    assert_eq!(Paint::parse("transparent").unwrap(), Paint::Color(Color::transparent()));
 }
\ No newline at end of file
@@ -1,11 +1,11 @@
 use nom::{
     IResult,
     bytes::complete::{tag, take_while_m_n},
-    character::complete::{alpha1, space0, digit1},
-    combinator::{map, map_res},
-    sequence::tuple,
+    character::complete::{alpha1, space0, space1},
+    combinator::{map, map_res, opt},
+    sequence::{tuple, delimited, preceded},
+    number::complete::float,
     branch::alt,
-    Err::Failure
 };
 use crate::prelude::*;
 
@@ -19,9 +19,6 @@ fn is_hex_digit(c: char) -> bool {
 fn from_hex(input: &str) -> Result<u8, std::num::ParseIntError> {
     u8::from_str_radix(input, 16)
 }
-fn from_decimal(input: &str) -> Result<u8, std::num::ParseIntError> {
-    u8::from_str(input)
-}
 fn hex_byte(input: &str) -> IResult<&str, u8, ()> {
     map_res(
       take_while_m_n(2, 2, is_hex_digit),
@@ -38,27 +35,6 @@ fn hex_nibble(input: &str) -> IResult<&str, u8, ()> {
         |n| 16 * n
     )(input)
 }
-fn integer(input: &str) -> IResult<&str, u8, ()> {
-    map_res(
-        digit1,
-        from_decimal
-    )(input)
-}
-fn percent(i: &str) -> IResult<&str, u8, ()> {
-    let (i, (pc, _)) = tuple((integer, tag("%")))(i)?;
-    if pc > 100 {
-        Err(Failure(()))
-    } else {
-        let val = (((pc as u16) * 255) / 100) as u8;
-        Ok((i, val))
-    }
-}
-fn comma(input: &str) -> IResult<&str, (), ()> {
-    map(
-        tag(","),
-        |_| ()
-    )(input)
-}
 fn hex_color(i: &str) -> IResult<&str, Color, ()> {
     let (i, (_, r, g, b)) = tuple((tag("#"), hex_byte, hex_byte, hex_byte))(i)?;
     Ok((i, Color::from_srgb_u8(r, g, b)))
@@ -69,6 +45,9 @@ fn short_hex_color(i: &str) -> IResult<&str, Color, ()> {
 }
 fn color_name(i: &str) -> IResult<&str, Color, ()> {
     let (i, name) = alpha1(i)?;
+    if name == "transparent" {
+        return Ok((i, Color::transparent()));
+    }
     match COLOR_NAMES.binary_search_by_key(&name, |&(name, _)| name) {
         Ok(idx) => {
             let (_, (r, g, b)) = COLOR_NAMES[idx];
@@ -77,40 +56,81 @@ fn color_name(i: &str) -> IResult<&str, Color, ()> {
         _ => Err(nom::Err::Error(()))
     }
 }
-// "rgb(" wsp* integer comma integer comma integer wsp* ")"
-fn rgb_color(i: &str) -> IResult<&str, Color, ()> {
-    let (i, _) = tag("rgb(")(i)?;
-    let (i, _) = space0(i)?;
-    let (i, r) = integer(i)?;
-    let (i, _) = comma(i)?;
-    let (i, g) = integer(i)?;
-    let (i, _) = comma(i)?;
-    let (i, b) = integer(i)?;
-    let (i, _) = space0(i)?;
-    let (i, _) = tag(")")(i)?;
-    Ok((i, Color::from_srgb_u8(r, g, b)))
+/// a number, or a percentage of `full`
+fn number_or_percent(i: &str, full: f32) -> IResult<&str, f32, ()> {
+    let (i, n) = float(i)?;
+    let (i, percent) = opt(tag("%"))(i)?;
+    Ok((i, if percent.is_some() { 0.01 * n * full } else { n }))
 }
-
-// "rgb(" wsp* integer "%" comma integer "%" comma integer "%" wsp* ")"
-fn rgb_percent_color(i: &str) -> IResult<&str, Color, ()> {
-    let (i, _) = tag("rgb(")(i)?;
+fn rgb_channel(i: &str) -> IResult<&str, f32, ()> {
+    number_or_percent(i, 255.)
+}
+fn alpha_value(i: &str) -> IResult<&str, f32, ()> {
+    number_or_percent(i, 1.)
+}
+/// saturation and lightness are percentages, the `%` is optional in the space separated syntax
+fn hsl_percent(i: &str) -> IResult<&str, f32, ()> {
+    let (i, n) = float(i)?;
+    let (i, _) = opt(tag("%"))(i)?;
+    Ok((i, 0.01 * n))
+}
+/// an angle in degrees, the unit defaults to `deg`
+fn hue(i: &str) -> IResult<&str, f32, ()> {
+    let (i, n) = float(i)?;
+    let (i, unit) = opt(alt((tag("deg"), tag("grad"), tag("rad"), tag("turn"))))(i)?;
+    let degrees = match unit {
+        Some("grad") => n * 0.9,
+        Some("rad") => n.to_degrees(),
+        Some("turn") => n * 360.,
+        _ => n
+    };
+    Ok((i, degrees))
+}
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> (f32, f32, f32) {
+    let f = |n: f32| {
+        let k = (n + hue / 30.).rem_euclid(12.);
+        let a = saturation * lightness.min(1. - lightness);
+        lightness - a * (k - 3.).min(9. - k).min(1.).max(-1.)
+    };
+    (f(0.), f(8.), f(4.))
+}
+/// `rgb()`, `rgba()`, `hsl()` and `hsla()`, with either the legacy syntax `rgb(r, g, b, a)`
+/// or the space separated one `rgb(r g b / a)`.
+/// The channels can be numbers or percentages, so can the alpha.
+fn color_function(i: &str) -> IResult<&str, Color, ()> {
+    let (i, name) = alt((tag("rgba("), tag("rgb("), tag("hsla("), tag("hsl(")))(i)?;
+    let hsl = name.starts_with("hsl");
     let (i, _) = space0(i)?;
-    let (i, r) = percent(i)?;
-    let (i, _) = comma(i)?;
-    let (i, g) = percent(i)?;
-    let (i, _) = comma(i)?;
-    let (i, b) = percent(i)?;
+    let (first, rest): (fn(&str) -> IResult<&str, f32, ()>, fn(&str) -> IResult<&str, f32, ()>) = match hsl {
+        true => (hue, hsl_percent),
+        false => (rgb_channel, rgb_channel),
+    };
+    let (i, x) = first(i)?;
+    let (i, legacy) = opt(delimited(space0, tag(","), space0))(i)?;
+    let legacy = legacy.is_some();
+    let (i, _) = if legacy { (i, "") } else { space1(i)? };
+    let (i, y) = rest(i)?;
+    let (i, _) = if legacy { delimited(space0, tag(","), space0)(i)? } else { space1(i)? };
+    let (i, z) = rest(i)?;
+    let alpha_sep = if legacy { "," } else { "/" };
+    let (i, alpha) = opt(preceded(delimited(space0, tag(alpha_sep), space0), alpha_value))(i)?;
     let (i, _) = space0(i)?;
     let (i, _) = tag(")")(i)?;
-    Ok((i, Color::from_srgb_u8(r, g, b)))
+
+    let (red, green, blue) = if hsl {
+        hsl_to_rgb(x, y.min(1.).max(0.), z.min(1.).max(0.))
+    } else {
+        (x / 255., y / 255., z / 255.)
+    };
+    let clamp = |v: f32| v.min(1.).max(0.);
+    Ok((i, Color { red: clamp(red), green: clamp(green), blue: clamp(blue), alpha: clamp(alpha.unwrap_or(1.)) }))
 }
 
 pub fn color(i: &str) -> IResult<&str, Color, ()> {
     alt((
         hex_color,
         short_hex_color,
-        rgb_color,
-        rgb_percent_color,
+        color_function,
         color_name
     ))(i)
 }
@@ -121,6 +141,22 @@ fn test_color() {
     assert_eq!(color("#012345").unwrap().1, Color::from_srgb_u8(0x01, 0x23, 0x45));
 }
 
+#[test]
+fn test_color_function() {
+    let red = Color::from_srgb_u8(255, 0, 0);
+    assert_eq!(color("rgb(255, 0, 0)").unwrap().1, red);
+    assert_eq!(color("rgb( 100%,0%,0% )").unwrap().1, red);
+    assert_eq!(color("rgb(255 0 0)").unwrap().1, red);
+    assert_eq!(color("rgba(255, 0, 0, 0.5)").unwrap().1, red.with_alpha(0.5));
+    assert_eq!(color("rgb(255 0 0 / 50%)").unwrap().1, red.with_alpha(0.5));
+    assert_eq!(color("hsl(0, 100%, 50%)").unwrap().1, red);
+    assert_eq!(color("hsl(120 50% 50% / 0.5)").unwrap().1, Color { red: 0.25, green: 0.75, blue: 0.25, alpha: 0.5 });
+    assert_eq!(color("hsla(0.5turn, 100%, 50%, 1)").unwrap().1, Color::from_srgb_u8(0, 255, 255));
+    // the separators can't be mixed
+    assert!(color("rgb(255 0, 0)").is_err());
+    assert!(color("rgb(255, 0, 0 / 1)").is_err());
+}
+
 static COLOR_NAMES: &[(&str, (u8, u8, u8))] = &[
     ("aliceblue", (240, 248, 255)),
     ("antiquewhite", (250, 235, 215)),
@@ -53,6 +53,7 @@ pub fn parse_color(s: &str) -> Result<Color, Error> {
 pub fn parse_paint(s: &str) -> Result<Paint, Error> {
     match alt((
         map(tag("none"), |_| Paint::None),
+        map(alt((tag("currentColor"), tag("currentcolor"))), |_| Paint::CurrentColor),
         map(func_iri, |s| Paint::Ref(s.into())),
         map(color::color, Paint::Color),
     ))(s) {
@@ -67,6 +68,7 @@ pub fn parse_paint(s: &str) -> Result<Paint, Error> {
 #[test]
 fn test_paint() {
     assert_eq!(parse_paint("url(#radialGradient862)").unwrap(), Paint::Ref("radialGradient862".into()));
+    assert_eq!(parse_paint("currentColor").unwrap(), Paint::CurrentColor);
 }
 
 fn list_sep(i: &str) -> IResult<&str, &str, ()> {
@@ -0,0 +1,67 @@
+use crate::prelude::*;
+use crate::parse_node_list;
+use std::sync::Arc;
+
+#[derive(Debug)]
+pub struct TagPattern {
+    pub items: Vec<Arc<Item>>,
+    pub id: Option<String>,
+    pub x: Option<LengthX>,
+    pub y: Option<LengthY>,
+    pub width: Option<LengthX>,
+    pub height: Option<LengthY>,
+    /// coordinate system of `x`, `y`, `width` and `height` (defaults to objectBoundingBox)
+    pub pattern_units: Option<GradientUnits>,
+    /// coordinate system of the content (defaults to userSpaceOnUse, ignored if there is a `viewBox`)
+    pub pattern_content_units: Option<GradientUnits>,
+    pub pattern_transform: Option<Transform2F>,
+    pub view_box: Option<Rect>,
+    pub preserve_aspect_ratio: Option<AspectRatio>,
+    pub href: Option<String>,
+}
+impl Tag for TagPattern {
+    fn id(&self) -> Option<&str> {
+        self.id.as_ref().map(|s| s.as_str())
+    }
+    fn children(&self) -> &[Arc<Item>] {
+        &*self.items
+    }
+}
+impl ParseNode for TagPattern {
+    fn parse_node(node: &Node) -> Result<TagPattern, Error> {
+        parse!(node => {
+            var x: Option<LengthX>,
+            var y: Option<LengthY>,
+            var width: Option<LengthX>,
+            var height: Option<LengthY>,
+            var pattern_units ("patternUnits"): Option<GradientUnits>,
+            var pattern_content_units ("patternContentUnits"): Option<GradientUnits>,
+            var id,
+        });
+        let items = parse_node_list(node.children())?;
+        let pattern_transform = node.attribute("patternTransform").map(transform_list).transpose()?;
+        let view_box = node.attribute("viewBox").map(Rect::parse).transpose()?;
+        let preserve_aspect_ratio = node.attribute("preserveAspectRatio").map(AspectRatio::parse).transpose()?;
+        let href = href(node);
+
+        Ok(TagPattern {
+            items, id, x, y, width, height,
+            pattern_units, pattern_content_units, pattern_transform,
+            view_box, preserve_aspect_ratio, href
+        })
+    }
+}
+
+#[test]
+fn test_pattern() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <pattern id="p" width="10" height="10" patternUnits="userSpaceOnUse" patternTransform="rotate(45)">
+            <rect width="5" height="5"/>
+        </pattern>
+    </svg>"#).unwrap();
+    let p = item!(svg, "p", Pattern);
+    assert_eq!(p.pattern_units, Some(GradientUnits::UserSpaceOnUse));
+    assert_eq!(p.pattern_content_units, None);
+    assert!(p.pattern_transform.is_some());
+    assert_eq!(p.items.len(), 1);
+}
@@ -13,8 +13,8 @@ pub struct TagRect {
     pub size: ValueVector,
 
     //#[attr("rx", "ry", animate, default)]
-    pub rx: Value<Option<Length>>,
-    pub ry: Value<Option<Length>>,
+    pub rx: Value<Option<LengthX>>,
+    pub ry: Value<Option<LengthY>>,
 
     //#[attr("id")]
     pub id: Option<String>,
@@ -38,8 +38,8 @@ impl ParseNode for TagRect {
             anim y: Value<LengthY>,
             anim height: Value<LengthY>,
             anim width: Value<LengthX>,
-            anim rx: Value<Option<Length>>,
-            anim ry: Value<Option<Length>>,
+            anim rx: Value<Option<LengthX>>,
+            anim ry: Value<Option<LengthY>>,
             var id,
         });
         let attrs = Attrs::parse(node)?;
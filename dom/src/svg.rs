@@ -1,8 +1,10 @@
 use crate::prelude::*;
 use crate::{parse_node, parse_node_list, link};
+use crate::css::StyleSheet;
 use libflate::gzip::Decoder;
 
 use std::sync::Arc;
+use std::collections::HashMap;
 use roxmltree::{Document};
 
 #[derive(Debug)]
@@ -10,6 +12,7 @@ pub struct TagSvg {
     pub id: Option<String>,
     pub items: Vec<Arc<Item>>,
     pub view_box: Option<Rect>,
+    pub preserve_aspect_ratio: Option<AspectRatio>,
     pub width: Option<LengthX>,
     pub height: Option<LengthY>,
     pub attrs: Attrs,
@@ -19,6 +22,8 @@ pub struct TagSvg {
 pub struct Svg {
     pub named_items: ItemCollection,
     pub root: Arc<Item>,
+    /// the unparsed attributes of every element with an id, by id and (local) attribute name
+    pub raw_attributes: HashMap<String, HashMap<String, String>>,
 }
 impl Tag for TagSvg {
     fn id(&self) -> Option<&str> {
@@ -32,6 +37,7 @@ impl Tag for TagSvg {
 impl ParseNode for TagSvg {
     fn parse_node(node: &Node) -> Result<TagSvg, Error> {
         let view_box = node.attribute("viewBox").map(Rect::parse).transpose()?;
+        let preserve_aspect_ratio = node.attribute("preserveAspectRatio").map(AspectRatio::parse).transpose()?;
         let width = node.attribute("width").map(LengthX::parse).transpose()?;
         let height = node.attribute("height").map(LengthY::parse).transpose()?;
         let id = node.attribute("id").map(|s| s.into());
@@ -39,7 +45,7 @@ impl ParseNode for TagSvg {
 
         let items = parse_node_list(node.children())?;
     
-        Ok(TagSvg { items, view_box, id, attrs, width, height })
+        Ok(TagSvg { items, view_box, preserve_aspect_ratio, id, attrs, width, height })
     }
 }
 
@@ -47,17 +53,32 @@ impl Svg {
     pub fn get_item(&self, id: &str) -> Option<&Arc<Item>> {
         self.named_items.get(id)
     }
+    /// the attribute `name` of the element with the given id, as written in the document
+    pub fn attribute(&self, id: &str, name: &str) -> Option<&str> {
+        self.raw_attributes.get(id)?.get(name).map(|s| s.as_str())
+    }
     pub fn from_str(text: &str) -> Result<Svg, Error> {
         let doc = Document::parse(text)?;
-        let root = parse_node(&doc.root_element(), true, true);
+        let root = StyleSheet::from_document(&doc).scoped(|| parse_node(&doc.root_element(), true, true));
         let root_item = Arc::new(root?.ok_or(Error::NotSvg)?);
 
         let mut named_items = ItemCollection::new();
         link(&mut named_items, &root_item);
 
+        let raw_attributes = doc.descendants()
+            .filter_map(|node| {
+                let id = node.attribute("id")?;
+                let attributes = node.attributes().iter()
+                    .map(|attr| (attr.name().into(), attr.value().into()))
+                    .collect();
+                Some((id.into(), attributes))
+            })
+            .collect();
+
         Ok(Svg {
             root: root_item,
             named_items,
+            raw_attributes,
         })
     }
     pub fn from_data(data: &[u8]) -> Result<Svg, Error> {
@@ -73,4 +94,24 @@ impl Svg {
             Self::from_str(text)
         }
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_preserve_aspect_ratio() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <svg id="nested" viewBox="0 0 10 20" width="10" height="10" preserveAspectRatio="xMinYMid slice"/>
+    </svg>"#).unwrap();
+    let aspect = item!(svg, "nested", Svg).preserve_aspect_ratio.unwrap();
+    assert_eq!(aspect.align, Align::XMinYMid);
+    assert!(aspect.slice);
+}
+
+#[test]
+fn test_raw_attribute() {
+    let svg = Svg::from_str(r##"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="r" width="10%" height="1" fill="#f00"/>
+    </svg>"##).unwrap();
+    assert_eq!(svg.attribute("r", "fill"), Some("#f00"));
+    assert_eq!(svg.attribute("r", "width"), Some("10%"));
+    assert_eq!(svg.attribute("r", "stroke"), None);
+    assert_eq!(svg.attribute("missing", "fill"), None);
+}
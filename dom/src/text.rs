@@ -8,6 +8,11 @@ pub struct TagText {
     pub items: Vec<Arc<Item>>,
     pub pos: GlyphPos,
     pub attrs: Attrs,
+
+    /// `inline-size`: width of the layout box, in the direction of the text
+    pub inline_size: Option<LengthX>,
+    /// with an `inline-size`, glyphs outside of the layout box are clipped if `overflow` clips
+    pub overflow: Option<Overflow>,
 }
 impl Tag for TagText {
     fn id(&self) -> Option<&str> {
@@ -27,6 +32,8 @@ impl ParseNode for TagText {
             var dy,
             var rotate,
             var id,
+            var inline_size ("inline-size"): Option<LengthX>,
+            var overflow: Option<Overflow>,
             _ => items,
         });
 
@@ -35,6 +42,8 @@ impl ParseNode for TagText {
             attrs: Attrs::parse(node)?,
             id,
             items,
+            inline_size,
+            overflow,
         })
     }
 }
@@ -91,6 +100,8 @@ impl ParseNode for TagTSpan {
 #[derive(Clone, Debug)]
 pub struct TagTRef {
     pub href: Option<String>,
+    pub attrs: Attrs,
+    pub pos: GlyphPos,
 }
 
 impl Tag for TagTRef {
@@ -103,9 +114,41 @@ impl Tag for TagTRef {
 }
 impl ParseNode for TagTRef {
     fn parse_node(node: &Node) -> Result<TagTRef, Error> {
+        parse!(node => {
+            var x,
+            var y,
+            var dx,
+            var dy,
+            var rotate,
+        });
         let href = href(node);
-        Ok(TagTRef { href })
+        let attrs = Attrs::parse(node)?;
+        Ok(TagTRef { href, attrs, pos: GlyphPos { x, y, dx, dy, rotate } })
+    }
+}
+
+/// the character data of `item` and all its descendants, as referenced by `<tref>`
+pub fn text_content(item: &Item) -> String {
+    fn collect(item: &Item, out: &mut String) {
+        match *item {
+            Item::String(ref s) => out.push_str(s),
+            ref item => for child in item.children() {
+                collect(child, out);
+            }
+        }
     }
+    let mut out = String::new();
+    collect(item, &mut out);
+    out
+}
+#[test]
+fn test_tref() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+        <text id="source">Hello <tspan>World</tspan></text>
+        <text><tref id="ref" xlink:href="#source"/></text>
+    </svg>"#).unwrap();
+    let source = svg.get_item("source").unwrap();
+    assert_eq!(text_content(source), "Hello World");
 }
 
 fn one_or_many<'a, T: 'a>(f: impl Fn(Length) -> T + 'a) -> impl Fn(&str) -> Result<OneOrMany<T>, Error> + 'a {
@@ -136,4 +179,14 @@ impl Parse for OneOrMany<LengthY> {
     fn parse(s: &str) -> Result<OneOrMany<LengthY>, Error> {
         one_or_many(LengthY)(s)
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_text_overflow() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <text id="t" inline-size="40" overflow="hidden">Supercalifragilistic</text>
+    </svg>"#).unwrap();
+    let text = item!(svg, "t", Text);
+    assert_eq!(text.inline_size.map(|l| l.0.num), Some(40.0));
+    assert_eq!(text.overflow, Some(Overflow::Hidden));
+}
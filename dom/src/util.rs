@@ -3,7 +3,7 @@ use pathfinder_geometry::{
     transform2d::Transform2F,
     rect::RectF,
 };
-use svgtypes::{TransformListParser, TransformListToken, Length, LengthListParser};
+use svgtypes::{TransformListParser, TransformListToken, Length, LengthListParser, AspectRatio, Align};
 use crate::error::Error;
 use std::str::FromStr;
 use roxmltree::Node;
@@ -109,8 +109,24 @@ fn pair<I: Iterator>(mut iter: I) -> Option<(I::Item, I::Item)> {
     }
 }
 
-pub fn style_list(s: &str) -> impl Iterator<Item=(&str, &str)> + '_ {
-    s.split(";").flat_map(|s| pair(s.splitn(2, ":"))).map(|(a, b)| (a.trim(), b.trim()))
+/// the declarations of a `style` attribute or a stylesheet rule, as `(name, value, important)`, with `!important` stripped.
+pub fn style_declarations(s: &str) -> impl Iterator<Item=(&str, &str, bool)> + '_ {
+    s.split(";").flat_map(|s| pair(s.splitn(2, ":"))).map(|(a, b)| {
+        let b = b.trim();
+        match strip_important(b) {
+            Some(b) => (a.trim(), b, true),
+            None => (a.trim(), b, false)
+        }
+    })
+}
+
+fn strip_important(s: &str) -> Option<&str> {
+    let bang = s.rfind('!')?;
+    if s[bang + 1 ..].trim().eq_ignore_ascii_case("important") {
+        Some(s[.. bang].trim_end())
+    } else {
+        None
+    }
 }
 
 pub fn max_bounds(mut iter: impl Iterator<Item=RectF>) -> Option<RectF> {
@@ -130,7 +146,7 @@ pub fn length(s: &str) -> Result<Length, Error> {
 
 pub fn href(node: &Node) -> Option<String> {
     let xlink = node.lookup_namespace_uri(Some("xlink")).unwrap_or_default();
-    node.attribute((xlink, "href")).map(|s| s.to_owned())
+    node.attribute((xlink, "href")).or_else(|| node.attribute("href")).map(|s| s.to_owned())
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -168,6 +184,24 @@ impl Parse for LengthY {
         Length::parse(s).map(LengthY)
     }
 }
+/// `defer` is only meaningful for `<image>` referencing an SVG. It is recorded, but nothing else changes.
+impl Parse for AspectRatio {
+    fn parse(s: &str) -> Result<Self, Error> {
+        let s = s.trim();
+        let (defer, rest) = match s.strip_prefix("defer") {
+            Some(rest) if rest.starts_with(char::is_whitespace) => (true, rest.trim_start()),
+            _ => (false, s)
+        };
+        let aspect = AspectRatio::from_str(rest).map_err(|_| Error::InvalidAttributeValue(s.into()))?;
+        Ok(AspectRatio { defer, .. aspect })
+    }
+}
+
+/// `xMidYMid meet`, used when `preserveAspectRatio` is absent
+pub fn default_aspect_ratio() -> AspectRatio {
+    AspectRatio { defer: false, align: Align::XMidYMid, slice: false }
+}
+
 impl Parse for String {
     fn parse(s: &str) -> Result<Self, Error> {
         Ok(s.into())
@@ -11,12 +11,20 @@ impl<T> Resolve for Animate<T> where T: Resolve, T::Output: Interpolate {
         if x < 0.0 {
             return None;
         }
+        let end = self.timing.active_end;
+        if x >= end {
+            if let AnimationFill::Remove = self.fill {
+                return None;
+            }
+        }
+        // frozen at the end of the active duration, which `max` may cut short.
+        // If `min` extends it instead, the last value is held in the meantime.
+        let x = x.min(end);
         if x >= 1.0 {
-            return match (self.fill, &self.mode) {
-                (AnimationFill::Remove, _) => None,
-                (AnimationFill::Freeze, AnimationMode::Absolute { to, .. }) => Some(to.resolve(options)),
-                (AnimationFill::Freeze, AnimationMode::Relative { by }) => Some(by.resolve(options)),
-                (AnimationFill::Freeze, AnimationMode::Values { ref pairs, .. }) => pairs.last().map(|(_, v)| v.resolve(options))
+            return match self.mode {
+                AnimationMode::Absolute { ref to, .. } => Some(to.resolve(options)),
+                AnimationMode::Relative { ref by, .. } => Some(by.resolve(options)),
+                AnimationMode::Values { ref pairs, .. } => pairs.last().map(|(_, v)| v.resolve(options))
             };
         }
 
@@ -24,7 +32,7 @@ impl<T> Resolve for Animate<T> where T: Resolve, T::Output: Interpolate {
             AnimationMode::Absolute { ref from, ref to } => {
                 Some(from.resolve(options).lerp(to.resolve(options), x))
             }
-            AnimationMode::Relative { ref by } => {
+            AnimationMode::Relative { ref by, .. } => {
                 Some(by.resolve(options).scale(x))
             }
             AnimationMode::Values { ref pairs, ref splines } => {
@@ -53,6 +61,114 @@ impl<T> Resolve for Animate<T> where T: Resolve, T::Output: Interpolate {
     }
 }
 
+impl Resolve for AnimateMotion {
+    type Output = Option<Transform2F>;
+    fn resolve(&self, options: &Options) -> Option<Transform2F> {
+        let x = self.timing.pos(options.time);
+        let end = self.timing.active_end;
+        let x = match self.fill {
+            _ if x < 0.0 => return None,
+            AnimationFill::Remove if x >= end => return None,
+            _ => x.min(end).min(1.0)
+        };
+        let distance = x * self.path_len;
+
+        // the segment containing `distance`
+        let idx = self.points.iter().position(|&(d, _)| d >= distance)?;
+        let (d1, p1) = self.points[idx];
+        let (d0, p0) = self.points[idx.saturating_sub(1)];
+        let pos = if d1 > d0 {
+            p0.lerp(p1, (distance - d0) / (d1 - d0))
+        } else {
+            p1
+        };
+        // at the very start, take the direction of the first segment
+        let direction = match idx {
+            0 => self.points.get(1).map(|&(_, p)| p - p1).unwrap_or_default(),
+            _ => p1 - p0
+        };
+        let tangent = direction.y().atan2(direction.x());
+        let angle = match self.rotate {
+            MotionRotate::Auto => tangent,
+            MotionRotate::AutoReverse => tangent + std::f32::consts::PI,
+            MotionRotate::Angle(angle) => angle,
+        };
+        Some(Transform2F::from_translation(pos) * Transform2F::from_rotation(angle))
+    }
+}
+
+#[test]
+fn test_animate_motion_auto_reverse() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="r" width="1" height="1">
+            <animateMotion path="M0,0 L100,0" dur="1s" rotate="auto-reverse"/>
+        </rect>
+    </svg>"#).unwrap();
+    let motion = item!(svg, "r", Rect).attrs.motion.clone().unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    options.time = Time::from_seconds(0.5);
+    let tr = motion.resolve(&options).unwrap();
+    // halfway along the path, facing backwards
+    let p = tr * vec2f(1.0, 0.0);
+    assert!((p - vec2f(49.0, 0.0)).length() < 1e-4);
+}
+
+#[test]
+fn test_animate_transform_rotate() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="r" width="1" height="1">
+            <animateTransform attributeName="transform" type="rotate" from="0" to="90" dur="1s"/>
+        </rect>
+    </svg>"#).unwrap();
+    let transform = item!(svg, "r", Rect).attrs.transform.clone();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    options.time = Time::from_seconds(0.5);
+    // the angle is interpolated, not the matrix, so halfway is exactly 45°
+    let p = transform.resolve(&options) * vec2f(1.0, 0.0);
+    let expected = std::f32::consts::FRAC_1_SQRT_2;
+    assert!((p - vec2f(expected, expected)).length() < 1e-5);
+}
+
+#[test]
+fn test_animate_max() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="r" width="1" height="1">
+            <animate attributeName="opacity" from="0" to="1" dur="4s" max="1s" fill="freeze"/>
+        </rect>
+    </svg>"#).unwrap();
+    let opacity = item!(svg, "r", Rect).attrs.opacity.clone();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    options.time = Time::from_seconds(0.5);
+    assert_eq!(opacity.resolve(&options), Some(0.125));
+    // the active duration ends after 1s, the value stays where it was then
+    options.time = Time::from_seconds(3.0);
+    assert_eq!(opacity.resolve(&options), Some(0.25));
+}
+
+#[test]
+fn test_animate_by() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="a" width="1" height="1">
+            <animate attributeName="opacity" from="0" by="0.5" dur="1s" fill="freeze"/>
+        </rect>
+        <rect id="b" width="1" height="1" opacity="0.25">
+            <animate attributeName="opacity" by="0.5" dur="1s" fill="freeze"/>
+        </rect>
+    </svg>"#).unwrap();
+    let opacity = |id| item!(svg, id, Rect).attrs.opacity.clone();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    options.time = Time::from_seconds(0.5);
+    assert_eq!(opacity("a").resolve(&options), Some(0.25));
+    options.time = Time::from_seconds(2.0);
+    assert_eq!(opacity("a").resolve(&options), Some(0.5));
+    // without `from` it starts at the base value
+    assert_eq!(opacity("b").resolve(&options), Some(0.75));
+}
+
 impl Compose for Transform2F {
     fn compose(self, rhs: Self) -> Self {
         self * rhs
@@ -0,0 +1,113 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::fmt;
+
+/// the document and the settings of a `DrawContext` that a composed scene depends on
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ContextKey {
+    /// address of the root of the document. The cache keeps the root alive, so it is not reused by another document.
+    pub root: usize,
+    pub dpi: u32,
+    pub root_font_size: u32,
+    pub max_depth: usize,
+    pub text_placeholders: bool,
+    pub languages: Vec<String>,
+    pub flattening_tolerance: Option<u32>,
+    /// address of the resolver and of the fallback fonts
+    pub href_resolver: Option<usize>,
+    pub fonts: Option<usize>,
+}
+
+// context, transform (matrix and translation) and time in seconds, as bits so they can be hashed
+type SceneKey = (ContextKey, [u32; 6], u32);
+
+/// composed scenes, for rendering the same documents with the same settings repeatedly.
+///
+/// Set `DrawContext::scene_cache` to use it. It can be shared between contexts, also of different documents.
+#[derive(Default)]
+pub struct SceneCache {
+    scenes: Mutex<HashMap<SceneKey, (Arc<Item>, Scene)>>,
+    hits: AtomicUsize,
+}
+impl fmt::Debug for SceneCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SceneCache")
+    }
+}
+impl SceneCache {
+    pub fn new() -> Self {
+        SceneCache::default()
+    }
+    /// how many scenes were returned from the cache instead of being composed
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+    pub fn clear(&self) {
+        self.scenes.lock().unwrap().clear();
+    }
+    pub(crate) fn get_or_compose(&self, context: ContextKey, root: &Arc<Item>, transform: Transform2F, time: Time, compose: impl FnOnce() -> Scene) -> Scene {
+        let m = transform.matrix;
+        let t = transform.vector;
+        let key = (
+            context,
+            [m.m11(), m.m12(), m.m21(), m.m22(), t.x(), t.y()].map(f32::to_bits),
+            time.seconds().to_bits(),
+        );
+        if let Some((_, scene)) = self.scenes.lock().unwrap().get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return scene.clone();
+        }
+
+        // compose without holding the lock, so other threads are not blocked meanwhile
+        let scene = compose();
+        self.scenes.lock().unwrap().insert(key, (root.clone(), scene.clone()));
+        scene
+    }
+}
+
+#[test]
+fn test_scene_cache() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10"><rect width="5" height="5"/></svg>"#).unwrap();
+    let mut ctx = DrawContext::new_without_fonts(&svg);
+    let cache = Arc::new(SceneCache::new());
+    ctx.scene_cache = Some(cache.clone());
+
+    let transform = Transform2F::from_scale(2.0);
+    let first = ctx.compose_with_transform(transform);
+    assert_eq!(cache.hits(), 0);
+    let second = ctx.compose_with_transform(transform);
+    assert_eq!(cache.hits(), 1);
+    assert_eq!(first.view_box(), second.view_box());
+    assert_eq!(first.bounds(), second.bounds());
+
+    // a different transform is composed again
+    ctx.compose_with_transform(Transform2F::from_scale(3.0));
+    assert_eq!(cache.hits(), 1);
+}
+
+#[test]
+fn test_scene_cache_shared() {
+    let small = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg"><rect width="5" height="5"/></svg>"#).unwrap();
+    let large = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg"><rect width="50" height="50"/></svg>"#).unwrap();
+    let cache = Arc::new(SceneCache::new());
+    let mut small_ctx = DrawContext::new_without_fonts(&small);
+    small_ctx.scene_cache = Some(cache.clone());
+    let mut large_ctx = DrawContext::new_without_fonts(&large);
+    large_ctx.scene_cache = Some(cache.clone());
+
+    // the documents do not get each other's scene
+    assert_eq!(small_ctx.compose().bounds().width(), 5.);
+    assert_eq!(large_ctx.compose().bounds().width(), 50.);
+    assert_eq!(cache.hits(), 0);
+
+    // neither does the same document with other settings
+    small_ctx.set_dpi(96.);
+    small_ctx.compose();
+    small_ctx.text_placeholders = true;
+    small_ctx.compose();
+    assert_eq!(cache.hits(), 0);
+    small_ctx.compose();
+    assert_eq!(cache.hits(), 1);
+}
@@ -0,0 +1,39 @@
+use pathfinder_content::{
+    outline::Outline,
+    dash::OutlineDash,
+};
+
+/// dash `outline` with the lengths of `dashes`, starting `offset` into the pattern.
+/// The offset wraps around the length of the pattern, so negative offsets start that far before it.
+///
+/// Everything that is stroked (shapes, text decorations and the content of markers) is dashed through this,
+/// in the user space of the stroked element, so the same `stroke-dasharray` results in the same spacing everywhere.
+pub fn dash_outline(outline: &Outline, dashes: &[f32], offset: f32) -> Outline {
+    let period: f32 = dashes.iter().sum();
+    let offset = if period > 0. { offset.rem_euclid(period) } else { 0. };
+    let mut dash = OutlineDash::new(outline, dashes, offset);
+    dash.dash();
+    dash.into_outline()
+}
+
+#[test]
+fn test_dash_offset() {
+    use pathfinder_content::outline::Contour;
+    use pathfinder_geometry::vector::vec2f;
+
+    let mut line = Contour::new();
+    line.push_endpoint(vec2f(0.0, 0.0));
+    line.push_endpoint(vec2f(20.0, 0.0));
+    let mut outline = Outline::new();
+    outline.push_contour(line);
+    let dashes = |offset: f32| -> Vec<(f32, f32)> {
+        dash_outline(&outline, &[4.0, 4.0], offset).contours().iter()
+            .map(|c| (c.bounds().min_x().round(), c.bounds().max_x().round()))
+            .collect()
+    };
+    assert_eq!(dashes(0.0), [(0., 4.), (8., 12.), (16., 20.)]);
+    // half a period later, the dashes are where the gaps were
+    assert_eq!(dashes(4.0), [(4., 8.), (12., 16.)]);
+    assert_eq!(dashes(-4.0), dashes(4.0));
+    assert_eq!(dashes(-20.0), dashes(4.0));
+}
@@ -1,33 +1,75 @@
 use crate::prelude::*;
 use pathfinder_content::{
-    outline::{Outline},
+    outline::{Outline, Contour},
     stroke::{OutlineStrokeToFill, StrokeStyle, LineCap, LineJoin},
     fill::{FillRule},
-    dash::OutlineDash,
+    effects::BlendMode,
 };
 use pathfinder_renderer::{
-    scene::{Scene, DrawPath, ClipPath, ClipPathId},
+    scene::{Scene, DrawPath, DrawPathId, ClipPath, ClipPathId},
     paint::Paint as PaPaint,
 };
 use pathfinder_color::ColorU;
 use svgtypes::{Length};
 use std::sync::Arc;
 use crate::gradient::BuildGradient;
+use crate::pattern::BuildPattern;
+use crate::dash::dash_outline;
+use crate::marker::marker_vertices;
 #[cfg(feature="text")]
 use crate::text::{FontCache};
 use isolang::Language;
 #[cfg(feature="text")]
 use svg_text::FontCollection;
 use std::rc::Rc;
-use std::borrow::Cow;
 use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+use crate::flatten::{Recorder, flatten_curves};
+use crate::path::clip_outline;
+use crate::cache::{SceneCache, ContextKey};
+use std::fmt;
+
+/// callback that loads the bytes of an external `href`, see `DrawContext::set_href_resolver`
+#[derive(Clone)]
+pub struct HrefResolver(Arc<Mutex<dyn FnMut(&str) -> Option<Vec<u8>> + Send>>);
+impl fmt::Debug for HrefResolver {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HrefResolver")
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct DrawContext<'a> {
     pub svg: &'a Svg,
 
+    /// pixels per inch, used for physical units. 75 unless set with `set_dpi`.
     pub dpi: f32,
 
+    /// maximum nesting depth of elements (including `<use>` indirections) that is drawn.
+    /// Deeper content is skipped with a warning instead of overflowing the stack.
+    pub max_depth: usize,
+
+    /// font size of the root element, used as the initial font size and for `rem`
+    pub root_font_size: f32,
+
+    /// if set, `compose_with_options` returns a copy of a previously composed scene
+    /// for the same transform, time and dpi instead of composing it again
+    pub scene_cache: Option<Arc<SceneCache>>,
+
+    /// draw text as a box of its approximate size when the `text` feature is disabled, instead of skipping it
+    pub text_placeholders: bool,
+
+    /// languages of the user (BCP 47 tags), in order of preference. `systemLanguage` is matched against them.
+    pub languages: Vec<String>,
+
+    /// loads external resources (`href`s that do not point into the document). Without it, they are not drawn.
+    pub href_resolver: Option<HrefResolver>,
+
+    /// if set, curves are flattened into lines that stay within this distance (in scene pixels) of them
+    /// before they reach the scene, instead of being flattened by pathfinder with its fixed tolerance.
+    /// Larger values are faster, smaller ones avoid visible corners on large curves.
+    pub flattening_tolerance: Option<f32>,
+
     #[cfg(feature="text")]
     pub font_cache: Option<FontCache<'a>>,
 }
@@ -36,6 +78,13 @@ impl<'a> DrawContext<'a> {
         DrawContext {
             svg,
             dpi: 75.0,
+            max_depth: 256,
+            root_font_size: 16.0,
+            scene_cache: None,
+            text_placeholders: false,
+            languages: vec!["en".into()],
+            href_resolver: None,
+            flattening_tolerance: None,
 
             #[cfg(feature="text")]
             font_cache: None
@@ -47,10 +96,39 @@ impl<'a> DrawContext<'a> {
         DrawContext {
             svg,
             dpi: 75.0,
+            max_depth: 256,
+            root_font_size: 16.0,
+            scene_cache: None,
+            text_placeholders: false,
+            languages: vec!["en".into()],
+            href_resolver: None,
+            flattening_tolerance: None,
 
             font_cache: Some(FontCache::new(fallback_fonts)),
         }
     }
+    /// set the resolution that physical units (`in`, `cm`, `mm`, `pt` and `pc`) are resolved with, in pixels per inch
+    pub fn set_dpi(&mut self, dpi: f32) {
+        self.dpi = dpi;
+    }
+    /// flatten curves into lines with at most `tolerance` pixels of error, see `flattening_tolerance`
+    pub fn set_flattening_tolerance(&mut self, tolerance: f32) {
+        self.flattening_tolerance = Some(tolerance);
+    }
+    /// set the callback that loads external `href`s (like the file of an `<image>`).
+    /// It is called with the `href` as written in the document and returns the content, or `None` if it can't be loaded.
+    pub fn set_href_resolver(&mut self, resolver: impl FnMut(&str) -> Option<Vec<u8>> + Send + 'static) {
+        self.href_resolver = Some(HrefResolver(Arc::new(Mutex::new(resolver))));
+    }
+    /// load the content of an external `href` with the resolver. References into the document are not passed to it.
+    pub fn load_href(&self, href: &str) -> Option<Vec<u8>> {
+        if href.starts_with("#") {
+            return None;
+        }
+        let HrefResolver(ref resolver) = *self.href_resolver.as_ref()?;
+        let mut resolver = resolver.lock().unwrap();
+        (&mut *resolver)(href)
+    }
     pub fn resolve(&self, id: &str) -> Option<&Arc<Item>> {
         self.svg.named_items.get(id)
     }
@@ -72,7 +150,66 @@ impl<'a> DrawContext<'a> {
         self.compose_with_options(&options)
     }
 
+    /// compose the document with the given options.
+    ///
+    /// With a `scene_cache`, the key is the document, the settings of the context and the transform and time of `options`.
+    /// Other changes to the options are not noticed. Recording options are never cached.
     pub fn compose_with_options(&'a self, options: &DrawOptions) -> Scene {
+        match self.scene_cache {
+            Some(ref cache) if options.recorder.is_none() => {
+                cache.get_or_compose(self.cache_key(), &self.svg.root, options.transform, options.time, || self.compose_uncached(options))
+            }
+            _ => self.compose_uncached(options)
+        }
+    }
+
+    fn cache_key(&self) -> ContextKey {
+        #[cfg(feature="text")]
+        let fonts = self.font_cache.as_ref().map(|cache| cache.fallback_address());
+        #[cfg(not(feature="text"))]
+        let fonts = None;
+        ContextKey {
+            root: Arc::as_ptr(&self.svg.root) as usize,
+            dpi: self.dpi.to_bits(),
+            root_font_size: self.root_font_size.to_bits(),
+            max_depth: self.max_depth,
+            text_placeholders: self.text_placeholders,
+            languages: self.languages.clone(),
+            flattening_tolerance: self.flattening_tolerance.map(f32::to_bits),
+            href_resolver: self.href_resolver.as_ref().map(|HrefResolver(resolver)| Arc::as_ptr(resolver) as *const u8 as usize),
+            fonts,
+        }
+    }
+
+    /// compose the document at `seconds` after the start of the animations.
+    ///
+    /// Equivalent to `compose` at `time` set to `Time::from_seconds(seconds)`.
+    pub fn compose_at_secs(&'a self, seconds: f32) -> Scene {
+        let mut options = DrawOptions::new(self);
+        options.set_transform(Transform2F::default());
+        options.time = Time::from_seconds(seconds as f64);
+        self.compose_with_options(&options)
+    }
+
+    /// like `compose`, but only with the shapes whose bounds intersect `region` (in the user space of the root).
+    ///
+    /// Meant for partial redraws: shapes outside of `region` are skipped entirely.
+    /// Content with unknown bounds and content that filters or markers may move into `region` is kept.
+    /// The `scene_cache` is not used.
+    pub fn compose_region(&'a self, region: RectF) -> Scene {
+        self.compose_region_with_transform(Transform2F::default(), region)
+    }
+
+    /// like `compose_with_transform`, but culled to `region` like `compose_region`.
+    /// `region` is still given in the user space of the root, before `transform` is applied.
+    pub fn compose_region_with_transform(&'a self, transform: Transform2F, region: RectF) -> Scene {
+        let mut options = DrawOptions::new(self);
+        options.set_transform(transform);
+        options.cull_region = Some(transform * region);
+        self.compose_uncached(&options)
+    }
+
+    fn compose_uncached(&'a self, options: &DrawOptions) -> Scene {
         let mut scene = Scene::new();
         
         if let Some(vb) = self.view_box() {
@@ -90,32 +227,146 @@ impl<'a> DrawContext<'a> {
         scene
     }
 
+    /// like `compose_with_viewbox`, but fit the document into `view_box` as described by `aspect`.
+    ///
+    /// With `slice`, the content outside of `view_box` is clipped.
+    pub fn compose_with_viewbox_aspect(&'a self, view_box: RectF, aspect: AspectRatio) -> Scene {
+        let mut options = DrawOptions::new(self);
+        let mut scene = Scene::new();
+        scene.set_view_box(options.transform * view_box);
+        if let Some(content) = self.view_box() {
+            if aspect.slice {
+                options.clip_to_rect(&mut scene, view_box);
+            }
+            options.apply_transform(view_box_transform(content, view_box, &aspect));
+        }
+        self.svg.root.draw_to(&mut scene, &options);
+        scene
+    }
+
+    /// compose only the top-level group with the given id or `inkscape:label`.
+    ///
+    /// Returns `None` if there is no such layer.
+    pub fn compose_layer(&'a self, layer_id: &str) -> Option<Scene> {
+        let root = match *self.svg.root {
+            Item::Svg(ref svg) => svg,
+            _ => return None
+        };
+        let is_layer = |item: &Item| match *item {
+            Item::G(ref g) => g.id() == Some(layer_id) || g.label.as_deref() == Some(layer_id),
+            _ => false
+        };
+        if !root.items.iter().any(|item| is_layer(item)) {
+            return None;
+        }
+
+        let mut options = DrawOptions::new(self);
+        options.set_transform(Transform2F::default());
+        let mut scene = Scene::new();
+        if let Some(vb) = self.view_box() {
+            scene.set_view_box(options.transform * vb);
+        }
+        root.draw_filtered(&mut scene, &options, is_layer);
+        Some(scene)
+    }
+
+    /// compose the document into a flat list of resolved draw commands, in the order they are drawn.
+    /// The outlines are in the coordinates of `compose`, the user space of the root.
+    ///
+    /// Content that is rendered through filters, masks or group opacity layers is not included.
+    pub fn flatten(&'a self) -> Vec<FlatDrawCmd> {
+        let recorder = Arc::new(Mutex::new(Recorder::default()));
+        let mut options = DrawOptions::new(self);
+        options.set_transform(Transform2F::default());
+        options.recorder = Some(recorder.clone());
+        self.compose_with_options(&options);
+        drop(options);
+
+        Recorder::unwrap_shared(recorder).into_cmds()
+    }
+
+    /// like `compose`, and map the draw paths of the scene to the id of the innermost element they belong to.
+    ///
+    /// Draw paths of elements without an id (and no ancestor with one) are not included,
+    /// neither is content that is rendered through filters or masks.
+    pub fn compose_with_element_ids(&'a self) -> (Scene, Vec<(DrawPathId, String)>) {
+        let recorder = Arc::new(Mutex::new(Recorder::default()));
+        let mut options = DrawOptions::new(self);
+        options.set_transform(Transform2F::default());
+        options.recorder = Some(recorder.clone());
+        let scene = self.compose_with_options(&options);
+        drop(options);
+
+        (scene, Recorder::unwrap_shared(recorder).into_element_ids())
+    }
+
+    /// draw the document into `scene`, after what it already contains.
+    ///
+    /// There is no variant that replaces the content: pathfinder's `Scene` can't be cleared in place,
+    /// so reusing one would not save any allocations over composing a new scene.
     pub fn compose_to_with_transform(&'a self, scene: &mut Scene, transform: Transform2F) {
         let mut options = DrawOptions::new(self);
         options.transform = transform;
         self.svg.root.draw_to(scene, &options);
     }
 
-    /// get the viewbox (computed if missing)
+    /// get the viewbox in user units (computed if missing).
+    ///
+    /// Without a `viewBox`, the `width` and `height` of the root are used,
+    /// and without those the bounds of the content.
+    /// A declared `viewBox` is used even if the content is invisible (and has no bounds).
     pub fn view_box(&'a self) -> Option<RectF> {
-        let options = BoundsOptions::new(self);
-        
-        if let Item::Svg(TagSvg { view_box: Some(r), width, height, .. }) = &*self.svg.root {
-            if let Some(size) = Vector(
-                width.unwrap_or(r.width),
-                height.unwrap_or(r.height)
-            ).try_resolve(&options) {
+        let mut options = BoundsOptions::new(self);
+        // callers apply their own transform to the result
+        options.transform = Transform2F::default();
+
+        if let Item::Svg(TagSvg { view_box, width, height, .. }) = &*self.svg.root {
+            let size = match (view_box, width, height) {
+                // relative sizes (like `100%`) can't be resolved here, the size of the view box is used instead
+                (Some(r), ..) => Vector(width.unwrap_or(r.width), height.unwrap_or(r.height)).try_resolve(&options)
+                    .or_else(|| Vector(r.width, r.height).try_resolve(&options)),
+                (None, &Some(width), &Some(height)) => Vector(width, height).try_resolve(&options),
+                _ => None
+            };
+            if let Some(size) = size {
                 return Some(RectF::new(Vector2F::zero(), size));
             }
         }
         self.svg.root.bounds(&options)
     }
+
+    /// the transform from the user space of the root element (the coordinates inside its `viewBox`)
+    /// to the output of `compose`, including the `transform` of the root.
+    pub fn viewbox_transform(&'a self) -> Transform2F {
+        let mut options = Options::new(self);
+        options.set_transform(Transform2F::default());
+        if let Item::Svg(ref svg) = *self.svg.root {
+            options = options.apply(&svg.attrs);
+            if let Some(ref view_box) = svg.view_box {
+                options.apply_viewbox(svg.width, svg.height, view_box, svg.preserve_aspect_ratio.as_ref());
+            }
+        }
+        options.transform
+    }
+
+    /// the inverse of `viewbox_transform`: maps points of the output (like the position of a click) into the document
+    pub fn viewbox_transform_inverse(&'a self) -> Transform2F {
+        self.viewbox_transform().inverse()
+    }
+}
+
+/// a pattern that only has zero lengths (or a negative one, which is an error) is drawn as a solid stroke
+fn draws_dashes(dashes: &[f32]) -> bool {
+    dashes.iter().all(|&d| d >= 0.) && dashes.iter().any(|&d| d > 0.)
 }
 
 #[derive(Clone, Debug)]
 pub struct Options<'a> {
     pub ctx: &'a DrawContext<'a>,
 
+    /// the `color` property. `currentColor` in `fill` and `stroke` is already replaced by it.
+    pub color: Color,
+
     pub fill: Paint,
     pub fill_rule: FillRule,
     pub fill_opacity: f32,
@@ -125,8 +376,23 @@ pub struct Options<'a> {
     pub stroke_opacity: f32,
     pub stroke_dasharray: Option<Rc<[f32]>>,
     pub stroke_dashoffset: f32,
+    pub stroke_linejoin: StrokeLineJoin,
+    pub stroke_miterlimit: f32,
+    pub paint_order: PaintOrder,
+    pub marker_start: Option<&'a TagMarker>,
+    pub marker_mid: Option<&'a TagMarker>,
+    pub marker_end: Option<&'a TagMarker>,
 
+    /// product of the `opacity` of this element and all its ancestors.
+    /// It is multiplied into the paint exactly once, in `resolve_paint`, on top of `fill-opacity`/`stroke-opacity`.
+    /// Anything that composites a group as a layer has to reset it to 1 for the content of that layer.
     pub opacity: f32,
+    pub visibility: Visibility,
+    /// `mix-blend-mode`. Without isolated groups, the paths of a blended group are blended individually.
+    pub blend_mode: BlendMode,
+    /// working color space of filters applied to this element.
+    /// Inherited; `apply_filter` runs the primitives in linearRGB unless this is sRGB.
+    pub color_interpolation_filters: ColorInterpolation,
 
     pub transform: Transform2F,
 
@@ -137,39 +403,91 @@ pub struct Options<'a> {
     pub time: Time,
 
     pub font_size: f32,
+    pub font_variant: FontVariant,
+    pub dominant_baseline: DominantBaseline,
+    /// the `alignment-baseline` of the element itself, which overrides the `dominant_baseline` for its text
+    pub alignment_baseline: Option<DominantBaseline>,
+    pub text_decoration: TextDecoration,
+    pub text_decoration_style: TextDecorationStyle,
     pub direction: TextFlow,
+    pub white_space: WhiteSpace,
+    pub text_anchor: TextAnchor,
 
     pub lang: Option<Language>,
+
+    /// number of elements applied so far
+    pub depth: usize,
 }
 impl<'a> Options<'a> {
     pub fn new(ctx: &'a DrawContext<'a>) -> Options<'a> {
         Options {
             ctx,
             opacity: 1.0,
+            visibility: Visibility::Visible,
+            blend_mode: BlendMode::SrcOver,
+            color_interpolation_filters: ColorInterpolation::LinearRGB,
+            color: Color::black(),
             fill: Paint::black(),
-            fill_rule: FillRule::EvenOdd,
+            fill_rule: FillRule::Winding,
             fill_opacity: 1.0,
             stroke: Paint::None,
             stroke_opacity: 1.0,
             stroke_style: StrokeStyle {
                 line_width: 1.0,
                 line_cap: LineCap::Butt,
-                line_join: LineJoin::Bevel,
+                line_join: LineJoin::Miter(4.0),
             },
             stroke_dasharray: None,
             stroke_dashoffset: 0.0,
+            stroke_linejoin: StrokeLineJoin::Miter,
+            stroke_miterlimit: 4.0,
+            paint_order: PaintOrder::normal(),
+            marker_start: None,
+            marker_mid: None,
+            marker_end: None,
             transform: Transform2F::from_scale(10.),
-            clip_rule: FillRule::EvenOdd,
+            clip_rule: FillRule::Winding,
             view_box: None,
             time: Time::start(),
-            font_size: 20.,
+            font_size: ctx.root_font_size,
+            font_variant: FontVariant::Normal,
+            dominant_baseline: DominantBaseline::Auto,
+            alignment_baseline: None,
+            text_decoration: TextDecoration::default(),
+            text_decoration_style: TextDecorationStyle::Solid,
             direction: TextFlow::LeftToRight,
+            white_space: WhiteSpace::Normal,
+            text_anchor: TextAnchor::Start,
             lang: None,
+            depth: 0,
+        }
+    }
+    fn resolve_marker(&self, attr: &Option<MarkerAttr>, inherited: Option<&'a TagMarker>) -> Option<&'a TagMarker> {
+        match *attr {
+            None => inherited,
+            Some(MarkerAttr::None) => None,
+            Some(MarkerAttr::Ref(ref id)) => match self.ctx.resolve(id).map(|item| &**item) {
+                Some(Item::Marker(ref marker)) => Some(marker),
+                r => {
+                    warn!("expected marker for {:?}, got {:?}", id, r);
+                    None
+                }
+            }
+        }
+    }
+    /// true (and logs a warning) if the nesting depth exceeds `DrawContext::max_depth`
+    pub fn too_deep(&self) -> bool {
+        if self.depth > self.ctx.max_depth {
+            warn!("maximum nesting depth of {} exceeded", self.ctx.max_depth);
+            true
+        } else {
+            false
         }
     }
     pub fn has_stroke(&self) -> bool {
         self.opacity > 0.0 &&
         self.stroke_opacity > 0. &&
+        self.stroke_style.line_width > 0. &&
         !matches!(self.stroke, Paint::None)
     }
     pub fn has_fill(&self) -> bool {
@@ -191,30 +509,69 @@ impl<'a> Options<'a> {
         if let Some(length) = attrs.stroke_width.resolve(self) {
             stroke_style.line_width = length;
         }
+        if let Some(cap) = attrs.stroke_linecap {
+            stroke_style.line_cap = match cap {
+                StrokeLineCap::Butt => LineCap::Butt,
+                StrokeLineCap::Round => LineCap::Round,
+                StrokeLineCap::Square => LineCap::Square,
+            };
+        }
+        let stroke_linejoin = attrs.stroke_linejoin.unwrap_or(self.stroke_linejoin);
+        let stroke_miterlimit = attrs.stroke_miterlimit.unwrap_or(self.stroke_miterlimit);
+        stroke_style.line_join = line_join(stroke_linejoin, stroke_miterlimit);
+        // `currentColor` refers to the `color` of the element itself, not of the parent
+        let color = attrs.color.unwrap_or(self.color);
         Options {
             clip_rule: attrs.clip_rule.unwrap_or(self.clip_rule),
-            opacity: attrs.opacity.resolve(self).unwrap_or(1.0),
-            transform: self.transform * attrs.transform.resolve(self),
-            fill: attrs.fill.resolve(self),
+            opacity: self.opacity * attrs.opacity.resolve(self).unwrap_or(1.0),
+            visibility: attrs.visibility.unwrap_or(self.visibility),
+            blend_mode: attrs.mix_blend_mode.unwrap_or(self.blend_mode),
+            color_interpolation_filters: attrs.color_interpolation_filters.unwrap_or(self.color_interpolation_filters),
+            // the motion is applied in the parent coordinate system, on top of the `transform` attribute
+            transform: self.transform
+                * attrs.motion.as_ref().and_then(|m| m.resolve(self)).unwrap_or_default()
+                * attrs.transform.resolve(self),
+            color,
+            fill: attrs.fill.resolve(self).with_current_color(color),
             fill_rule: attrs.fill_rule.unwrap_or(self.fill_rule),
             fill_opacity: attrs.fill_opacity.resolve(self).unwrap_or(self.fill_opacity),
-            stroke: attrs.stroke.resolve(self),
+            stroke: attrs.stroke.resolve(self).with_current_color(color),
             stroke_style,
             stroke_opacity: attrs.stroke_opacity.resolve(self).unwrap_or(self.stroke_opacity),
-            stroke_dasharray: attrs.stroke_dasharray.resolve(self),
+            stroke_dasharray: match attrs.stroke_dasharray.resolve(self) {
+                Some(dashes) => Some(dashes).filter(|dashes| draws_dashes(dashes)),
+                None => self.stroke_dasharray.clone(),
+            },
+            stroke_dashoffset: attrs.stroke_dashoffset.resolve(self).unwrap_or(self.stroke_dashoffset),
+            stroke_linejoin,
+            stroke_miterlimit,
+            paint_order: attrs.paint_order.unwrap_or(self.paint_order),
+            marker_start: self.resolve_marker(&attrs.marker_start, self.marker_start),
+            marker_mid: self.resolve_marker(&attrs.marker_mid, self.marker_mid),
+            marker_end: self.resolve_marker(&attrs.marker_end, self.marker_end),
             direction: attrs.direction.unwrap_or(self.direction),
+            white_space: attrs.white_space.unwrap_or(self.white_space),
+            text_anchor: attrs.text_anchor.unwrap_or(self.text_anchor),
             font_size: attrs.font_size.resolve(self).unwrap_or(self.font_size),
+            font_variant: attrs.font_variant.unwrap_or(self.font_variant),
+            dominant_baseline: attrs.dominant_baseline.unwrap_or(self.dominant_baseline),
+            alignment_baseline: attrs.alignment_baseline,
+            text_decoration: attrs.text_decoration.map_or(self.text_decoration, |d| d.union(self.text_decoration)),
+            text_decoration_style: attrs.text_decoration_style.unwrap_or(self.text_decoration_style),
             lang: attrs.lang.or(self.lang),
+            depth: self.depth + 1,
             .. *self
         }
     }
-    fn resolve_paint(&self, paint: &Paint, opacity: f32) -> Option<PaPaint> {
+    fn resolve_paint(&self, scene: &mut Scene, paint: &Paint, opacity: f32, bounds: RectF) -> Option<PaPaint> {
         let opacity = opacity * self.opacity;
         match *paint {
             Paint::Color(ref c) => Some(PaPaint::from_color(c.color_u(opacity))),
+            Paint::CurrentColor => Some(PaPaint::from_color(self.color.color_u(opacity))),
             Paint::Ref(ref id) => match self.ctx.svg.named_items.get(id).map(|arc| &**arc) {
-                Some(Item::LinearGradient(ref gradient)) => Some(PaPaint::from_gradient(gradient.build(self, opacity))),
-                Some(Item::RadialGradient(ref gradient)) => Some(PaPaint::from_gradient(gradient.build(self, opacity))),
+                Some(Item::LinearGradient(ref gradient)) => Some(PaPaint::from_gradient(gradient.build(self, opacity, bounds))),
+                Some(Item::RadialGradient(ref gradient)) => Some(PaPaint::from_gradient(gradient.build(self, opacity, bounds))),
+                Some(Item::Pattern(ref pattern)) => pattern.build(scene, self, opacity, bounds),
                 r => {
                     dbg!(id, r);
                     None
@@ -223,54 +580,203 @@ impl<'a> Options<'a> {
             _ => None
         }
     }
-    pub fn resolve_length(&self, length: Length) -> Option<f32> {
-        let scale = match length.unit {
+    /// user units per unit of the absolute and font relative lengths, `None` for percentages.
+    /// This is at most a multiplication, which is not worth memoizing.
+    fn unit_scale(&self, unit: LengthUnit) -> Option<f32> {
+        Some(match unit {
             LengthUnit::None => 1.0,
             LengthUnit::Cm => self.ctx.dpi * (1.0 / 2.54),
-            LengthUnit::Em => unimplemented!(),
-            LengthUnit::Ex => unimplemented!(),
+            LengthUnit::Em => self.font_size,
+            // no x-height is available here, so use the common approximation of half an em
+            LengthUnit::Ex => 0.5 * self.font_size,
             LengthUnit::In => self.ctx.dpi,
             LengthUnit::Mm => self.ctx.dpi * (1.0 / 25.4),
-            LengthUnit::Pc => unimplemented!(),
+            // a pica is 12 points
+            LengthUnit::Pc => self.ctx.dpi * (12.0 / 75.),
             LengthUnit::Percent => return None,
             LengthUnit::Pt => self.ctx.dpi * (1.0 / 75.),
             LengthUnit::Px => 1.0
+        })
+    }
+    /// resolve a length that is not bound to an axis (like `stroke-width`) to user units.
+    ///
+    /// Returns `None` for percentages without a view box.
+    pub fn resolve_length(&self, length: Length) -> Option<f32> {
+        let scale = match length.unit {
+            // not bound to an axis: a percentage of the normalized diagonal of the viewport.
+            // Without a view box, there is nothing to refer to and the length can't be resolved.
+            LengthUnit::Percent => 0.01 * self.view_box?.size().length() * std::f32::consts::FRAC_1_SQRT_2,
+            unit => self.unit_scale(unit)?
         };
         Some(length.num as f32 * scale)
     }
     pub fn resolve_length_along(&self, length: Length, axis: Axis) -> Option<f32> {
         let scale = match length.unit {
-            LengthUnit::None => 1.0,
-            LengthUnit::Cm => self.ctx.dpi * (1.0 / 2.54),
-            LengthUnit::Em => unimplemented!(),
-            LengthUnit::Ex => unimplemented!(),
-            LengthUnit::In => self.ctx.dpi,
-            LengthUnit::Mm => self.ctx.dpi * (1.0 / 25.4),
-            LengthUnit::Pc => unimplemented!(),
-            LengthUnit::Percent => return match axis {
-                Axis::X => self.view_box.map(|r| r.width() * 0.01),
-                Axis::Y => self.view_box.map(|r| r.height() * 0.01),
+            LengthUnit::Percent => match axis {
+                Axis::X => 0.01 * self.view_box?.width(),
+                Axis::Y => 0.01 * self.view_box?.height(),
             },
-            LengthUnit::Pt => self.ctx.dpi * (1.0 / 75.),
-            LengthUnit::Px => 1.0
+            unit => self.unit_scale(unit)?
         };
         Some(length.num as f32 * scale)
     }
-    pub fn apply_viewbox(&mut self, width: Option<LengthX>, height: Option<LengthY>, view_box: &Rect) {
+    /// the viewport of an element with the given `width` and `height`, which default to the size of the view box
+    pub fn viewport(&self, width: Option<LengthX>, height: Option<LengthY>, view_box: &Rect) -> RectF {
         let view_box = view_box.resolve(self);
         let width = width.and_then(|l| l.try_resolve(self)).unwrap_or(view_box.width());
         let height = height.and_then(|l| l.try_resolve(self)).unwrap_or(view_box.height());
-        let size = vec2f(width, height);
-        
-        self.apply_transform(Transform2F::from_scale(view_box.size().recip() * size) * Transform2F::from_translation(-view_box.origin()));
+        RectF::new(Vector2F::zero(), vec2f(width, height))
+    }
+    pub fn apply_viewbox(&mut self, width: Option<LengthX>, height: Option<LengthY>, view_box: &Rect, aspect: Option<&AspectRatio>) {
+        let viewport = self.viewport(width, height, view_box);
+        let view_box = view_box.resolve(self);
+        let aspect = aspect.cloned().unwrap_or_else(default_aspect_ratio);
+
+        self.apply_transform(view_box_transform(view_box, viewport, &aspect));
         self.view_box = Some(view_box);
     }
 }
 
+/// map the SVG join onto the joins pathfinder knows about.
+/// pathfinder has no `arcs` join, so it falls back to `miter` as the spec demands.
+/// `miter-clip` is drawn as `miter`, which bevels once the limit is exceeded; `clipped_miters` adds the part beyond the bevel.
+fn line_join(join: StrokeLineJoin, miter_limit: f32) -> LineJoin {
+    match join {
+        StrokeLineJoin::Miter | StrokeLineJoin::MiterClip | StrokeLineJoin::Arcs => LineJoin::Miter(miter_limit),
+        StrokeLineJoin::Round => LineJoin::Round,
+        StrokeLineJoin::Bevel => LineJoin::Bevel,
+    }
+}
+/// the parts of the `miter-clip` joins of `outline` (in user space) beyond the bevel:
+/// where the miter exceeds `miter_limit`, it is cut off at `miter_limit * half_width` from the vertex.
+fn clipped_miters(outline: &Outline, half_width: f32, miter_limit: f32) -> Outline {
+    let mut miters = Outline::new();
+    for contour in outline.contours() {
+        let mut single = Outline::new();
+        single.push_contour(contour.clone());
+        let vertices = marker_vertices(&single);
+        let mut joins: Vec<_> = vertices.iter().filter_map(|v| Some((v.point, v.incoming?, v.outgoing?))).collect();
+        if contour.is_closed() {
+            if let (Some(first), Some(last)) = (vertices.first(), vertices.last()) {
+                joins.extend(last.incoming.and_then(|incoming| Some((first.point, incoming, first.outgoing?))));
+            }
+        }
+        for (point, incoming, outgoing) in joins {
+            if let Some(miter) = clipped_miter(point, incoming, outgoing, half_width, miter_limit) {
+                miters.push_contour(miter);
+            }
+        }
+    }
+    miters
+}
+
+/// the polygon between the bevel of the join at `point` and the line cutting off its miter,
+/// or `None` if the miter is within the limit (and drawn by pathfinder) or there is no join
+fn clipped_miter(point: Vector2F, incoming: Vector2F, outgoing: Vector2F, half_width: f32, miter_limit: f32) -> Option<Contour> {
+    if incoming.square_length() == 0.0 || outgoing.square_length() == 0.0 {
+        return None;
+    }
+    let a = incoming * (1.0 / incoming.length());
+    let b = outgoing * (1.0 / outgoing.length());
+    // the miter points along `a - b`, away from the inside of the corner
+    let m = a - b;
+    if m.square_length() < 1e-12 {
+        return None;
+    }
+    let m = m * (1.0 / m.length());
+    // the miter is 1 / sin(θ/2) times the stroke width, θ being the angle between the segments
+    let sin_half = ((1.0 + a.dot(b)) * 0.5).max(0.0).sqrt();
+    if sin_half * miter_limit >= 1.0 {
+        return None;
+    }
+    let outer = |d: Vector2F| {
+        let n = vec2f(-d.y(), d.x());
+        if n.dot(m) < 0.0 { -n } else { n }
+    };
+    let (p1, p2) = (point + outer(a) * half_width, point + outer(b) * half_width);
+    // follow the outer edges from the bevel up to the clip line
+    let t = (miter_limit * half_width - (p1 - point).dot(m)) / a.dot(m);
+    if t <= 0.0 {
+        return None;
+    }
+    let mut contour = Contour::new();
+    contour.push_endpoint(p1);
+    contour.push_endpoint(p1 + a * t);
+    contour.push_endpoint(p2 - b * t);
+    contour.push_endpoint(p2);
+    contour.close();
+    Some(contour)
+}
+
+#[test]
+fn test_miter_clip() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <path d="M0,0 L10,1 L0,2" fill="none" stroke="black" stroke-linejoin="miter-clip"/>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 1);
+    // the bevel ends at about 10.05 and the full miter at about 15, the clipped one at 10 + 4 * 0.5
+    let max_x = cmds[0].outline.bounds().max_x();
+    assert!(max_x > 11.9 && max_x < 12.01, "{}", max_x);
+}
+
+#[test]
+fn test_default_miter_limit() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <path id="p" d="M0,0 L10,1 L0,2" stroke="black" stroke-miterlimit="0.5"/>
+    </svg>"#).unwrap();
+    let attrs = attrs!(svg, "p");
+    let ctx = DrawContext::new_without_fonts(&svg);
+    // the invalid limit is ignored, so the sharp corner is beveled beyond the default of 4
+    let options = Options::new(&ctx).apply(attrs);
+    assert!(matches!(options.stroke_style.line_join, LineJoin::Miter(limit) if limit == 4.0));
+
+    // the miter would reach to about x = 15, the bevel ends just past the vertex at x = 10
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 2);
+    let max_x = cmds[1].outline.bounds().max_x();
+    assert!(max_x > 10.0 && max_x < 10.5, "{}", max_x);
+}
+
+/// the transform that fits `view_box` into `viewport` according to `aspect`
+pub fn view_box_transform(view_box: RectF, viewport: RectF, aspect: &AspectRatio) -> Transform2F {
+    let scale = viewport.size() * view_box.size().recip();
+    let (align_x, align_y) = match aspect.align {
+        Align::None => return Transform2F::from_translation(viewport.origin())
+            * Transform2F::from_scale(scale)
+            * Transform2F::from_translation(-view_box.origin()),
+        Align::XMinYMin => (0.0, 0.0),
+        Align::XMidYMin => (0.5, 0.0),
+        Align::XMaxYMin => (1.0, 0.0),
+        Align::XMinYMid => (0.0, 0.5),
+        Align::XMidYMid => (0.5, 0.5),
+        Align::XMaxYMid => (1.0, 0.5),
+        Align::XMinYMax => (0.0, 1.0),
+        Align::XMidYMax => (0.5, 1.0),
+        Align::XMaxYMax => (1.0, 1.0),
+    };
+    // meet fits the whole view box into the viewport, slice covers the viewport entirely
+    let scale = match aspect.slice {
+        false => scale.x().min(scale.y()),
+        true => scale.x().max(scale.y()),
+    };
+    let offset = (viewport.size() - view_box.size() * scale) * vec2f(align_x, align_y);
+    Transform2F::from_translation(viewport.origin() + offset)
+        * Transform2F::from_scale(Vector2F::splat(scale))
+        * Transform2F::from_translation(-view_box.origin())
+}
+
 #[derive(Clone, Debug)]
 pub struct DrawOptions<'a> {
     pub common: Options<'a>,
     pub clip_path: Option<(RectF, ClipPathId)>, //ClipPathAttr,
+
+    /// if set, everything pushed to the scene is recorded here as well (see `DrawContext::flatten`)
+    pub recorder: Option<Arc<Mutex<Recorder>>>,
+
+    /// if set, shapes whose bounds (in scene coordinates) do not intersect this rect are skipped (see `DrawContext::compose_region`)
+    pub cull_region: Option<RectF>,
 }
 impl<'a> Deref for DrawOptions<'a> {
     type Target = Options<'a>;
@@ -312,11 +818,16 @@ impl<'a> BoundsOptions<'a> {
         let clip_rect = match attrs.clip_path {
             Some(ClipPathAttr::Ref(ref id)) => {
                 if let Some(Item::ClipPath(p)) = self.ctx.resolve(id).map(|t| &**t) {
-                    let outline = p.resolve(&self);
-                    let inner_rect = outline.bounds();
-                    match self.clip_rect {
-                        None => Some(inner_rect),
-                        Some(outer_rect) => outer_rect.intersection(inner_rect),
+                    // the bounding box of the element is not known here, so objectBoundingBox clips are not included
+                    match clip_outline(p, &common, None) {
+                        Some(outline) => {
+                            let inner_rect = outline.bounds();
+                            match self.clip_rect {
+                                None => Some(inner_rect),
+                                Some(outer_rect) => outer_rect.intersection(inner_rect),
+                            }
+                        }
+                        None => self.clip_rect
                     }
                 } else {
                     println!("clip path missing: {}", id);
@@ -347,7 +858,9 @@ impl<'a> DrawOptions<'a> {
     pub fn new(ctx: &'a DrawContext<'a>) -> DrawOptions<'a> {
         DrawOptions {
             common: Options::new(ctx),
-            clip_path: None
+            clip_path: None,
+            recorder: None,
+            cull_region: None,
         }
     }
     pub fn debug_outline(&self, scene: &mut Scene, path: &Outline, color: ColorU) {
@@ -359,44 +872,107 @@ impl<'a> DrawOptions<'a> {
         self.draw_transformed(scene, path, Transform2F::default());
     }
     pub fn draw_transformed(&self, scene: &mut Scene, path: &Outline, transform: Transform2F) {
+        if !self.visibility.is_visible() {
+            return;
+        }
         let tr = self.transform * transform;
-        let clip_path_id = self.clip_path.map(|(_, id)| id);
-        if let Some(ref fill) = self.resolve_paint(&self.fill, self.fill_opacity) {
-            let outline = path.clone().transformed(&tr);
-            let paint_id = scene.push_paint(fill);
-            let mut draw_path = DrawPath::new(outline, paint_id);
-            draw_path.set_fill_rule(self.fill_rule);
-            draw_path.set_clip_path(clip_path_id);
-            scene.push_draw_path(draw_path);
-        }
-        if let Some(ref stroke) = self.resolve_paint(&self.stroke, self.stroke_opacity) {
+        // fast path for the most common case: a solid fill, no stroke, no clip, nothing to record and no flattening
+        if let (Paint::Color(ref color), Paint::None) = (&self.fill, &self.stroke) {
+            if self.clip_path.is_none() && self.recorder.is_none() && self.blend_mode == BlendMode::SrcOver && self.ctx.flattening_tolerance.is_none() {
+                let paint_id = scene.push_paint(&PaPaint::from_color(color.color_u(self.fill_opacity * self.opacity)));
+                let mut draw_path = DrawPath::new(path.clone().transformed(&tr), paint_id);
+                draw_path.set_fill_rule(self.fill_rule);
+                scene.push_draw_path(draw_path);
+                return;
+            }
+        }
+        // markers are drawn by the shapes after this, so they end up on top regardless of `paint-order`
+        if self.paint_order.stroke_below_fill() {
+            self.draw_stroke(scene, path, transform);
+            self.draw_fill(scene, path, transform);
+        } else {
+            self.draw_fill(scene, path, transform);
+            self.draw_stroke(scene, path, transform);
+        }
+    }
+    fn draw_fill(&self, scene: &mut Scene, path: &Outline, transform: Transform2F) {
+        let bounds = transform * path.bounds();
+        if let Some(ref fill) = self.resolve_paint(scene, &self.fill, self.fill_opacity, bounds) {
+            let outline = path.clone().transformed(&(self.transform * transform));
+            self.push_draw_path(scene, outline, fill, self.fill_rule);
+        }
+    }
+    fn draw_stroke(&self, scene: &mut Scene, path: &Outline, transform: Transform2F) {
+        let bounds = transform * path.bounds();
+        if let Some(ref stroke) = self.resolve_paint(scene, &self.stroke, self.stroke_opacity, bounds) {
             if self.stroke_style.line_width > 0. {
-                let paint_id = scene.push_paint(stroke);
-
-                let mut outline = Cow::Borrowed(path);
+                // dashes and the stroke width are in user space, `transform` only places the path in it
+                let mut outline = path.clone().transformed(&transform);
                 if let Some(ref dash) = self.stroke_dasharray {
-                    let mut dash = OutlineDash::new(&path, dash, self.stroke_dashoffset);
-                    dash.dash();
-                    outline = Cow::Owned(dash.into_outline());
+                    outline = dash_outline(&outline, dash, self.stroke_dashoffset);
                 }
-                let mut stroke = OutlineStrokeToFill::new(&outline, self.stroke_style);
-                stroke.offset();
-                let path = stroke.into_outline();
-                let mut draw_path = DrawPath::new(path.transformed(&tr), paint_id);
-                draw_path.set_clip_path(clip_path_id);
-                scene.push_draw_path(draw_path);
+                let mut stroke_to_fill = OutlineStrokeToFill::new(&outline, self.stroke_style);
+                stroke_to_fill.offset();
+                let mut path = stroke_to_fill.into_outline();
+                if self.stroke_linejoin == StrokeLineJoin::MiterClip {
+                    for miter in clipped_miters(&outline, self.stroke_style.line_width * 0.5, self.stroke_miterlimit).contours() {
+                        path.push_contour(miter.clone());
+                    }
+                }
+                self.push_draw_path(scene, path.transformed(&self.transform), stroke, FillRule::Winding);
             }
         }
     }
+    /// push `outline` (in scene coordinates) with the current clip path
+    pub(crate) fn push_draw_path(&self, scene: &mut Scene, outline: Outline, paint: &PaPaint, fill_rule: FillRule) {
+        let outline = match self.ctx.flattening_tolerance {
+            Some(tolerance) => flatten_curves(&outline, tolerance),
+            None => outline
+        };
+        let clip_path_id = self.clip_path.map(|(_, id)| id);
+        let recorded = self.recorder.as_ref().map(|_| outline.clone());
+        let paint_id = scene.push_paint(paint);
+        let mut draw_path = DrawPath::new(outline, paint_id);
+        draw_path.set_fill_rule(fill_rule);
+        draw_path.set_clip_path(clip_path_id);
+        draw_path.set_blend_mode(self.blend_mode);
+        let path_id = scene.push_draw_path(draw_path);
+        if let (Some(ref recorder), Some(outline)) = (&self.recorder, recorded) {
+            recorder.lock().unwrap().push_draw_path(path_id, outline, paint.clone(), fill_rule, clip_path_id);
+        }
+    }
+    /// push `outline` (in scene coordinates) as a clip path, itself clipped by `parent`
+    fn push_clip_path(&self, scene: &mut Scene, outline: Outline, fill_rule: FillRule, parent: Option<ClipPathId>) -> ClipPathId {
+        let recorded = self.recorder.as_ref().map(|_| outline.clone());
+        let mut clip_path = ClipPath::new(outline);
+        clip_path.set_fill_rule(fill_rule);
+        clip_path.set_clip_path(parent);
+        let id = scene.push_clip_path(clip_path);
+        if let (Some(ref recorder), Some(outline)) = (&self.recorder, recorded) {
+            recorder.lock().unwrap().push_clip_path(id, outline, fill_rule, parent);
+        }
+        id
+    }
     pub fn apply(&self, scene: &mut Scene, attrs: &Attrs) -> DrawOptions<'a> {
+        self.apply_with_bbox(scene, attrs, |_| None)
+    }
+    /// like `apply`, with the bounding box of the element in its user space (computed with the applied options),
+    /// which is needed for clip paths with `objectBoundingBox` units.
+    pub fn apply_with_bbox(&self, scene: &mut Scene, attrs: &Attrs, bbox: impl FnOnce(&Options<'a>) -> Option<RectF>) -> DrawOptions<'a> {
         let common = self.common.apply(attrs);
-        dbg!(&attrs.clip_path);
         let clip_path = match attrs.clip_path {
             Some(ClipPathAttr::Ref(ref id)) => {
                 if let Some(Item::ClipPath(p)) = self.ctx.resolve(id).map(|t| &**t) {
-                    let outline = p.resolve(&common);
+                    let outline = match clip_outline(p, &common, bbox(&common)) {
+                        Some(outline) => outline,
+                        // an `objectBoundingBox` clip path of an element without a bounding box hides the element
+                        None => {
+                            warn!("clip path {} needs a bounding box", id);
+                            Outline::new()
+                        }
+                    };
                     let clip_rect = outline.bounds();
-                    println!("{:?}, {:?}, {:?}", p, outline, clip_rect);
+                    debug!("clip path {:?}: {:?}, bounds {:?}", p, outline, clip_rect);
                     // begin debug
                     /*
                     let paint = PaPaint::from_color(ColorU::new(255, 0, 255, 127));
@@ -408,23 +984,18 @@ impl<'a> DrawOptions<'a> {
                     // end debug
 
                     let push_clip_path = |id: Option<ClipPathId>| {
-                        let mut clip_path = ClipPath::new(outline);
-                        clip_path.set_fill_rule(self.clip_rule);
-                        clip_path.set_clip_path(id);
-                        scene.push_clip_path(clip_path)
+                        self.push_clip_path(scene, outline, self.clip_rule, id)
                     };
 
                     if let Some((rect, id)) = self.clip_path {
-                        if let Some(intersection) = rect.intersection(clip_rect) {
-                            Some((intersection, push_clip_path(Some(id))))
-                        } else {
-                            None
-                        }
+                        // disjoint clip paths hide everything, they must not fall back to no clipping at all
+                        let intersection = rect.intersection(clip_rect).unwrap_or_default();
+                        Some((intersection, push_clip_path(Some(id))))
                     } else {
                         Some((clip_rect, push_clip_path(None)))
                     }
                 } else {
-                    println!("clip path missing: {}", id);
+                    warn!("clip path missing: {}", id);
                     None
                 }
             }
@@ -434,7 +1005,19 @@ impl<'a> DrawOptions<'a> {
         debug!("fill {:?} + {:?} -> {:?}", self.fill, attrs.fill, common.fill);
         debug!("stroke {:?} + {:?} -> {:?}", self.stroke, attrs.stroke, common.stroke);
         
-        DrawOptions { common, clip_path: dbg!(clip_path) }
+        DrawOptions { common, clip_path, recorder: self.recorder.clone(), cull_region: self.cull_region }
+    }
+    /// intersect the clip region with `rect`, given in the current user space
+    pub fn clip_to_rect(&mut self, scene: &mut Scene, rect: RectF) {
+        let outline = Outline::from_rect(rect).transformed(&self.transform);
+        let rect = outline.bounds();
+        let id = self.push_clip_path(scene, outline, FillRule::Winding, self.clip_path.map(|(_, id)| id));
+
+        let rect = match self.clip_path {
+            Some((outer, _)) => outer.intersection(rect).unwrap_or_default(),
+            None => rect
+        };
+        self.clip_path = Some((rect, id));
     }
     pub fn bounds_options(&self) -> BoundsOptions<'a> {
         BoundsOptions {
@@ -443,3 +1026,526 @@ impl<'a> DrawOptions<'a> {
         }
     }
 }
+
+#[test]
+fn test_zero_stroke_width_bounds() {
+    let bounds = |rect: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg">{}</svg>"#, rect)).unwrap();
+        DrawContext::new_without_fonts(&svg).view_box()
+    };
+    let stroked = bounds(r#"<rect x="10" y="10" width="20" height="20" stroke="black" stroke-width="0"/>"#);
+    let filled = bounds(r#"<rect x="10" y="10" width="20" height="20"/>"#);
+    assert!(filled.is_some());
+    assert_eq!(stroked, filled);
+}
+
+#[test]
+fn test_view_box_transform_meet() {
+    let aspect = AspectRatio { defer: false, align: Align::XMidYMid, slice: false };
+    let content = RectF::new(Vector2F::zero(), vec2f(100., 100.));
+    let view_box = RectF::new(Vector2F::zero(), vec2f(400., 100.));
+    let tr = view_box_transform(content, view_box, &aspect);
+    assert_eq!(tr * content, RectF::new(vec2f(150., 0.), vec2f(100., 100.)));
+}
+
+#[test]
+fn test_view_box_transform_defer() {
+    let content = RectF::new(Vector2F::zero(), vec2f(100., 50.));
+    let view_box = RectF::new(vec2f(10., 10.), vec2f(300., 300.));
+    let deferred = AspectRatio::parse("defer xMaxYMid slice").unwrap();
+    let plain = AspectRatio::parse("xMaxYMid slice").unwrap();
+    assert!(deferred.defer);
+    assert_eq!(view_box_transform(content, view_box, &deferred), view_box_transform(content, view_box, &plain));
+}
+
+#[test]
+fn test_rem_font_size() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <g id="g" font-size="10"><text id="t" font-size="2rem">A</text></g>
+    </svg>"#).unwrap();
+    let attrs = |id| attrs!(svg, id);
+    let mut ctx = DrawContext::new_without_fonts(&svg);
+    ctx.root_font_size = 12.0;
+    let options = Options::new(&ctx);
+    let g = options.apply(attrs("g"));
+    assert_eq!(g.font_size, 10.0);
+    assert_eq!(g.apply(attrs("t")).font_size, 24.0);
+}
+
+#[test]
+fn test_view_box_fallback() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg"><rect width="10" height="10"/></svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    assert_eq!(ctx.view_box(), Some(RectF::new(Vector2F::zero(), vec2f(10., 10.))));
+
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="30"><rect width="10" height="10"/></svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    assert_eq!(ctx.view_box(), Some(RectF::new(Vector2F::zero(), vec2f(20., 30.))));
+}
+
+#[test]
+fn test_view_box_of_invisible_content() {
+    for root in &[
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 30 20"><rect width="10" height="10" display="none"/></svg>"#,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 30 20" opacity="0" display="none"><rect width="10" height="10"/></svg>"#,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 30 20" width="100%" height="100%"><g display="none"/></svg>"#,
+    ] {
+        let svg = Svg::from_str(root).unwrap();
+        let ctx = DrawContext::new_without_fonts(&svg);
+        assert_eq!(ctx.view_box(), Some(RectF::new(Vector2F::zero(), vec2f(30., 20.))), "{}", root);
+    }
+}
+
+#[test]
+fn test_color_interpolation_filters_inherit() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <g id="outer" color-interpolation-filters="sRGB"><g id="inner" filter="url(#f)"/></g>
+    </svg>"#).unwrap();
+    let attrs = |id| attrs!(svg, id);
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let options = Options::new(&ctx);
+    assert_eq!(options.color_interpolation_filters, ColorInterpolation::LinearRGB);
+    let inner = options.apply(attrs("outer")).apply(attrs("inner"));
+    assert_eq!(inner.color_interpolation_filters, ColorInterpolation::SRGB);
+}
+
+#[test]
+fn test_inherit_gradient_fill() {
+    let svg = Svg::from_str(r##"<svg xmlns="http://www.w3.org/2000/svg">
+        <linearGradient id="lg"><stop offset="0" stop-color="red"/><stop offset="1" stop-color="blue"/></linearGradient>
+        <g id="g" fill="url(#lg)" stroke="url(#lg)">
+            <rect id="a" width="1" height="1"/>
+            <rect id="b" width="1" height="1" fill="inherit" stroke="inherit"/>
+        </g>
+    </svg>"##).unwrap();
+    let attrs = |id| attrs!(svg, id);
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let g = Options::new(&ctx).apply(attrs("g"));
+    for id in &["a", "b"] {
+        let options = g.apply(attrs(id));
+        assert_eq!(options.fill, Paint::Ref("lg".into()));
+        assert_eq!(options.stroke, Paint::Ref("lg".into()));
+    }
+}
+
+#[test]
+fn test_clip_path_units_with_transform() {
+    let svg = Svg::from_str(r##"<svg xmlns="http://www.w3.org/2000/svg">
+        <clipPath id="user"><rect width="5" height="5"/></clipPath>
+        <clipPath id="bbox" clipPathUnits="objectBoundingBox"><rect width="0.5" height="0.5"/></clipPath>
+        <rect width="10" height="10" transform="rotate(90)" clip-path="url(#user)"/>
+        <rect x="10" width="10" height="20" transform="rotate(90)" clip-path="url(#bbox)"/>
+    </svg>"##).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 2);
+    let clip_bounds = |i: usize| cmds[i].clip[0].0.bounds();
+    let approx = |a: RectF, b: RectF| (a.origin() - b.origin()).length() < 1e-3 && (a.lower_right() - b.lower_right()).length() < 1e-3;
+
+    // both clips are rotated along with the element
+    assert!(approx(clip_bounds(0), RectF::from_points(vec2f(-5., 0.), vec2f(0., 5.))), "{:?}", clip_bounds(0));
+    // the upper left quarter of the bounding box (10, 0) .. (20, 20)
+    assert!(approx(clip_bounds(1), RectF::from_points(vec2f(-10., 10.), vec2f(0., 15.))), "{:?}", clip_bounds(1));
+}
+
+#[test]
+fn test_clip_path_units_triangle() {
+    let svg = Svg::from_str(r##"<svg xmlns="http://www.w3.org/2000/svg">
+        <clipPath id="triangle" clipPathUnits="objectBoundingBox"><path d="M0,0 L1,0 L0.5,1 z"/></clipPath>
+        <rect x="50" y="20" width="200" height="100" clip-path="url(#triangle)"/>
+    </svg>"##).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].clip.len(), 1);
+    let clip = &cmds[0].clip[0].0;
+    assert_eq!(clip.bounds(), RectF::from_points(vec2f(50., 20.), vec2f(250., 120.)));
+    let points: Vec<Vector2F> = (0 .. 3).map(|i| clip.contours()[0].position_of(i)).collect();
+    assert_eq!(points, [vec2f(50., 20.), vec2f(250., 20.), vec2f(150., 120.)]);
+}
+
+#[test]
+fn test_clip_path_units_without_bbox() {
+    let svg = Svg::from_str(r##"<svg xmlns="http://www.w3.org/2000/svg">
+        <clipPath id="c" clipPathUnits="objectBoundingBox"><rect width="1" height="1"/></clipPath>
+        <g id="g" clip-path="url(#c)"/>
+        <line x1="0" y1="5" x2="20" y2="5" fill="none" stroke="black" stroke-width="4" clip-path="url(#c)"/>
+        <symbol id="s"><rect width="10" height="10"/></symbol>
+        <use href="#s" x="30" clip-path="url(#c)"/>
+    </svg>"##).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let attrs = attrs!(svg, "g");
+    let options = DrawOptions::new(&ctx).apply_with_bbox(&mut Scene::new(), attrs, |_| None);
+    let (rect, _) = options.clip_path.expect("no clip path");
+    assert_eq!(rect.size(), Vector2F::zero());
+
+    // the bounding box of the line has no height, so the clip path covers nothing
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 2);
+    assert_eq!(cmds[0].clip[0].0.bounds().height(), 0.);
+    // a `<use>` has the bounding box of the content it references
+    assert_eq!(cmds[1].clip[0].0.bounds(), RectF::new(vec2f(30., 0.), vec2f(10., 10.)));
+}
+
+#[test]
+fn test_compose_at_secs() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect width="1" height="1">
+            <animate attributeName="width" from="0" to="4" dur="2s" fill="freeze"/>
+        </rect>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = DrawOptions::new(&ctx);
+    options.set_transform(Transform2F::default());
+    options.time = Time::from_seconds(1.5);
+    let expected = ctx.compose_with_options(&options);
+    let scene = ctx.compose_at_secs(1.5);
+    assert_eq!(scene.bounds(), expected.bounds());
+    assert_eq!(scene.bounds().width(), 3.0);
+}
+
+#[test]
+fn test_compose_region() {
+    let rects: String = (0 .. 100).map(|i| format!(r#"<rect x="{}" y="{}" width="10" height="10"/>"#, 20 * (i % 10), 20 * (i / 10))).collect();
+    let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg"><g transform="translate(5 5)">{}</g></svg>"#, rects)).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    assert_eq!(ctx.compose_region(RectF::new(vec2f(0., 0.), vec2f(1000., 1000.))).bounds(), ctx.compose().bounds());
+    // only the two rects at (20, 20) and (40, 20) overlap
+    let scene = ctx.compose_region(RectF::new(vec2f(30., 30.), vec2f(30., 10.)));
+    assert_eq!(scene.bounds(), RectF::new(vec2f(25., 25.), vec2f(30., 10.)));
+
+    // the region stays in user space, the scene is transformed
+    let scene = ctx.compose_region_with_transform(Transform2F::from_scale(2.), RectF::new(vec2f(30., 30.), vec2f(30., 10.)));
+    assert_eq!(scene.bounds(), RectF::new(vec2f(50., 50.), vec2f(60., 20.)));
+}
+
+#[test]
+fn test_compose_layer() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <g id="background"><rect x="2" y="3" width="10" height="10"/></g>
+        <g id="foreground"><rect x="20" width="5" height="5"/></g>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let layer = ctx.compose_layer("background").unwrap();
+    // the layer lines up with the full render
+    assert_eq!(layer.bounds(), RectF::new(vec2f(2., 3.), vec2f(10., 10.)));
+    assert_eq!(layer.bounds().union_rect(ctx.compose_layer("foreground").unwrap().bounds()), ctx.compose().bounds());
+    assert!(ctx.compose_layer("missing").is_none());
+}
+
+#[test]
+fn test_compose_with_element_ids() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="a" width="1" height="1"/>
+        <rect id="b" x="2" width="1" height="1"/>
+        <rect id="c" x="4" width="1" height="1"/>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let (scene, ids) = ctx.compose_with_element_ids();
+    let ids: Vec<_> = ids.iter().map(|&(path, ref id)| (scene.get_draw_path(path).outline().bounds().min_x(), id.as_str())).collect();
+    assert_eq!(ids, [(0.0, "a"), (2.0, "b"), (4.0, "c")]);
+}
+
+#[test]
+fn test_solid_fill_fast_path() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <path id="p" d="M0 0 H4 V4 H0 Z M1 1 H3 V3 H1 Z" fill="red" fill-opacity="0.5" opacity="0.5" fill-rule="evenodd"/>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    // without a recorder, the solid fill takes the fast path
+    let fast = ctx.compose();
+    let fast_path = fast.get_draw_path(DrawPathId(0));
+    // recording forces the general path
+    let (general, ids) = ctx.compose_with_element_ids();
+    let general_path = general.get_draw_path(ids[0].0);
+
+    assert_eq!(fast_path.outline().bounds(), general_path.outline().bounds());
+    assert_eq!(fast_path.fill_rule(), FillRule::EvenOdd);
+    assert_eq!(fast_path.fill_rule(), general_path.fill_rule());
+    assert_eq!(fast_path.blend_mode(), general_path.blend_mode());
+    // the paint includes both opacities
+    assert_eq!(fast.get_paint(fast_path.paint()), general.get_paint(general_path.paint()));
+    assert_eq!(fast.get_paint(fast_path.paint()).base_color().a, 64);
+}
+
+#[test]
+fn test_visibility_collapse() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect width="10" height="10" visibility="collapse"/>
+        <g visibility="collapse"><circle r="5"/></g>
+    </svg>"#).unwrap();
+    // collapse hides the shapes like hidden
+    assert!(DrawContext::new_without_fonts(&svg).flatten().is_empty());
+}
+
+#[test]
+fn test_mix_blend_mode() {
+    let svg = Svg::from_str(r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 10">
+        <rect width="20" height="10" fill="#ff2040"/>
+        <rect id="top" x="10" width="10" height="10" fill="#40ff20" mix-blend-mode="difference"/>
+    </svg>"##).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let (scene, ids) = ctx.compose_with_element_ids();
+    assert_eq!(ids.len(), 1);
+    assert_eq!(scene.get_draw_path(ids[0].0).blend_mode(), BlendMode::Difference);
+
+    // the absolute differences of the channels
+    let image = ctx.rasterize(None).image;
+    assert_eq!(image.get_pixel(5, 5).0, [255, 32, 64, 255]);
+    let pixel = image.get_pixel(15, 5).0;
+    let expected = [191, 223, 32, 255];
+    assert!(pixel.iter().zip(&expected).all(|(&a, &b)| (a as i32 - b as i32).abs() <= 1), "{:?}", pixel);
+}
+
+#[test]
+fn test_resolve_pica() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg"/>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let options = Options::new(&ctx);
+    let pica = Length { num: 1.0, unit: LengthUnit::Pc };
+    let points = Length { num: 12.0, unit: LengthUnit::Pt };
+    assert_eq!(options.resolve_length(pica), options.resolve_length(points));
+    assert_eq!(options.resolve_length(pica), Some(12.0));
+    assert_eq!(options.resolve_length_along(pica, Axis::X), Some(12.0));
+}
+
+#[test]
+fn test_font_relative_lengths_along() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect x="1em" y="2ex" width="1em" height="1ex" font-size="10"/>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    options.font_size = 10.0;
+    let em = Length { num: 1.0, unit: LengthUnit::Em };
+    let ex = Length { num: 1.0, unit: LengthUnit::Ex };
+    assert_eq!(options.resolve_length_along(em, Axis::X), options.resolve_length(em));
+    assert_eq!(options.resolve_length_along(ex, Axis::Y), Some(5.0));
+    assert_eq!(ctx.compose().bounds(), RectF::new(vec2f(10., 10.), vec2f(10., 5.)));
+}
+
+#[test]
+fn test_percent_stroke_width() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect id="r" width="10" height="10" stroke="black" stroke-width="50%"/>
+    </svg>"#).unwrap();
+    let attrs = attrs!(svg, "r");
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    options.view_box = Some(RectF::new(Vector2F::zero(), vec2f(30., 40.)));
+    let options = options.apply(attrs);
+    // the diagonal is 50, normalized by sqrt(2)
+    let expected = 0.5 * 50.0 / 2f32.sqrt();
+    assert!((options.stroke_style.line_width - expected).abs() < 1e-4);
+}
+
+#[test]
+fn test_set_dpi() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect width="1in" height="1in"/>
+    </svg>"#).unwrap();
+    let mut ctx = DrawContext::new_without_fonts(&svg);
+    ctx.set_dpi(96.0);
+    let scene = ctx.compose();
+    assert_eq!(scene.bounds(), RectF::new(Vector2F::zero(), vec2f(96., 96.)));
+}
+
+#[test]
+fn test_current_color() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <g id="outer" color="red">
+            <rect id="a" fill="currentColor" width="1" height="1"/>
+            <g id="inner" color="blue" fill="currentColor">
+                <rect id="b" stroke="currentColor" width="1" height="1"/>
+                <rect id="c" color="lime" fill="currentColor" width="1" height="1"/>
+            </g>
+        </g>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let attrs = |id: &str| attrs!(svg, id);
+    let color = |name: &str| Paint::Color(Color::parse(name).unwrap());
+
+    let outer = Options::new(&ctx).apply(attrs("outer"));
+    assert_eq!(outer.apply(attrs("a")).fill, color("red"));
+    let inner = outer.apply(attrs("inner"));
+    assert_eq!(inner.fill, color("blue"));
+    let b = inner.apply(attrs("b"));
+    assert_eq!((b.fill, b.stroke), (color("blue"), color("blue")));
+    // the own color of the element is used
+    assert_eq!(inner.apply(attrs("c")).fill, color("lime"));
+}
+
+#[test]
+fn test_color_alpha() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect width="10" height="10" fill="rgb(255 0 0 / 50%)"/>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    assert_eq!(cmds.len(), 1);
+    let color = cmds[0].paint.base_color();
+    assert_eq!((color.r, color.g, color.b), (255, 0, 0));
+    assert!(color.a == 127 || color.a == 128, "{:?}", color);
+}
+
+#[test]
+fn test_fill_rule_inherit() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <g fill-rule="evenodd" clip-rule="evenodd">
+            <rect id="inherit" width="10" height="10" fill-rule="inherit" clip-rule="inherit"/>
+            <rect id="absent" width="10" height="10"/>
+            <rect id="own" width="10" height="10" fill-rule="nonzero"/>
+        </g>
+        <rect id="root" width="10" height="10" fill-rule="inherit"/>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    let rules: Vec<FillRule> = cmds.iter().map(|cmd| cmd.fill_rule).collect();
+    // without an ancestor that sets it, the initial value nonzero is inherited
+    assert_eq!(rules, [FillRule::EvenOdd, FillRule::EvenOdd, FillRule::Winding, FillRule::Winding]);
+
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let attrs = |id: &str| attrs!(svg, id);
+    let mut group = Options::new(&ctx);
+    group.clip_rule = FillRule::EvenOdd;
+    assert_eq!(group.apply(attrs("inherit")).clip_rule, FillRule::EvenOdd);
+    assert_eq!(Options::new(&ctx).apply(attrs("inherit")).clip_rule, FillRule::Winding);
+}
+
+#[test]
+fn test_stroke_dasharray() {
+    let contours = |dasharray: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <line x1="0" y1="5" x2="20" y2="5" stroke="black" stroke-dasharray="{}"/>
+        </svg>"#, dasharray)).unwrap();
+        let cmds = DrawContext::new_without_fonts(&svg).flatten();
+        cmds.last().unwrap().outline.contours().len()
+    };
+    // dashes at 0, 6, 12 and 18
+    assert_eq!(contours("4 2"), 4);
+    // an odd number of lengths is repeated: "4 4 4 4"
+    assert_eq!(contours("4"), 3);
+    assert_eq!(contours("0 0"), 1);
+    assert_eq!(contours("4 -2"), 1);
+    assert_eq!(contours("none"), 1);
+}
+
+#[test]
+fn test_stroke_dasharray_inherit() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <g fill="none" stroke="black" stroke-dasharray="4 2" stroke-dashoffset="1">
+            <path d="M0,0 L12,0"/>
+            <g><path d="M0,5 L12,5"/></g>
+            <path d="M0,10 L12,10" stroke-dasharray="none"/>
+        </g>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    // with the offset, the dashes are 0..3, 5..9 and 11..12
+    let dashes: Vec<usize> = cmds.iter().map(|cmd| cmd.outline.contours().len()).collect();
+    assert_eq!(dashes, [3, 3, 1]);
+}
+
+#[test]
+fn test_paint_order() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <g paint-order="stroke markers">
+            <rect width="10" height="10" fill="blue" stroke="red"/>
+        </g>
+        <rect width="10" height="10" fill="blue" stroke="red"/>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    let colors: Vec<_> = cmds.iter().map(|cmd| cmd.paint.base_color()).collect();
+    let (red, blue) = (ColorU::new(255, 0, 0, 255), ColorU::new(0, 0, 255, 255));
+    // the inherited order puts the stroke below the fill, the initial one above it
+    assert_eq!(colors, [red, blue, blue, red]);
+}
+
+#[test]
+fn test_stroke_dashoffset() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <line x1="0" y1="5" x2="20" y2="5" stroke="black" stroke-dasharray="4 4" stroke-dashoffset="-4"/>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    // starting in the gap, only the dashes at 4 and 12 remain
+    let dashes: Vec<f32> = cmds.last().unwrap().outline.contours().iter().map(|c| c.bounds().center().x().round()).collect();
+    assert_eq!(dashes, [6., 14.]);
+}
+
+#[test]
+fn test_miter_spike() {
+    // the corner at (20, 4) is about 23 degrees, which needs a miter limit of about 5.1
+    let tip = |attrs: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <polyline points="0,0 20,4 0,8" fill="none" stroke="black" stroke-width="2" {}/>
+        </svg>"#, attrs)).unwrap();
+        let cmds = DrawContext::new_without_fonts(&svg).flatten();
+        cmds.last().unwrap().outline.bounds().max_x()
+    };
+    assert!(tip(r#"stroke-miterlimit="10""#) > 24.5);
+    // past the limit the join falls back to a bevel, which doesn't reach further than half the stroke width
+    assert!(tip("") < 21.01);
+    assert!(tip(r#"stroke-linejoin="bevel" stroke-miterlimit="10""#) < 21.01);
+    assert!(tip(r#"stroke-linejoin="round""#) < 21.01);
+}
+
+#[test]
+fn test_stroke_linecap() {
+    let bounds = |cap: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <line x1="10" y1="10" x2="20" y2="10" stroke="black" stroke-width="4" stroke-linecap="{}"/>
+        </svg>"#, cap)).unwrap();
+        let cmds = DrawContext::new_without_fonts(&svg).flatten();
+        let b = cmds.last().unwrap().outline.bounds();
+        (b.min_x().round(), b.max_x().round())
+    };
+    assert_eq!(bounds("butt"), (10., 20.));
+    // both extend half the stroke width past the ends
+    assert_eq!(bounds("round"), (8., 22.));
+    assert_eq!(bounds("square"), (8., 22.));
+}
+
+#[test]
+fn test_transparent_fill() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <rect width="10" height="10" fill="transparent"/>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    // drawn as a color with zero alpha, unlike `none` which draws nothing at all
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].paint.base_color().a, 0);
+}
+
+#[test]
+fn test_viewbox_transform() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50" viewBox="10 0 20 20">
+        <rect x="10" width="20" height="20"/>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let transform = ctx.viewbox_transform();
+    // the same transform the content is drawn with
+    assert_eq!(transform * RectF::new(vec2f(10., 0.), vec2f(20., 20.)), ctx.flatten()[0].outline.bounds());
+
+    let p = vec2f(12.5, 7.0);
+    let back = ctx.viewbox_transform_inverse() * (transform * p);
+    assert!((back - p).length() < 1e-4, "{:?}", back);
+}
+
+#[test]
+fn test_nested_clip_paths() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 40">
+        <clipPath id="circle"><circle cx="20" cy="20" r="15"/></clipPath>
+        <clipPath id="right"><rect x="20" width="20" height="40"/></clipPath>
+        <g clip-path="url(#circle)"><rect width="40" height="40" clip-path="url(#right)"/></g>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].clip.len(), 2);
+
+    // only the right half of the circle is left
+    let image = ctx.rasterize(None).image;
+    assert_eq!(image.get_pixel(28, 20).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(22, 6).0, [0, 0, 0, 255]);
+    // inside the circle only
+    assert_eq!(image.get_pixel(10, 20).0[3], 0);
+    // inside the rect only
+    assert_eq!(image.get_pixel(37, 20).0[3], 0);
+    assert_eq!(image.get_pixel(30, 5).0[3], 0);
+}
@@ -47,7 +47,7 @@ impl DrawItem for TagEllipse {
         if !self.attrs.display {
             return;
         }
-        let options = options.apply(scene, &self.attrs);
+        let options = options.apply_with_bbox(scene, &self.attrs, |options| ellipse_outline(self, options).map(|o| o.bounds()));
 
         if let Some(outline) = ellipse_outline(self, &options) {
             options.draw(scene, &outline);
@@ -102,7 +102,7 @@ impl DrawItem for TagCircle {
         if !self.attrs.display {
             return;
         }
-        let options = options.apply(scene, &self.attrs);
+        let options = options.apply_with_bbox(scene, &self.attrs, |options| circle_outline(self, options).map(|o| o.bounds()));
 
         if let Some(outline) = circle_outline(self, &options) {
             options.draw(scene, &outline);
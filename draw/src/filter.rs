@@ -10,30 +10,637 @@ use pathfinder_content::{
     render_target::{RenderTargetId},
 };
 use pathfinder_geometry::rect::RectI;
-use pathfinder_color::matrix::ColorMatrix;
+use pathfinder_color::{ColorF, ColorU, matrix::ColorMatrix};
+use pathfinder_simd::default::F32x4;
+use std::collections::HashMap;
+use crate::gradient::bbox_fraction;
+use crate::transfer::{Curve, RampSum, weight_matrix, srgb_to_linear};
 
+/// Extent of the blur kernel in multiples of sigma.
+///
+/// The render targets are padded by this much transparent black on each side,
+/// so the kernel never samples past the edge of the target (which would clamp and smear the edge color).
+const BLUR_KERNEL_SIGMAS: f32 = 3.0;
+
+/// a deviation of zero disables the blur along that axis
+fn blur_filter(direction: BlurDirection, sigma: f32) -> Option<PatternFilter> {
+    if sigma > 0.0 {
+        Some(PatternFilter::Blur { direction, sigma })
+    } else {
+        None
+    }
+}
+
+/// The filter region in scene pixels, for content with the given bounds (in scene pixels).
+/// With `objectBoundingBox` units (the default) the values are fractions of the bounding box in user space.
+pub fn filter_region(filter: &TagFilter, options: &Options, bounds: RectF) -> RectF {
+    effect_region(filter.filter_units, filter.x, filter.y, filter.width, filter.height, options, bounds)
+}
+
+/// like `filter_region`, for any element with a region of this kind (like a `<mask>`).
+/// Missing values default to -10% for the position and 120% for the size.
+pub fn effect_region(units: Option<GradientUnits>, x: Option<LengthX>, y: Option<LengthY>, width: Option<LengthX>, height: Option<LengthY>, options: &Options, bounds: RectF) -> RectF {
+    let region = match units.unwrap_or(GradientUnits::ObjectBoundingBox) {
+        GradientUnits::ObjectBoundingBox => {
+            let bbox = options.transform.inverse() * bounds;
+            let fraction = |length: Option<Length>, default: f32| length.map(bbox_fraction).unwrap_or(default);
+            let origin = vec2f(fraction(x.map(|x| x.0), -0.1), fraction(y.map(|y| y.0), -0.1));
+            let size = vec2f(fraction(width.map(|w| w.0), 1.2), fraction(height.map(|h| h.0), 1.2));
+            RectF::new(bbox.origin() + origin * bbox.size(), size * bbox.size())
+        }
+        GradientUnits::UserSpaceOnUse => {
+            let percent = |p: f64| Length::new(p, LengthUnit::Percent);
+            let x = x.unwrap_or(LengthX(percent(-10.))).resolve(options);
+            let y = y.unwrap_or(LengthY(percent(-10.))).resolve(options);
+            let width = width.unwrap_or(LengthX(percent(120.))).resolve(options);
+            let height = height.unwrap_or(LengthY(percent(120.))).resolve(options);
+            RectF::new(vec2f(x, y), vec2f(width, height))
+        }
+    };
+    options.transform * region
+}
+
+/// The output of the filter is limited to the filter region.
+///
+/// With `color-interpolation-filters="linearRGB"` (the default) the primitives work on linear values:
+/// the source graphic, flood colors and `<feImage>` are converted to linearRGB, and the result back to sRGB.
+/// The render targets only have 8 bits per channel, so dark colors lose some precision on the way.
 pub fn apply_filter(filter: &TagFilter, scene: &mut Scene, options: &DrawOptions, bounds: RectF, f: impl FnOnce(&mut Scene, &DrawOptions)) {
-    if let Some(first) = filter.filters.first() {
-        let mut options2 = options.clone();
-        let info = FilterState::pre(first, scene, bounds, &mut options2);
-        f(scene, &options2);
-        info.post(scene, options);
+    let region = filter_region(filter, options, bounds);
+    let user_space_primitives = filter.primitive_units.unwrap_or(GradientUnits::UserSpaceOnUse) == GradientUnits::UserSpaceOnUse;
+    match filter.filters[..] {
+        [] => f(scene, options),
+        [Filter::Image(ref image)] => draw_fe_image(image, scene, options),
+        // a single primitive that only processes the source graphic is applied without the intermediate results of a chain
+        [ref first] if user_space_primitives && applies_to_source(first) && options.color_interpolation_filters != ColorInterpolation::LinearRGB => {
+            let mut options2 = options.clone();
+            // the content ends up in a render target, not in the scene itself
+            options2.recorder = None;
+            let info = match FilterState::pre(first, scene, bounds, region, &mut options2) {
+                Some(info) => info,
+                None => return
+            };
+            f(scene, &options2);
+            info.post(scene, options);
+        }
+        _ => apply_filter_chain(filter, scene, options, bounds, region, f),
+    }
+}
+
+/// whether `filter` reads the source graphic and covers the whole filter region, so it can be applied without a chain
+fn applies_to_source(filter: &Filter) -> bool {
+    let (input, region) = match *filter {
+        Filter::GaussianBlur(ref blur) => (&blur.input, &blur.region),
+        Filter::ColorMatrix(ref matrix) => (&matrix.input, &matrix.region),
+        Filter::Blend(ref blend) => (&blend.input, &blend.region),
+        _ => return false
+    };
+    matches!(input.as_deref(), None | Some("SourceGraphic")) && region.is_unset()
+}
+
+/// the output of a primitive in a filter chain
+#[derive(Copy, Clone)]
+struct FilterResult {
+    /// the size of the filter region
+    render_target: RenderTargetId,
+    /// the primitive subregion, in pixels of the render target
+    subregion: RectI,
+}
+
+/// state of a filter chain. Every result is a render target covering the filter region.
+///
+/// The render targets hold premultiplied colors, and all primitives operate on them directly.
+/// Every primitive writes its result into a fresh render target, which clamps the channels to [0, 1].
+/// Primitives that are defined on colors that are not premultiplied (`feComponentTransfer`)
+/// have to account for that themselves, see `FilterChain::transfer`.
+struct FilterChain<'o, 'a> {
+    /// filter region, in scene pixels
+    region: RectI,
+    /// options of the filtered element, with the transform mapping its user space to the render targets
+    options: DrawOptions<'a>,
+    source: FilterResult,
+    source_alpha: Option<FilterResult>,
+    results: HashMap<&'o str, FilterResult>,
+    last: FilterResult,
+    /// the bounding box of the content in user space, if the primitives are in `objectBoundingBox` units
+    bbox: Option<RectF>,
+    /// whether the primitives work on linearRGB values
+    linear: bool,
+}
+
+/// Apply the primitives in order, passing results by name (`result`/`in`/`in2`) or implicitly to the next primitive.
+/// `bounds` are those of the content and `region` is the filter region, both in scene pixels.
+fn apply_filter_chain<'o>(filter: &'o TagFilter, scene: &mut Scene, options: &DrawOptions, bounds: RectF, region: RectF, f: impl FnOnce(&mut Scene, &DrawOptions)) {
+    let bbox = match filter.primitive_units.unwrap_or(GradientUnits::UserSpaceOnUse) {
+        GradientUnits::ObjectBoundingBox => Some(options.transform.inverse() * bounds),
+        GradientUnits::UserSpaceOnUse => None,
+    };
+    let region = region.round_out().to_i32();
+    if region.size().x() <= 0 || region.size().y() <= 0 {
+        return;
+    }
+    let full = RectI::new(Default::default(), region.size());
+
+    let mut target_options = options.clone();
+    target_options.recorder = None;
+    target_options.transform = Transform2F::from_translation(-region.origin().to_f32()) * options.transform;
+
+    let source = scene.push_render_target(RenderTarget::new(region.size(), String::new()));
+    f(scene, &target_options);
+    scene.pop_render_target();
+    let source = FilterResult { render_target: source, subregion: full };
+
+    let mut chain = FilterChain {
+        region,
+        options: target_options,
+        source,
+        source_alpha: None,
+        results: HashMap::new(),
+        last: source,
+        bbox,
+        linear: options.color_interpolation_filters == ColorInterpolation::LinearRGB,
+    };
+    chain.source = chain.to_linear(scene, source);
+    chain.last = chain.source;
+    for filter in &filter.filters {
+        let (result, name) = chain.apply(filter, scene);
+        if let Some(name) = name {
+            chain.results.insert(name, result);
+        }
+        chain.last = result;
+    }
+
+    let last = if chain.linear {
+        let to_srgb = Curve::linear_to_srgb();
+        chain.transfer(scene, chain.last, chain.last.subregion, [&to_srgb, &to_srgb, &to_srgb, &Curve::identity()])
     } else {
-        f(scene, options);
+        chain.last
+    };
+    let mut pattern = Pattern::from_render_target(last.render_target, region.size());
+    pattern.apply_transform(Transform2F::from_translation(region.origin().to_f32()));
+    let paint_id = scene.push_paint(&Paint::from_pattern(pattern));
+    scene.push_draw_path(DrawPath::new(Outline::from_rect(region.to_f32()), paint_id));
+}
+
+impl<'o, 'a> FilterChain<'o, 'a> {
+    fn full(&self) -> RectI {
+        RectI::new(Default::default(), self.region.size())
+    }
+    fn push_target(&self, scene: &mut Scene) -> RenderTargetId {
+        scene.push_render_target(RenderTarget::new(self.region.size(), String::new()))
+    }
+    /// draw `result` into the current render target, limited to `rect`
+    fn draw_result(&self, scene: &mut Scene, result: FilterResult, rect: RectI, filter: Option<PatternFilter>, mode: BlendMode) {
+        let mut pattern = Pattern::from_render_target(result.render_target, self.region.size());
+        pattern.set_filter(filter);
+        let paint_id = scene.push_paint(&Paint::from_pattern(pattern));
+        let mut path = DrawPath::new(Outline::from_rect(rect.to_f32()), paint_id);
+        path.set_blend_mode(mode);
+        scene.push_draw_path(path);
+    }
+    fn input(&mut self, scene: &mut Scene, name: Option<&str>) -> FilterResult {
+        match name {
+            None => self.last,
+            Some("SourceGraphic") => self.source,
+            Some("SourceAlpha") => match self.source_alpha {
+                Some(alpha) => alpha,
+                None => {
+                    let zero = F32x4::splat(0.0);
+                    let alpha_only = ColorMatrix([zero, zero, zero, F32x4::new(0.0, 0.0, 0.0, 1.0), zero]);
+                    let render_target = self.push_target(scene);
+                    self.draw_result(scene, self.source, self.full(), Some(PatternFilter::ColorMatrix(alpha_only)), BlendMode::SrcOver);
+                    scene.pop_render_target();
+
+                    let alpha = FilterResult { render_target, subregion: self.full() };
+                    self.source_alpha = Some(alpha);
+                    alpha
+                }
+            },
+            Some(name) => match self.results.get(name) {
+                Some(&result) => result,
+                None => {
+                    warn!("filter result {:?} not found", name);
+                    self.last
+                }
+            }
+        }
+    }
+    /// the primitive subregion in pixels of the render targets. Missing values are taken from `default`.
+    fn subregion(&self, region: &PrimitiveRegion, default: RectI) -> RectI {
+        let to_target = self.options.transform;
+        let default = to_target.inverse() * default.to_f32();
+        let (x, y, width, height) = match self.bbox {
+            Some(bbox) => (
+                region.x.map(|x| bbox.origin_x() + bbox_fraction(x.0) * bbox.width()),
+                region.y.map(|y| bbox.origin_y() + bbox_fraction(y.0) * bbox.height()),
+                region.width.map(|w| bbox_fraction(w.0) * bbox.width()),
+                region.height.map(|h| bbox_fraction(h.0) * bbox.height()),
+            ),
+            None => (
+                region.x.map(|x| x.resolve(&self.options)),
+                region.y.map(|y| y.resolve(&self.options)),
+                region.width.map(|w| w.resolve(&self.options)),
+                region.height.map(|h| h.resolve(&self.options)),
+            )
+        };
+        let origin = vec2f(x.unwrap_or(default.origin_x()), y.unwrap_or(default.origin_y()));
+        let size = vec2f(width.unwrap_or(default.width()), height.unwrap_or(default.height()));
+        let rect = to_target * RectF::new(origin, size);
+        rect.round_out().intersection(self.full().to_f32()).unwrap_or_default().to_i32()
+    }
+    /// `dx`/`dy` of a primitive in pixels of the render targets
+    fn offset(&self, dx: f32, dy: f32) -> Vector2F {
+        let offset = match self.bbox {
+            Some(bbox) => vec2f(dx, dy) * bbox.size(),
+            None => vec2f(dx, dy),
+        };
+        self.options.transform.matrix * offset
+    }
+    /// `stdDeviation` of a primitive in pixels of the render targets
+    fn sigma(&self, std_deviation: Vector2F) -> Vector2F {
+        let std_deviation = match self.bbox {
+            Some(bbox) => std_deviation * bbox.size(),
+            None => std_deviation,
+        };
+        self.options.transform.extract_scale() * std_deviation
+    }
+    fn apply(&mut self, filter: &'o Filter, scene: &mut Scene) -> (FilterResult, Option<&'o str>) {
+        match *filter {
+            Filter::Flood(ref flood) => {
+                let subregion = self.subregion(&flood.region, self.full());
+                let render_target = self.push_target(scene);
+                let paint_id = scene.push_paint(&Paint::from_color(self.color(flood.color.color_f(flood.opacity)).to_u8()));
+                scene.push_draw_path(DrawPath::new(Outline::from_rect(subregion.to_f32()), paint_id));
+                scene.pop_render_target();
+                (FilterResult { render_target, subregion }, flood.result.as_deref())
+            }
+            Filter::Tile(ref tile) => {
+                let input = self.input(scene, tile.input.as_deref());
+                // unlike other primitives, the subregion defaults to the whole filter region
+                let subregion = self.subregion(&tile.region, self.full());
+                let tile_size = input.subregion.size();
+                if tile_size.x() <= 0 || tile_size.y() <= 0 {
+                    let render_target = self.push_target(scene);
+                    scene.pop_render_target();
+                    return (FilterResult { render_target, subregion }, tile.result.as_deref());
+                }
+
+                // copy the subregion of the input into a render target of its own, which is then repeated
+                let copy = scene.push_render_target(RenderTarget::new(tile_size, String::new()));
+                let mut pattern = Pattern::from_render_target(input.render_target, self.region.size());
+                pattern.apply_transform(Transform2F::from_translation(-input.subregion.origin().to_f32()));
+                let paint_id = scene.push_paint(&Paint::from_pattern(pattern));
+                scene.push_draw_path(DrawPath::new(Outline::from_rect(RectI::new(Default::default(), tile_size).to_f32()), paint_id));
+                scene.pop_render_target();
+
+                let render_target = self.push_target(scene);
+                let mut pattern = Pattern::from_render_target(copy, tile_size);
+                pattern.set_repeat_x(true);
+                pattern.set_repeat_y(true);
+                pattern.apply_transform(Transform2F::from_translation(input.subregion.origin().to_f32()));
+                let paint_id = scene.push_paint(&Paint::from_pattern(pattern));
+                scene.push_draw_path(DrawPath::new(Outline::from_rect(subregion.to_f32()), paint_id));
+                scene.pop_render_target();
+                (FilterResult { render_target, subregion }, tile.result.as_deref())
+            }
+            Filter::Composite(ref composite) => {
+                let mode = match composite.operator {
+                    CompositeOperator::Over => BlendMode::SrcOver,
+                    CompositeOperator::In => BlendMode::SrcIn,
+                    CompositeOperator::Out => BlendMode::SrcOut,
+                    CompositeOperator::Atop => BlendMode::SrcAtop,
+                    CompositeOperator::Xor => BlendMode::Xor,
+                    CompositeOperator::Arithmetic { k1, k2, k3, k4 } => {
+                        return self.arithmetic(scene, composite, [k1, k2, k3, k4]);
+                    }
+                };
+                self.composite(scene, composite.input.as_deref(), composite.input2.as_deref(), &composite.region, mode, composite.result.as_deref())
+            }
+            Filter::Blend(ref blend) => {
+                self.composite(scene, blend.input.as_deref(), blend.input2.as_deref(), &blend.region, blend.mode, blend.result.as_deref())
+            }
+            Filter::ColorMatrix(ref filter) => {
+                let input = self.input(scene, filter.input.as_deref());
+                let subregion = self.subregion(&filter.region, input.subregion);
+                let matrix = match filter.matrix {
+                    ColorMatrixType::Matrix(columns) => ColorMatrix(columns),
+                    ColorMatrixType::Saturate(saturation) => ColorMatrix::saturate(saturation),
+                    ColorMatrixType::HueRotate(radians) => ColorMatrix::hue_rotate(radians),
+                    ColorMatrixType::LuminanceToAlpha => ColorMatrix::luminance_to_alpha(),
+                };
+                let render_target = self.push_target(scene);
+                self.draw_result(scene, input, subregion, Some(PatternFilter::ColorMatrix(matrix)), BlendMode::SrcOver);
+                scene.pop_render_target();
+                (FilterResult { render_target, subregion }, filter.result.as_deref())
+            }
+            Filter::ComponentTransfer(ref transfer) => {
+                let input = self.input(scene, transfer.input.as_deref());
+                let subregion = self.subregion(&transfer.region, input.subregion);
+                let curves = [
+                    Curve::from_transfer(&transfer.func_r),
+                    Curve::from_transfer(&transfer.func_g),
+                    Curve::from_transfer(&transfer.func_b),
+                    Curve::from_transfer(&transfer.func_a),
+                ];
+                let [r, g, b, a] = &curves;
+                (self.transfer(scene, input, subregion, [r, g, b, a]), transfer.result.as_deref())
+            }
+            Filter::GaussianBlur(ref blur) => {
+                let input = self.input(scene, blur.input.as_deref());
+                let subregion = self.subregion(&blur.region, input.subregion);
+                let sigma = self.sigma(blur.std_deviation);
+                let blurred_x = self.push_target(scene);
+                self.draw_result(scene, input, self.full(), blur_filter(BlurDirection::X, sigma.x()), BlendMode::SrcOver);
+                scene.pop_render_target();
+                let blurred_x = FilterResult { render_target: blurred_x, subregion };
+
+                let render_target = self.push_target(scene);
+                self.draw_result(scene, blurred_x, subregion, blur_filter(BlurDirection::Y, sigma.y()), BlendMode::SrcOver);
+                scene.pop_render_target();
+                (FilterResult { render_target, subregion }, blur.result.as_deref())
+            }
+            Filter::Merge(ref merge) => {
+                let inputs: Vec<FilterResult> = merge.inputs.iter().map(|name| self.input(scene, name.as_deref())).collect();
+                let default = inputs.iter()
+                    .map(|input| input.subregion.to_f32())
+                    .fold(None, |union: Option<RectF>, rect| Some(union.map_or(rect, |u| u.union_rect(rect))))
+                    .unwrap_or_else(|| self.full().to_f32());
+                let subregion = self.subregion(&merge.region, default.to_i32());
+                let render_target = self.push_target(scene);
+                for &input in &inputs {
+                    self.draw_result(scene, input, subregion, None, BlendMode::SrcOver);
+                }
+                scene.pop_render_target();
+                (FilterResult { render_target, subregion }, merge.result.as_deref())
+            }
+            Filter::Offset(ref offset) => {
+                let input = self.input(scene, offset.input.as_deref());
+                let subregion = self.subregion(&offset.region, input.subregion);
+                let render_target = self.push_target(scene);
+                let mut pattern = Pattern::from_render_target(input.render_target, self.region.size());
+                pattern.apply_transform(Transform2F::from_translation(self.offset(offset.dx, offset.dy)));
+                let paint_id = scene.push_paint(&Paint::from_pattern(pattern));
+                scene.push_draw_path(DrawPath::new(Outline::from_rect(subregion.to_f32()), paint_id));
+                scene.pop_render_target();
+                (FilterResult { render_target, subregion }, offset.result.as_deref())
+            }
+            Filter::DropShadow(ref shadow) => {
+                let input = self.input(scene, shadow.input.as_deref());
+                let subregion = self.subregion(&shadow.region, input.subregion);
+
+                // the alpha of the input in the flood color (premultiplied), moved by the offset in pixels.
+                // The flood color is converted like that of `<feFlood>`, the input already is in the color space of the chain.
+                // The input defaults to the previous result, so a second shadow also falls from the first one.
+                let color = self.color(shadow.color.color_f(shadow.opacity));
+                let zero = F32x4::splat(0.0);
+                let a = color.a();
+                let tint = ColorMatrix([zero, zero, zero, F32x4::new(color.r() * a, color.g() * a, color.b() * a, a), zero]);
+                let offset = self.offset(shadow.dx, shadow.dy);
+                let shifted = self.push_target(scene);
+                let mut pattern = Pattern::from_render_target(input.render_target, self.region.size());
+                pattern.set_filter(Some(PatternFilter::ColorMatrix(tint)));
+                pattern.apply_transform(Transform2F::from_translation(offset));
+                let paint_id = scene.push_paint(&Paint::from_pattern(pattern));
+                scene.push_draw_path(DrawPath::new(Outline::from_rect(self.full().to_f32()), paint_id));
+                scene.pop_render_target();
+                let shifted = FilterResult { render_target: shifted, subregion: self.full() };
+
+                let sigma = self.sigma(shadow.std_deviation);
+                let blurred_x = self.push_target(scene);
+                self.draw_result(scene, shifted, self.full(), blur_filter(BlurDirection::X, sigma.x()), BlendMode::SrcOver);
+                scene.pop_render_target();
+                let blurred_x = FilterResult { render_target: blurred_x, subregion: self.full() };
+
+                let render_target = self.push_target(scene);
+                self.draw_result(scene, blurred_x, subregion, blur_filter(BlurDirection::Y, sigma.y()), BlendMode::SrcOver);
+                self.draw_result(scene, input, subregion, None, BlendMode::SrcOver);
+                scene.pop_render_target();
+                (FilterResult { render_target, subregion }, shadow.result.as_deref())
+            }
+            Filter::Image(ref image) => {
+                let render_target = self.push_target(scene);
+                draw_fe_image(image, scene, &self.options);
+                scene.pop_render_target();
+                (self.to_linear(scene, FilterResult { render_target, subregion: self.full() }), None)
+            }
+        }
+    }
+    /// `input` drawn onto `input2` with `mode`. The subregion defaults to the union of the inputs.
+    fn composite(&mut self, scene: &mut Scene, input: Option<&str>, input2: Option<&str>, region: &PrimitiveRegion, mode: BlendMode, name: Option<&'o str>) -> (FilterResult, Option<&'o str>) {
+        let a = self.input(scene, input);
+        let b = self.input(scene, input2);
+        let subregion = self.subregion(region, a.subregion.to_f32().union_rect(b.subregion.to_f32()).to_i32());
+
+        let render_target = self.push_target(scene);
+        self.draw_result(scene, b, subregion, None, BlendMode::SrcOver);
+        self.draw_result(scene, a, subregion, None, mode);
+        scene.pop_render_target();
+        (FilterResult { render_target, subregion }, name)
+    }
+    /// `k1 i1 i2 + k2 i1 + k3 i2 + k4` of the premultiplied inputs `i1` (`in`) and `i2` (`in2`).
+    ///
+    /// The terms are added up with `Lighter` blending, which clamps the sum to [0, 1].
+    /// The product is the multiply blend of the inputs, masked by both of their alphas,
+    /// which is exact where each input is either opaque or transparent.
+    /// Negative coefficients can't be subtracted this way and count as zero.
+    fn arithmetic(&mut self, scene: &mut Scene, composite: &'o FeComposite, k: [f32; 4]) -> (FilterResult, Option<&'o str>) {
+        let a = self.input(scene, composite.input.as_deref());
+        let b = self.input(scene, composite.input2.as_deref());
+        let subregion = self.subregion(&composite.region, a.subregion.to_f32().union_rect(b.subregion.to_f32()).to_i32());
+        if k.iter().any(|&k| k < 0.0) {
+            warn!("feComposite: negative arithmetic coefficients {:?} are treated as zero", k);
+        }
+        let [k1, k2, k3, k4] = [k[0].max(0.0), k[1].max(0.0), k[2].max(0.0), k[3].max(0.0)];
+        let scale = |k: f32| {
+            let zero = F32x4::splat(0.0);
+            Some(PatternFilter::ColorMatrix(ColorMatrix([
+                F32x4::new(k, 0.0, 0.0, 0.0),
+                F32x4::new(0.0, k, 0.0, 0.0),
+                F32x4::new(0.0, 0.0, k, 0.0),
+                F32x4::new(0.0, 0.0, 0.0, k),
+                zero,
+            ])))
+        };
+
+        let product = if k1 > 0.0 {
+            let render_target = self.push_target(scene);
+            self.draw_result(scene, b, subregion, None, BlendMode::SrcOver);
+            self.draw_result(scene, a, subregion, None, BlendMode::Multiply);
+            self.draw_result(scene, a, subregion, None, BlendMode::DestIn);
+            self.draw_result(scene, b, subregion, None, BlendMode::DestIn);
+            scene.pop_render_target();
+            Some(FilterResult { render_target, subregion })
+        } else {
+            None
+        };
+
+        let render_target = self.push_target(scene);
+        if k4 > 0.0 {
+            // white with alpha k4 is (k4, k4, k4, k4) premultiplied
+            let paint_id = scene.push_paint(&Paint::from_color(ColorF::new(1.0, 1.0, 1.0, k4.min(1.0)).to_u8()));
+            let mut path = DrawPath::new(Outline::from_rect(subregion.to_f32()), paint_id);
+            path.set_blend_mode(BlendMode::Lighter);
+            scene.push_draw_path(path);
+        }
+        if k2 > 0.0 {
+            self.draw_result(scene, a, subregion, scale(k2), BlendMode::Lighter);
+        }
+        if k3 > 0.0 {
+            self.draw_result(scene, b, subregion, scale(k3), BlendMode::Lighter);
+        }
+        if let Some(product) = product {
+            self.draw_result(scene, product, subregion, scale(k1), BlendMode::Lighter);
+        }
+        scene.pop_render_target();
+        (FilterResult { render_target, subregion }, composite.result.as_deref())
+    }
+    /// `color` (given in sRGB) in the color space of the chain
+    fn color(&self, color: ColorF) -> ColorF {
+        if self.linear {
+            ColorF::new(srgb_to_linear(color.r()), srgb_to_linear(color.g()), srgb_to_linear(color.b()), color.a())
+        } else {
+            color
+        }
+    }
+    /// `input` (drawn in sRGB) in the color space of the chain
+    fn to_linear(&self, scene: &mut Scene, input: FilterResult) -> FilterResult {
+        if self.linear {
+            let to_linear = Curve::srgb_to_linear();
+            self.transfer(scene, input, input.subregion, [&to_linear, &to_linear, &to_linear, &Curve::identity()])
+        } else {
+            input
+        }
+    }
+    /// draw a rect of the opaque `color` into the current render target
+    fn fill(&self, scene: &mut Scene, rect: RectI, color: ColorU) {
+        let paint_id = scene.push_paint(&Paint::from_color(color));
+        scene.push_draw_path(DrawPath::new(Outline::from_rect(rect.to_f32()), paint_id));
     }
+    /// `input` with the transfer functions `curves` (red, green, blue, alpha) applied to its colors, which are not premultiplied.
+    ///
+    /// The functions can't be evaluated per pixel directly, so the work is done on opaque layers,
+    /// where straight and premultiplied colors are the same: the colors of the input are divided by its alpha,
+    /// each function is added up from the ramps of a `RampSum`, and the colors are multiplied by the new alpha at the end.
+    fn transfer(&self, scene: &mut Scene, input: FilterResult, subregion: RectI, curves: [&Curve; 4]) -> FilterResult {
+        let [r, g, b, a] = curves;
+        let zero = F32x4::splat(0.0);
+
+        // (C / A, 1), with a color dodge of the opaque gray 1 - A, which divides by A
+        let colors = {
+            let render_target = self.push_target(scene);
+            self.fill(scene, subregion, ColorU::black());
+            self.draw_result(scene, input, subregion, None, BlendMode::SrcOver);
+            let inverse_alpha = ColorMatrix([zero, zero, zero, F32x4::new(-1.0, -1.0, -1.0, 0.0), F32x4::splat(1.0)]);
+            self.draw_result(scene, input, subregion, Some(PatternFilter::ColorMatrix(inverse_alpha)), BlendMode::ColorDodge);
+            scene.pop_render_target();
+            FilterResult { render_target, subregion }
+        };
+        let colors = if r.is_identity() && g.is_identity() && b.is_identity() {
+            colors
+        } else {
+            self.ramp_sum(scene, colors, &RampSum::new([r, g, b]))
+        };
+
+        // the new alpha, as (0, 0, 0, A')
+        let alpha = if a.is_identity() {
+            let alpha_only = ColorMatrix([zero, zero, zero, F32x4::new(0.0, 0.0, 0.0, 1.0), zero]);
+            (input, alpha_only)
+        } else {
+            let render_target = self.push_target(scene);
+            let gray = ColorMatrix([zero, zero, zero, F32x4::new(1.0, 1.0, 1.0, 0.0), F32x4::new(0.0, 0.0, 0.0, 1.0)]);
+            self.draw_result(scene, input, subregion, Some(PatternFilter::ColorMatrix(gray)), BlendMode::SrcOver);
+            scene.pop_render_target();
+            let gray = self.ramp_sum(scene, FilterResult { render_target, subregion }, &RampSum::new([a, a, a]));
+            let red_to_alpha = ColorMatrix([F32x4::new(0.0, 0.0, 0.0, 1.0), zero, zero, zero, zero]);
+            (gray, red_to_alpha)
+        };
+
+        let render_target = self.push_target(scene);
+        self.draw_result(scene, alpha.0, subregion, Some(PatternFilter::ColorMatrix(alpha.1)), BlendMode::SrcOver);
+        self.draw_result(scene, colors, subregion, None, BlendMode::SrcIn);
+        scene.pop_render_target();
+        FilterResult { render_target, subregion }
+    }
+    /// evaluate `sum` on the opaque `layer`: the rising and the falling segments are added up with `Lighter` blending
+    /// into separate opaque layers, and the difference of the two is the result.
+    fn ramp_sum(&self, scene: &mut Scene, layer: FilterResult, sum: &RampSum) -> FilterResult {
+        let subregion = layer.subregion;
+        let ramps: Vec<FilterResult> = sum.ramps.iter().map(|ramp| {
+            let render_target = self.push_target(scene);
+            self.draw_result(scene, layer, subregion, Some(PatternFilter::ColorMatrix(ramp.matrix())), BlendMode::SrcOver);
+            scene.pop_render_target();
+            FilterResult { render_target, subregion }
+        }).collect();
+
+        let add_up = |scene: &mut Scene, initial: [f32; 3], weights: Vec<[f32; 3]>| {
+            let render_target = self.push_target(scene);
+            let [r, g, b] = initial;
+            self.fill(scene, subregion, ColorF::new(r, g, b, 1.0).to_u8());
+            for (&ramp, weights) in ramps.iter().zip(weights) {
+                if weights != [0.0; 3] {
+                    self.draw_result(scene, ramp, subregion, Some(PatternFilter::ColorMatrix(weight_matrix(weights))), BlendMode::Lighter);
+                }
+            }
+            scene.pop_render_target();
+            FilterResult { render_target, subregion }
+        };
+        let rising = add_up(scene, sum.initial, sum.weights(1.0).collect());
+        let falling: Vec<[f32; 3]> = sum.weights(-1.0).collect();
+        if falling.iter().all(|&w| w == [0.0; 3]) {
+            return rising;
+        }
+        let falling = add_up(scene, [0.0; 3], falling);
+
+        let render_target = self.push_target(scene);
+        self.draw_result(scene, rising, subregion, None, BlendMode::SrcOver);
+        self.draw_result(scene, falling, subregion, None, BlendMode::Difference);
+        scene.pop_render_target();
+        FilterResult { render_target, subregion }
+    }
+}
+
+/// `<feImage>` referencing an element: the element is drawn (with its own transform) like a `<use>`,
+/// in the user space of the filtered element and shifted by the `x`/`y` of the primitive.
+/// The result takes the place of the source graphic.
+fn draw_fe_image(image: &FeImage, scene: &mut Scene, options: &DrawOptions) {
+    let href = match image.href {
+        Some(ref href) => href,
+        None => return
+    };
+    let item = match options.ctx.resolve_href(href) {
+        Some(item) => item,
+        None => {
+            warn!("feImage: can't resolve {:?}", href);
+            return;
+        }
+    };
+    let mut options = options.clone();
+    let x = image.x.map(|x| x.resolve(&options)).unwrap_or(0.0);
+    let y = image.y.map(|y| y.resolve(&options)).unwrap_or(0.0);
+    options.apply_transform(Transform2F::from_translation(vec2f(x, y)));
+    options.depth += 1;
+    if options.too_deep() {
+        return;
+    }
+    item.draw_to(scene, &options);
 }
 
 enum FilterState {
     GaussianBlur(GaussianBlurInfo),
     ColorMatrix(ColorMatrixInfo),
+    Blend(BlendInfo),
 }
 impl FilterState {
-    fn pre(filter: &Filter, scene: &mut Scene, outline_bounds: RectF, options: &mut DrawOptions) -> FilterState {
-        match *filter {
+    /// `None` if nothing of the content is inside the filter `region`
+    fn pre(filter: &Filter, scene: &mut Scene, outline_bounds: RectF, region: RectF, options: &mut DrawOptions) -> Option<FilterState> {
+        Some(match *filter {
             Filter::GaussianBlur(ref f) => {
                 let sigma = options.transform.extract_scale() * f.std_deviation;
-                let bounds = outline_bounds.dilate(sigma * 3.0).round_out().to_i32();
-        
+                // one extra pixel, so that rounding out never leaves the kernel reaching the edge
+                let bounds = outline_bounds.dilate(sigma * BLUR_KERNEL_SIGMAS + Vector2F::splat(1.0)).round_out().to_i32();
+                // the content outside of the region still contributes to the blur, only the output is cut off
+                let region = bounds.to_f32().intersection(region)?;
+
                 let render_target_y = RenderTarget::new(bounds.size(), String::new());
                 let render_target_id_y = scene.push_render_target(render_target_y);
                 let render_target_x = RenderTarget::new(bounds.size(), String::new());
@@ -45,11 +652,12 @@ impl FilterState {
                     render_target_id_x,
                     render_target_id_y,
                     sigma,
-                    bounds
+                    bounds,
+                    region,
                 })
             }
-            Filter::ColorMatrix(filter) => {
-                let bounds = outline_bounds.round_out().to_i32();
+            Filter::ColorMatrix(ref filter) => {
+                let bounds = outline_bounds.intersection(region)?.round_out().to_i32();
                 let render_target = RenderTarget::new(bounds.size(), String::new());
                 let render_target_id = scene.push_render_target(render_target);
                 options.transform = Transform2F::from_translation(-bounds.origin().to_f32()) * options.transform;
@@ -57,10 +665,27 @@ impl FilterState {
                 FilterState::ColorMatrix(ColorMatrixInfo {
                     render_target_id,
                     bounds,
-                    filter, 
+                    matrix: filter.matrix,
                 })
             }
-        }
+            // `in2` is taken to be the backdrop: the result of `in` is composited onto it with `mode`,
+            // which pathfinder implements with the separable and non-separable formulas of the compositing spec.
+            Filter::Blend(ref filter) => {
+                let bounds = outline_bounds.intersection(region)?.round_out().to_i32();
+                let render_target = RenderTarget::new(bounds.size(), String::new());
+                let render_target_id = scene.push_render_target(render_target);
+                options.transform = Transform2F::from_translation(-bounds.origin().to_f32()) * options.transform;
+
+                FilterState::Blend(BlendInfo {
+                    render_target_id,
+                    bounds,
+                    mode: filter.mode,
+                })
+            }
+            Filter::Image(_) | Filter::Flood(_) | Filter::Tile(_) | Filter::Composite(_) | Filter::ComponentTransfer(_) | Filter::DropShadow(_) | Filter::Offset(_) | Filter::Merge(_) => {
+                unreachable!("only primitives of the source graphic are applied directly")
+            }
+        })
     }
     fn post(self, scene: &mut Scene, options: &DrawOptions) {
         match self {
@@ -69,15 +694,16 @@ impl FilterState {
                     render_target_id_x,
                     render_target_id_y,
                     sigma,
-                    bounds
+                    bounds,
+                    region,
                 } = info;
         
                 let mut paint_x = Pattern::from_render_target(render_target_id_x, bounds.size());
                 let mut paint_y = Pattern::from_render_target(render_target_id_y, bounds.size());
                 paint_y.apply_transform(Transform2F::from_translation(bounds.origin().to_f32()));
         
-                paint_x.set_filter(Some(PatternFilter::Blur { direction: BlurDirection::X, sigma: sigma.x() }));
-                paint_y.set_filter(Some(PatternFilter::Blur { direction: BlurDirection::Y, sigma: sigma.y() }));
+                paint_x.set_filter(blur_filter(BlurDirection::X, sigma.x()));
+                paint_y.set_filter(blur_filter(BlurDirection::Y, sigma.y()));
         
                 let paint_id_x = scene.push_paint(&Paint::from_pattern(paint_x));
                 let paint_id_y = scene.push_paint(&Paint::from_pattern(paint_y));
@@ -86,7 +712,7 @@ impl FilterState {
                 // TODO(pcwalton): Apply clip as necessary.
                 let outline_x = Outline::from_rect(RectF::new(vec2f(0.0, 0.0), bounds.size().to_f32()));
                 let path_x = DrawPath::new(outline_x, paint_id_x);
-                let outline_y = Outline::from_rect(bounds.to_f32());
+                let outline_y = Outline::from_rect(region);
                 let path_y = DrawPath::new(outline_y, paint_id_y);
                 //path_y.set_clip_path(clip_path);
         
@@ -99,23 +725,48 @@ impl FilterState {
                 let ColorMatrixInfo {
                     render_target_id,
                     bounds,
-                    filter
+                    matrix
                 } = info;
 
-                let mut paint = Pattern::from_render_target(render_target_id, bounds.size());
-                let (matrix, blend_mode) = match filter {
-                    FeColorMatrix::Matrix(columns) => (ColorMatrix(columns), BlendMode::SrcOver),
-                    FeColorMatrix::Saturate(saturation) => (ColorMatrix::saturate(saturation), BlendMode::SrcOver),
-                    FeColorMatrix::HueRotate(radians) => (ColorMatrix::hue_rotate(radians), BlendMode::SrcOver),
-                    FeColorMatrix::LuminanceToAlpha => (ColorMatrix::luminance_to_alpha(), BlendMode::DestIn),
+                let (matrix, blend_mode) = match matrix {
+                    ColorMatrixType::Matrix(columns) => (ColorMatrix(columns), BlendMode::SrcOver),
+                    ColorMatrixType::Saturate(saturation) => (ColorMatrix::saturate(saturation), BlendMode::SrcOver),
+                    ColorMatrixType::HueRotate(radians) => (ColorMatrix::hue_rotate(radians), BlendMode::SrcOver),
+                    ColorMatrixType::LuminanceToAlpha => (ColorMatrix::luminance_to_alpha(), BlendMode::DestIn),
                 };
+                scene.pop_render_target();
+
+                // The matrix can produce values outside of [0, 1]. Blending them directly would carry them
+                // into the backdrop (a negative channel darkens it), so the matrix is applied into a render target first,
+                // which clamps every channel when it is stored, and the clamped result is blended.
+                let clamped = scene.push_render_target(RenderTarget::new(bounds.size(), String::new()));
+                let mut paint = Pattern::from_render_target(render_target_id, bounds.size());
                 paint.set_filter(Some(PatternFilter::ColorMatrix(matrix)));
+                let paint_id = scene.push_paint(&Paint::from_pattern(paint));
+                scene.push_draw_path(DrawPath::new(Outline::from_rect(RectF::new(Vector2F::zero(), bounds.size().to_f32())), paint_id));
+                scene.pop_render_target();
+
+                let mut paint = Pattern::from_render_target(clamped, bounds.size());
+                paint.apply_transform(Transform2F::from_translation(bounds.origin().to_f32()));
+                let paint_id = scene.push_paint(&Paint::from_pattern(paint));
+                let mut path = DrawPath::new(Outline::from_rect(bounds.to_f32()), paint_id);
+                path.set_blend_mode(blend_mode);
+                scene.push_draw_path(path);
+            }
+            FilterState::Blend(info) => {
+                let BlendInfo {
+                    render_target_id,
+                    bounds,
+                    mode
+                } = info;
+
+                let mut paint = Pattern::from_render_target(render_target_id, bounds.size());
                 paint.apply_transform(Transform2F::from_translation(bounds.origin().to_f32()));
 
                 let paint_id = scene.push_paint(&Paint::from_pattern(paint));
                 let outline = Outline::from_rect(bounds.to_f32());
                 let mut path = DrawPath::new(outline, paint_id);
-                path.set_blend_mode(blend_mode);
+                path.set_blend_mode(mode);
 
                 scene.pop_render_target();
                 scene.push_draw_path(path);
@@ -127,11 +778,323 @@ impl FilterState {
 struct GaussianBlurInfo {
     sigma: Vector2F,
     bounds: RectI,
+    /// the part of `bounds` inside of the filter region, in scene pixels
+    region: RectF,
     render_target_id_y: RenderTargetId,
     render_target_id_x: RenderTargetId,
 }
 struct ColorMatrixInfo {
     bounds: RectI,
     render_target_id: RenderTargetId,
-    filter: FeColorMatrix,
-}
\ No newline at end of file
+    matrix: ColorMatrixType,
+}
+struct BlendInfo {
+    bounds: RectI,
+    render_target_id: RenderTargetId,
+    mode: BlendMode,
+}
+
+#[test]
+fn test_filter_stripes_in_circle() {
+    // a red line, composited onto itself in a taller subregion, is repeated and then cut to the circle
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 40">
+        <filter id="stripes">
+            <feFlood flood-color="red" x="0" y="0" width="40" height="2" result="line"/>
+            <feComposite in="line" in2="line" x="0" y="0" width="40" height="4" result="tile"/>
+            <feTile in="tile" result="lines"/>
+            <feComposite in="lines" in2="SourceAlpha" operator="in"/>
+        </filter>
+        <g filter="url(#stripes)"><circle cx="20" cy="20" r="20"/></g>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let image = ctx.rasterize(None).image;
+
+    assert_eq!(image.get_pixel(20, 5).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(20, 7).0[3], 0);
+    assert_eq!(image.get_pixel(20, 33).0, [255, 0, 0, 255]);
+    // outside of the circle
+    assert_eq!(image.get_pixel(1, 1).0[3], 0);
+}
+
+#[test]
+fn test_color_matrix_clamps() {
+    let render = |values: &str, content: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">
+            <filter id="f"><feColorMatrix type="matrix" values="{}"/></filter>
+            {}
+        </svg>"#, values, content)).unwrap();
+        DrawContext::new_without_fonts(&svg).rasterize(None).image
+    };
+
+    // adding 1 to every channel of gray saturates to white instead of overflowing
+    let white = render("1 0 0 0 1  0 1 0 0 1  0 0 1 0 1  0 0 0 1 0", r#"<rect width="10" height="10" fill="gray" filter="url(#f)"/>"#);
+    assert_eq!(white.get_pixel(5, 5).0, [255, 255, 255, 255]);
+
+    // half transparent red with a negative blue channel: the blue backdrop must show through at half intensity
+    let mixed = render(
+        "0.5 0 0 0 0  0 0 0 0 0  0 0 0 0 -1  0 0 0 0 0.5",
+        r#"<rect width="10" height="10" fill="blue"/><rect width="10" height="10" fill="red" filter="url(#f)"/>"#
+    );
+    let [r, g, b, a] = mixed.get_pixel(5, 5).0;
+    assert!((r as i32 - 128).abs() <= 2 && g == 0 && (b as i32 - 128).abs() <= 2 && a == 255, "{:?}", [r, g, b, a]);
+}
+
+#[test]
+fn test_gaussian_blur_edge() {
+    let render = |filter: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 20">
+            {}
+            <rect x="10" y="5" width="20" height="10" filter="url(#f)"/>
+        </svg>"#, filter)).unwrap();
+        DrawContext::new_without_fonts(&svg).rasterize(None).image
+    };
+    let alpha = |image: &::image::RgbaImage, x, y| image.get_pixel(x, y).0[3];
+
+    let sharp = render(r#"<filter id="f"/>"#);
+    assert_eq!((alpha(&sharp, 8, 10), alpha(&sharp, 11, 10)), (0, 255));
+
+    // blurred horizontally only, over a region covering the whole image
+    let blurred = render(r#"<filter id="f" filterUnits="userSpaceOnUse" x="0" y="0" width="40" height="20">
+        <feGaussianBlur stdDeviation="2 0"/>
+    </filter>"#);
+    let profile: Vec<u8> = (5 .. 15).map(|x| alpha(&blurred, x, 10)).collect();
+    assert!(profile.windows(2).all(|w| w[0] <= w[1]), "{:?}", profile);
+    assert!(alpha(&blurred, 6, 10) > 0 && alpha(&blurred, 11, 10) < 255, "{:?}", profile);
+    assert!((alpha(&blurred, 10, 10) as i32 - 128).abs() < 40, "{:?}", profile);
+    // the vertical edge stays sharp
+    assert_eq!((alpha(&blurred, 20, 4), alpha(&blurred, 20, 5)), (0, 255));
+
+    // the default region only reaches 10% of the bounding box (2 units) past the edge
+    let clipped = render(r#"<filter id="f"><feGaussianBlur stdDeviation="2 0"/></filter>"#);
+    assert!(alpha(&clipped, 8, 10) > 0);
+    assert_eq!(alpha(&clipped, 7, 10), 0);
+}
+
+#[test]
+fn test_desaturate() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">
+        <filter id="f"><feColorMatrix type="saturate" values="0"/></filter>
+        <rect width="10" height="10" fill="red" filter="url(#f)"/>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    let [r, g, b, a] = image.get_pixel(5, 5).0;
+    // the luminance of red, in all channels
+    assert!(r > 0 && r < 255, "{:?}", [r, g, b, a]);
+    assert!((r as i32 - g as i32).abs() <= 1 && (g as i32 - b as i32).abs() <= 1, "{:?}", [r, g, b, a]);
+    assert_eq!(a, 255);
+}
+
+#[test]
+fn test_drop_shadow() {
+    // the region covers the whole image, the default one would cut the shadow off
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 40">
+        <filter id="f" filterUnits="userSpaceOnUse" x="0" y="0" width="40" height="40">
+            <feDropShadow dx="4" dy="4" stdDeviation="1"/>
+        </filter>
+        <rect x="10" y="10" width="15" height="15" fill="white" filter="url(#f)"/>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).into_straight();
+    let pixel = |x, y| image.get_pixel(x, y).0;
+
+    // the square stays on top
+    assert_eq!(pixel(17, 17), [255, 255, 255, 255]);
+    // a dark copy below and to the right of it, nothing on the other side
+    let [r, g, b, a] = pixel(27, 20);
+    assert!(r < 50 && g < 50 && b < 50 && a > 200, "{:?}", pixel(27, 20));
+    assert_eq!(pixel(8, 20)[3], 0);
+    // with a soft edge
+    assert!(pixel(28, 20)[3] < 255 && pixel(29, 20)[3] > 0 && pixel(29, 20)[3] < 255);
+}
+
+#[test]
+fn test_blur_source_alpha_chain() {
+    // the classic drop shadow chain: blurred alpha of the source, moved, below the source
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 40">
+        <filter id="f" filterUnits="userSpaceOnUse" x="0" y="0" width="40" height="40">
+            <feGaussianBlur in="SourceAlpha" stdDeviation="0.5" result="blur"/>
+            <feOffset in="blur" dx="10" dy="10" result="shadow"/>
+            <feMerge><feMergeNode in="shadow"/><feMergeNode in="SourceGraphic"/></feMerge>
+        </filter>
+        <rect x="5" y="5" width="15" height="15" fill="red" filter="url(#f)"/>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    assert_eq!(image.get_pixel(12, 12).0, [255, 0, 0, 255]);
+    // the shadow is black, not a blurred copy of the red source
+    let [r, g, b, a] = image.get_pixel(27, 27).0;
+    assert!(r < 5 && g < 5 && b < 5 && a > 250, "{:?}", [r, g, b, a]);
+}
+
+#[test]
+fn test_color_interpolation_filters() {
+    // a blur between red and blue
+    let blurred = |color_interpolation: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 10">
+            <filter id="f" filterUnits="userSpaceOnUse" x="0" y="0" width="20" height="10">
+                <feGaussianBlur stdDeviation="2"/>
+            </filter>
+            <g filter="url(#f)" color-interpolation-filters="{}"><rect width="10" height="10" fill="red"/><rect x="10" width="10" height="10" fill="blue"/></g>
+        </svg>"#, color_interpolation)).unwrap();
+        DrawContext::new_without_fonts(&svg).rasterize(None).image
+    };
+    let (linear, srgb) = (blurred("linearRGB"), blurred("sRGB"));
+    // in linearRGB, the mix in the middle is lighter: both red and blue are higher than in sRGB
+    let [lr, lg, lb, la] = linear.get_pixel(10, 5).0;
+    let [sr, _, sb, sa] = srgb.get_pixel(10, 5).0;
+    assert!(lr > sr + 30 && lb > sb + 30, "linear {:?}, sRGB {:?}", linear.get_pixel(10, 5), srgb.get_pixel(10, 5));
+    assert!(lg <= 2);
+    // the color space does not change the alpha
+    assert!((la as i32 - sa as i32).abs() <= 1, "{} != {}", la, sa);
+}
+
+#[test]
+fn test_offset_blend() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 20">
+        <filter id="f" filterUnits="userSpaceOnUse" x="0" y="0" width="40" height="20">
+            <feOffset dx="10" result="moved"/>
+            <feBlend in="SourceGraphic" in2="moved"/>
+        </filter>
+        <rect y="5" width="8" height="10" filter="url(#f)"/>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    let alpha = |x| image.get_pixel(x, 10).0[3];
+    // the original and the moved copy, with a gap between them
+    assert_eq!((alpha(4), alpha(14)), (255, 255));
+    assert_eq!((alpha(9), alpha(25)), (0, 0));
+}
+
+#[test]
+fn test_stacked_drop_shadows() {
+    let render = |primitives: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 30">
+            <filter id="f" filterUnits="userSpaceOnUse" x="0" y="0" width="40" height="30">{}</filter>
+            <rect x="10" y="5" width="10" height="10" fill="white" filter="url(#f)"/>
+        </svg>"#, primitives)).unwrap();
+        DrawContext::new_without_fonts(&svg).rasterize(None).into_straight()
+    };
+
+    let image = render(r#"
+        <feDropShadow dx="6" dy="0" stdDeviation="0" flood-color="red"/>
+        <feDropShadow dx="0" dy="6" stdDeviation="0" flood-color="blue"/>
+    "#);
+    assert_eq!(image.get_pixel(15, 10).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(23, 10).0, [255, 0, 0, 255]);
+    // the second shadow falls from the square and from the first shadow
+    assert_eq!(image.get_pixel(15, 18).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(23, 18).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(5, 10).0[3], 0);
+
+    // the soft edge of a shadow fades out in the flood color. The chain works in linearRGB,
+    // so this only holds if the flood color is converted to it along with the source.
+    let image = render(r#"<feDropShadow dx="6" dy="0" stdDeviation="2" flood-color="rgb(0, 128, 255)"/>"#);
+    let [r, g, b, a] = image.get_pixel(25, 10).0;
+    assert!(a > 60 && a < 240, "{:?}", [r, g, b, a]);
+    assert!(r <= 4 && (g as i32 - 128).abs() <= 4 && b >= 251, "{:?}", [r, g, b, a]);
+}
+
+#[test]
+fn test_flood_merge() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 40">
+        <filter id="f">
+            <feFlood flood-color="blue" result="background"/>
+            <feMerge><feMergeNode in="background"/><feMergeNode in="SourceGraphic"/></feMerge>
+        </filter>
+        <rect x="10" y="10" width="20" height="20" filter="url(#f)"/>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    assert_eq!(image.get_pixel(20, 20).0, [0, 0, 0, 255]);
+    // the default filter region reaches 2 units past the square
+    assert_eq!(image.get_pixel(9, 20).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(20, 31).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(5, 20).0[3], 0);
+    assert_eq!(image.get_pixel(20, 35).0[3], 0);
+}
+
+#[test]
+fn test_blend_multiply() {
+    // half transparent yellow behind half transparent red, worked out in sRGB
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 20">
+        <filter id="f" filterUnits="userSpaceOnUse" x="0" y="0" width="40" height="20">
+            <feFlood flood-color="yellow" flood-opacity="0.5" width="20" result="backdrop"/>
+            <feBlend mode="multiply" in="SourceGraphic" in2="backdrop"/>
+        </filter>
+        <rect x="10" width="20" height="20" fill="red" fill-opacity="0.5" filter="url(#f)" color-interpolation-filters="sRGB"/>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    let close = |x, y, expected: [u8; 4]| {
+        let pixel = image.get_pixel(x, y).0;
+        assert!(pixel.iter().zip(&expected).all(|(&a, &b)| (a as i32 - b as i32).abs() <= 2), "{:?} at ({}, {}), expected {:?}", pixel, x, y, expected);
+    };
+    close(5, 10, [128, 128, 0, 128]);
+    close(25, 10, [128, 0, 0, 128]);
+    // premultiplied (0.75, 0.25, 0, 0.75): each color shows through where the other one is transparent,
+    // and the overlap adds the product of the colors
+    close(15, 10, [191, 64, 0, 191]);
+}
+
+#[test]
+fn test_composite_arithmetic() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 20">
+        <filter id="average">
+            <feFlood flood-color="red" result="red"/>
+            <feFlood flood-color="blue" result="blue"/>
+            <feComposite operator="arithmetic" in="red" in2="blue" k2="0.5" k3="0.5"/>
+        </filter>
+        <filter id="product">
+            <feFlood flood-color="blue" result="blue"/>
+            <feComposite operator="arithmetic" in="SourceGraphic" in2="blue" k1="1" k4="0.25"/>
+        </filter>
+        <g color-interpolation-filters="sRGB">
+            <rect width="15" height="20" filter="url(#average)"/>
+            <rect x="24" y="5" width="12" height="10" fill="white" filter="url(#product)"/>
+        </g>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    let close = |x, y, expected: [u8; 4]| {
+        let pixel = image.get_pixel(x, y).0;
+        assert!(pixel.iter().zip(&expected).all(|(&a, &b)| (a as i32 - b as i32).abs() <= 2), "{:?} at ({}, {}), expected {:?}", pixel, x, y, expected);
+    };
+    close(7, 10, [128, 0, 128, 255]);
+    // white times blue, plus k4 on every channel
+    close(30, 10, [64, 64, 255, 255]);
+    // only k4 where the source graphic is transparent, but still inside the filter region
+    close(23, 10, [64, 64, 64, 64]);
+}
+
+#[test]
+fn test_component_transfer() {
+    let render = |funcs: &str, fill: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">
+            <filter id="f"><feComponentTransfer>{}</feComponentTransfer></filter>
+            <rect width="10" height="10" {} filter="url(#f)" color-interpolation-filters="sRGB"/>
+        </svg>"#, funcs, fill)).unwrap();
+        DrawContext::new_without_fonts(&svg).rasterize(None).image.get_pixel(5, 5).0
+    };
+    let close = |pixel: [u8; 4], expected: [u8; 4]| {
+        assert!(pixel.iter().zip(&expected).all(|(&a, &b)| (a as i32 - b as i32).abs() <= 2), "{:?}, expected {:?}", pixel, expected);
+    };
+    let gamma = r#"<feFuncR type="gamma" exponent="2.2"/><feFuncG type="gamma" exponent="2.2"/><feFuncB type="table" tableValues="1 0"/>"#;
+    // 255 * (128 / 255)^2.2 = 56
+    close(render(gamma, r#"fill="rgb(128, 128, 128)""#), [56, 56, 127, 255]);
+    // the functions see the color without alpha, the result is premultiplied again
+    close(render(gamma, r#"fill="rgb(128, 128, 128)" fill-opacity="0.5""#), [28, 28, 64, 128]);
+    // a new alpha scales the colors along with it
+    close(render(r#"<feFuncA type="discrete" tableValues="0 0.5"/>"#, r#"fill="rgb(128, 128, 128)""#), [64, 64, 64, 128]);
+}
+
+#[test]
+fn test_color_interpolation_filters_inherited() {
+    let render = |content: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 10">
+            <filter id="f" filterUnits="userSpaceOnUse" x="0" y="0" width="20" height="10"><feGaussianBlur stdDeviation="2"/></filter>
+            {}
+        </svg>"#, content)).unwrap();
+        DrawContext::new_without_fonts(&svg).rasterize(None).image
+    };
+    let rects = r#"<rect width="10" height="10" fill="red"/><rect x="10" width="10" height="10" fill="blue"/>"#;
+    let own = render(&format!(r#"<g filter="url(#f)" color-interpolation-filters="sRGB">{}</g>"#, rects));
+    let inherited = render(&format!(r#"<g color-interpolation-filters="sRGB"><g filter="url(#f)">{}</g></g>"#, rects));
+    let default = render(&format!(r#"<g filter="url(#f)">{}</g>"#, rects));
+    // the sRGB of the outer group applies to the filter of the inner one, unlike the default linearRGB
+    assert_eq!(inherited.get_pixel(10, 5), own.get_pixel(10, 5));
+    assert!(default.get_pixel(10, 5).0[0] > inherited.get_pixel(10, 5).0[0] + 30);
+}
@@ -0,0 +1,126 @@
+use crate::prelude::*;
+use pathfinder_content::{
+    fill::FillRule,
+    outline::{Contour, ContourIterFlags},
+};
+use pathfinder_renderer::{
+    scene::{ClipPathId, DrawPathId},
+    paint::Paint as PaPaint,
+};
+use std::sync::{Arc, Mutex};
+
+/// A fully resolved draw command, as it ends up in the scene.
+#[derive(Clone, Debug)]
+pub struct FlatDrawCmd {
+    /// the outline in root (scene) coordinates
+    pub outline: Outline,
+    pub paint: PaPaint,
+    pub fill_rule: FillRule,
+
+    /// the command is only visible inside all of these outlines (in root coordinates)
+    pub clip: Vec<(Outline, FillRule)>,
+}
+
+/// collects draw commands while a document is composed
+#[derive(Debug, Default)]
+pub struct Recorder {
+    cmds: Vec<FlatDrawCmd>,
+    // (id, outline, fill rule, parent clip path)
+    clip_paths: Vec<(ClipPathId, Outline, FillRule, Option<ClipPathId>)>,
+    // ids of the elements that are currently drawn, innermost last
+    elements: Vec<String>,
+    // draw paths in the scene and the id of the innermost element they belong to
+    element_ids: Vec<(DrawPathId, String)>,
+}
+impl Recorder {
+    /// take the recorder back once composing is done and all options holding it are dropped
+    pub fn unwrap_shared(recorder: Arc<Mutex<Recorder>>) -> Recorder {
+        Arc::try_unwrap(recorder).ok().expect("recorder still in use").into_inner().unwrap()
+    }
+    pub fn push_clip_path(&mut self, id: ClipPathId, outline: Outline, fill_rule: FillRule, parent: Option<ClipPathId>) {
+        self.clip_paths.push((id, outline, fill_rule, parent));
+    }
+    pub fn push_element(&mut self, id: &str) {
+        self.elements.push(id.into());
+    }
+    pub fn pop_element(&mut self) {
+        self.elements.pop();
+    }
+    pub fn push_draw_path(&mut self, path_id: DrawPathId, outline: Outline, paint: PaPaint, fill_rule: FillRule, clip_path: Option<ClipPathId>) {
+        if let Some(element) = self.elements.last() {
+            self.element_ids.push((path_id, element.clone()));
+        }
+        let mut clip = vec![];
+        let mut next = clip_path;
+        while let Some(id) = next {
+            match self.clip_paths.iter().find(|&&(other, ..)| other == id) {
+                Some(&(_, ref outline, fill_rule, parent)) => {
+                    clip.push((outline.clone(), fill_rule));
+                    next = parent;
+                }
+                None => break
+            }
+        }
+        self.cmds.push(FlatDrawCmd { outline, paint, fill_rule, clip });
+    }
+    pub fn into_cmds(self) -> Vec<FlatDrawCmd> {
+        self.cmds
+    }
+    pub fn into_element_ids(self) -> Vec<(DrawPathId, String)> {
+        self.element_ids
+    }
+}
+
+/// upper limit of the lines a single curve is flattened into, for tiny or broken tolerances
+const MAX_CURVE_LINES: f32 = 1024.0;
+
+/// replace the curves of `outline` by lines that deviate at most `tolerance` from them.
+///
+/// The number of lines per curve follows Wang's formula, which bounds the error of evenly spaced samples.
+pub fn flatten_curves(outline: &Outline, tolerance: f32) -> Outline {
+    let mut flat = Outline::new();
+    for contour in outline.contours() {
+        let mut flat_contour = Contour::new();
+        for segment in contour.iter(ContourIterFlags::empty()) {
+            if flat_contour.is_empty() {
+                flat_contour.push_endpoint(segment.baseline.from());
+            }
+            if segment.is_line() {
+                flat_contour.push_endpoint(segment.baseline.to());
+                continue;
+            }
+            let cubic = segment.to_cubic();
+            let (p0, p1, p2, p3) = (cubic.baseline.from(), cubic.ctrl.from(), cubic.ctrl.to(), cubic.baseline.to());
+            let m = (p0 - p1 * 2.0 + p2).length().max((p1 - p2 * 2.0 + p3).length());
+            let lines = (0.75 * m / tolerance).sqrt().ceil().max(1.0).min(MAX_CURVE_LINES) as usize;
+            let cubic = cubic.as_cubic_segment();
+            for i in 1 ..= lines {
+                flat_contour.push_endpoint(cubic.sample(i as f32 / lines as f32));
+            }
+        }
+        if contour.is_closed() {
+            flat_contour.close();
+        }
+        flat.push_contour(flat_contour);
+    }
+    flat
+}
+
+#[test]
+fn test_flattening_tolerance() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <circle r="1000"/>
+    </svg>"#).unwrap();
+    let lines = |tolerance| {
+        let mut ctx = DrawContext::new_without_fonts(&svg);
+        ctx.set_flattening_tolerance(tolerance);
+        let cmds = ctx.flatten();
+        assert_eq!(cmds.len(), 1);
+        let outline = &cmds[0].outline;
+        assert!(outline.contours().iter().all(|c| c.iter(ContourIterFlags::empty()).all(|s| s.is_line())));
+        outline.contours().iter().map(|c| c.len() as usize).sum::<usize>()
+    };
+    let fine = lines(0.1);
+    let coarse = lines(2.0);
+    assert!(fine > 2 * coarse, "{} lines at 0.1, {} at 2", fine, coarse);
+}
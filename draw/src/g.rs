@@ -1,6 +1,8 @@
 use crate::prelude::*;
 use std::sync::Arc;
-use crate::filter::apply_filter;
+use crate::filter::{apply_filter, filter_region};
+use crate::mask::apply_mask;
+use crate::opacity::{apply_opacity, opacity_without_layer};
 
 impl DrawItem for TagG {
     fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
@@ -9,6 +11,26 @@ impl DrawItem for TagG {
         }
 
         let options = options.apply(&self.attrs);
+        if options.too_deep() {
+            return None;
+        }
+        max_bounds(self.items.iter().flat_map(|item| item.bounds(&options)))
+    }
+    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
+        draw_items(scene, &self.items, &self.attrs, options);
+    }
+}
+
+impl DrawItem for TagA {
+    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        if !self.attrs.display {
+            return None;
+        }
+
+        let options = options.apply(&self.attrs);
+        if options.too_deep() {
+            return None;
+        }
         max_bounds(self.items.iter().flat_map(|item| item.bounds(&options)))
     }
     fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
@@ -16,69 +38,195 @@ impl DrawItem for TagG {
     }
 }
 
+impl TagSwitch {
+    /// the first child that is rendered and whose conditions are met
+    fn selected(&self, ctx: &DrawContext) -> Option<&Arc<Item>> {
+        self.items.iter().find(|item| {
+            let attrs = match item.attrs() {
+                Some(attrs) => attrs,
+                None => return false
+            };
+            match attrs.system_language {
+                Some(ref tags) => tags.iter().any(|tag| ctx.languages.iter().any(|lang| language_matches(tag, lang))),
+                None => true
+            }
+        })
+    }
+}
+
+impl DrawItem for TagSwitch {
+    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        if !self.attrs.display {
+            return None;
+        }
+
+        let options = options.apply(&self.attrs);
+        if options.too_deep() {
+            return None;
+        }
+        self.selected(options.ctx)?.bounds(&options)
+    }
+    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
+        if let Some(item) = self.selected(options.ctx) {
+            draw_items(scene, std::slice::from_ref(item), &self.attrs, options);
+        }
+    }
+}
+
 fn draw_items(scene: &mut Scene, items: &[Arc<Item>], attrs: &Attrs, options: &DrawOptions) {
     if !attrs.display {
         return;
     }
+    let opacity = attrs.opacity.resolve(options).unwrap_or(1.0);
+
+    // the bounds of the children are in scene space, the bounding box is wanted in the user space of the group
+    let options = options.apply_with_bbox(scene, attrs, |options| {
+        let bounds_options = BoundsOptions { common: options.clone(), clip_rect: None };
+        let bounds = max_bounds(items.iter().flat_map(|item| item.bounds(&bounds_options)))?;
+        Some(options.transform.inverse() * bounds)
+    });
+    if options.too_deep() {
+        return;
+    }
+
+    let bounds_of_items = |options: &BoundsOptions| max_bounds(items.iter().flat_map(|item| item.bounds(options)));
+    let draw_children = |scene: &mut Scene, options: &DrawOptions| {
+        for item in items {
+            item.draw_to(scene, options);
+        }
+    };
+
+    // overlapping content has to be composited as a whole, after it is filtered, clipped and masked
+    if opacity < 1.0 && !opacity_without_layer(items, &options) {
+        let bounds_options = options.bounds_options();
+        let mut bounds = get_or_return!(max_bounds(items.iter().flat_map(|item| item.bounds(&bounds_options))));
+        if let Some(Iri(ref filter_id)) = attrs.filter {
+            if let Some(Item::Filter(filter)) = options.ctx.resolve(&filter_id).map(|i| &**i) {
+                bounds = filter_region(filter, &options, bounds);
+            }
+        }
+        apply_opacity(scene, &options, bounds, |scene, options| draw_masked(scene, attrs, options, &bounds_of_items, &draw_children));
+        return;
+    }
+
+    draw_masked(scene, attrs, &options, &bounds_of_items, &draw_children);
+}
+
+/// draw an element that is not a container, with its `filter` and `mask`.
+/// They are applied as if the element was the only child of a group with just these two attributes.
+pub fn draw_with_effects(item: &Item, scene: &mut Scene, options: &DrawOptions, draw: impl Fn(&mut Scene, &DrawOptions)) {
+    let attrs = match leaf_attrs(item) {
+        Some(attrs) => attrs,
+        None => return draw(scene, options)
+    };
+    if !attrs.display || (attrs.filter.is_none() && attrs.mask.is_none()) {
+        return draw(scene, options);
+    }
+    draw_masked(scene, attrs, options, &|options| item.bounds(options), &draw);
+}
+
+/// the attributes of `item` if it is not a container, which applies them itself
+pub fn leaf_attrs(item: &Item) -> Option<&Attrs> {
+    match *item {
+        Item::G(_) | Item::A(_) | Item::Switch(_) | Item::Svg(_) | Item::Use(_) => None,
+        ref item => item.attrs()
+    }
+}
 
-    let options = options.apply(scene, attrs);
+// `bounds` gives the bounds of the content in scene space and `draw` draws it
+fn draw_masked(scene: &mut Scene, attrs: &Attrs, options: &DrawOptions, bounds: &dyn Fn(&BoundsOptions) -> Option<RectF>, draw: &dyn Fn(&mut Scene, &DrawOptions)) {
+    // the filter is applied to the content before it is clipped and masked
+    if let Some(Iri(ref mask_id)) = attrs.mask {
+        match options.ctx.resolve(&mask_id).map(|i| &**i) {
+            Some(Item::Mask(mask)) => {
+                let bounds_options = options.bounds_options();
+                let content_bounds = get_or_return!(bounds(&bounds_options));
+                apply_mask(mask, scene, options, content_bounds, |scene, options| draw_filtered(scene, attrs, options, bounds, draw));
+                return;
+            }
+            r => warn!("expected mask for {:?}, got {:?}", mask_id, r)
+        }
+    }
 
+    draw_filtered(scene, attrs, options, bounds, draw);
+}
+fn draw_filtered(scene: &mut Scene, attrs: &Attrs, options: &DrawOptions, bounds: &dyn Fn(&BoundsOptions) -> Option<RectF>, draw: &dyn Fn(&mut Scene, &DrawOptions)) {
     if let Some(Iri(ref filter_id)) = attrs.filter {
         let bounds_options = options.bounds_options();
-        let bounds = get_or_return!(max_bounds(items.iter().flat_map(|item| item.bounds(&bounds_options))));
+        let bounds = get_or_return!(bounds(&bounds_options));
 
         match options.ctx.resolve(&filter_id).map(|i| &**i) {
             Some(Item::Filter(filter)) => {
-                apply_filter(filter, scene, &options, bounds, |scene, options| {
-                    for item in items {
-                        item.as_ref().draw_to(scene, options);
-                    }
-                });
+                // filter primitives (like `feOffset`) can move content from anywhere into the cull region
+                let mut options = options.clone();
+                options.cull_region = None;
+                apply_filter(filter, scene, &options, bounds, draw);
                 return;
             },
             r => println!("expected filter for {:?}, got {:?}", filter_id, r)
         }
     }
 
-    for item in items.iter() {
-        item.draw_to(scene, &options);
-    }
+    draw(scene, options);
 }
-fn content_transform<'a>(tag: &TagUse, options: &mut Options<'a>, item: &Item) {
+/// `options` already contain the transform of the `<use>` (applied on top of the parent's).
+/// Then `x`/`y` and last the fit of the viewBox of a referenced symbol or svg are applied:
+/// `parent * use.transform * translate(x, y) * viewBox fit`.
+pub fn content_transform<'a>(tag: &TagUse, options: &mut Options<'a>, item: &Item) {
     let pos = tag.pos.resolve(&options);
     options.apply_transform(Transform2F::from_translation(pos));
     match *item {
-        Item::Symbol(TagSymbol { view_box: Some(ref view_box), .. }) |
-        Item::Svg(TagSvg { view_box: Some(ref view_box), .. }) => {
-            options.apply_viewbox(tag.width, tag.height, view_box);
+        Item::Symbol(TagSymbol { view_box: Some(ref view_box), ref preserve_aspect_ratio, .. }) |
+        Item::Svg(TagSvg { view_box: Some(ref view_box), ref preserve_aspect_ratio, .. }) => {
+            options.apply_viewbox(tag.width, tag.height, view_box, preserve_aspect_ratio.as_ref());
         }
         _ => {}
     }
 }
 
+/// the bounds of the content a `<use>` references. A `<symbol>` is only drawn through a `<use>`, so it has no bounds of its own.
+fn referenced_bounds(item: &Item, options: &BoundsOptions) -> Option<RectF> {
+    match *item {
+        Item::Symbol(TagSymbol { ref items, .. }) => max_bounds(items.iter().flat_map(|item| item.bounds(options))),
+        ref item => item.bounds(options),
+    }
+}
+
 impl DrawItem for TagUse {
     fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
         if !self.attrs.display {
             return None;
         }
         let mut options = options.apply(&self.attrs);
+        if options.too_deep() {
+            return None;
+        }
         let item = &**options.ctx.resolve_href(self.href.as_ref()?)?;
         content_transform(self, &mut options, item);
-        item.bounds(&options)
+        referenced_bounds(item, &options)
     }
     fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
         if !self.attrs.display {
             return;
         }
-        let mut options = options.apply(scene, &self.attrs);
         let href = get_ref_or_return!(self.href, "<use> without href");
         let item = get_or_return!(options.ctx.resolve_href(href), "can't resolve <use href={:?}>", href);
+        // the bounding box is that of the referenced content, in the user space of the `<use>`
+        let mut options = options.apply_with_bbox(scene, &self.attrs, |options| {
+            let mut bounds_options = BoundsOptions { common: options.clone(), clip_rect: None };
+            content_transform(self, &mut bounds_options, item);
+            Some(options.transform.inverse() * referenced_bounds(item, &bounds_options)?)
+        });
+        if options.too_deep() {
+            return;
+        }
         content_transform(&self, &mut options, item);
         debug!("item: {:?}", *item);
         match **item {
             Item::Symbol(TagSymbol { ref items, ref attrs, .. }) |
             Item::Svg(TagSvg { ref items, ref attrs, .. }) |
-            Item::G(TagG { ref items, ref attrs, ..}) => {
+            Item::G(TagG { ref items, ref attrs, ..}) |
+            Item::A(TagA { ref items, ref attrs, ..}) => {
                 draw_items(scene, &items, attrs, &options);
             }
             ref item => {
@@ -87,3 +235,130 @@ impl DrawItem for TagUse {
         }
     }
 }
+
+#[test]
+fn test_use_symbol_transform_order() {
+    let svg = Svg::from_str(r##"<svg xmlns="http://www.w3.org/2000/svg">
+        <symbol id="s" viewBox="0 0 10 10"><rect width="10" height="10"/></symbol>
+        <g transform="translate(100, 0)">
+            <use href="#s" x="5" y="7" width="20" height="20" transform="rotate(90)"/>
+        </g>
+    </svg>"##).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 1);
+
+    // scaled to 20x20, moved to (5, 7), rotated to (-27, 5) .. (-7, 25) and then moved by the parent
+    let bounds = cmds[0].outline.bounds();
+    let expected = RectF::from_points(vec2f(73., 5.), vec2f(93., 25.));
+    assert!((bounds.origin() - expected.origin()).length() < 1e-3, "{:?}", bounds);
+    assert!((bounds.lower_right() - expected.lower_right()).length() < 1e-3, "{:?}", bounds);
+}
+
+#[test]
+fn test_unknown_element_children() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:vendor="urn:example:vendor" version="1.1" baseProfile="full">
+        <vendor:wrapper fill="red"><rect width="10" height="10"/></vendor:wrapper>
+        <metadata><rect width="10" height="10"/></metadata>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].outline.bounds(), RectF::new(Vector2F::zero(), vec2f(10., 10.)));
+}
+
+#[test]
+fn test_group_opacity_without_layer() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <g opacity="0.5"><rect width="10" height="10" fill="blue"/></g>
+        <g opacity="0.5"><rect width="10" height="10" fill="blue"/><rect y="5" width="10" height="10" fill="blue"/></g>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let cmds = ctx.flatten();
+    // the single rect is drawn directly with half the alpha, the overlapping ones are composited as a layer
+    assert_eq!(cmds.len(), 2);
+    assert!(cmds[0].paint.pattern().is_none());
+    assert!(cmds[0].paint.base_color().a < 255);
+    assert!(cmds[1].paint.pattern().is_some());
+}
+
+#[test]
+fn test_switch_system_language() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <switch>
+            <rect systemLanguage="e" width="1" height="1"/>
+            <rect systemLanguage="de, en" width="2" height="2"/>
+            <rect width="3" height="3"/>
+        </switch>
+    </svg>"#).unwrap();
+    let mut ctx = DrawContext::new_without_fonts(&svg);
+    ctx.languages = vec!["en-GB".into()];
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].outline.bounds().size(), vec2f(2., 2.));
+
+    // no match, the fallback without conditions is used
+    ctx.languages = vec!["fr".into()];
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].outline.bounds().size(), vec2f(3., 3.));
+}
+
+#[test]
+fn test_switch_image() {
+    // the image is selected even though it has nothing to draw, so the fallback is not used
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <switch>
+            <image systemLanguage="en" width="1" height="1"/>
+            <rect width="3" height="3"/>
+        </switch>
+    </svg>"#).unwrap();
+    let mut ctx = DrawContext::new_without_fonts(&svg);
+    ctx.languages = vec!["en".into()];
+    assert_eq!(ctx.flatten().len(), 0);
+
+    ctx.languages = vec!["fr".into()];
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].outline.bounds().size(), vec2f(3., 3.));
+}
+
+#[test]
+fn test_unsupported_svg_element_children() {
+    // `fill="freeze"` is not a paint, the `<animateMotion>` must not be parsed like a group
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <defs><path id="p" d="M0 0 L10 0"/></defs>
+        <g>
+            <rect width="10" height="10"/>
+            <animateMotion fill="freeze" dur="1s" path="M0 0 L10 0"><mpath href="#p"/></animateMotion>
+        </g>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    assert_eq!(cmds.len(), 1);
+}
+
+#[test]
+fn test_anchor_children() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <a id="link" href="https://example.com"><rect x="5" y="5" width="10" height="20"/></a>
+    </svg>"#).unwrap();
+    assert_eq!(item!(svg, "link", A).href.as_deref(), Some("https://example.com"));
+    // the children of the link are drawn like those of a group
+    let ctx = DrawContext::new_without_fonts(&svg);
+    assert_eq!(ctx.compose().bounds(), RectF::new(vec2f(5., 5.), vec2f(10., 20.)));
+    assert_eq!(ctx.flatten().len(), 1);
+}
+
+#[test]
+fn test_deeply_nested_groups() {
+    let depth = 10_000;
+    let svg = format!(r#"<svg xmlns="http://www.w3.org/2000/svg"><rect width="10" height="10"/>{}<rect x="20" width="10" height="10"/>{}</svg>"#,
+        "<g>".repeat(depth), "</g>".repeat(depth));
+    let svg = Svg::from_str(&svg).unwrap();
+    // the content beyond the maximum depth is dropped, without overflowing the stack
+    let ctx = DrawContext::new_without_fonts(&svg);
+    ctx.compose();
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].outline.bounds(), RectF::new(vec2f(0., 0.), vec2f(10., 10.)));
+}
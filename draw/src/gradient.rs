@@ -1,5 +1,5 @@
 use crate::prelude::*;
-use pathfinder_content::gradient::{Gradient};
+use pathfinder_content::gradient::{Gradient, GradientWrap};
 use pathfinder_color::{ColorU};
 use pathfinder_geometry::line_segment::LineSegment2F;
 use pathfinder_simd::default::F32x2;
@@ -9,6 +9,8 @@ struct PartialLinearGradient<'a> {
     from: (Option<LengthX>, Option<LengthY>),
     to: (Option<LengthX>, Option<LengthY>),
     gradient_transform: Option<Transform2F>,
+    gradient_units: Option<GradientUnits>,
+    spread_method: Option<SpreadMethod>,
     stops: &'a [TagStop],
 }
 
@@ -17,15 +19,18 @@ struct PartialRadialGradient<'a> {
     focus: (Option<LengthX>, Option<LengthY>),
     radius: Option<Length>,
     gradient_transform: Option<Transform2F>,
+    gradient_units: Option<GradientUnits>,
+    spread_method: Option<SpreadMethod>,
     stops: &'a [TagStop],
 }
 
 pub trait BuildGradient {
-    fn build(&self, options: &Options, opacity: f32) -> Gradient;
+    /// `bounds` is the bounding box of the painted element in the coordinate system of `options.transform`.
+    fn build(&self, options: &Options, opacity: f32, bounds: RectF) -> Gradient;
 }
 
 impl BuildGradient for TagLinearGradient {
-    fn build(&self, options: &Options, opacity: f32) -> Gradient {
+    fn build(&self, options: &Options, opacity: f32, bounds: RectF) -> Gradient {
         if let Some(item) = self.href.as_ref().and_then(|href| options.ctx.resolve_href(&href)) {
             match &**item {
                 Item::LinearGradient(other) => {
@@ -33,16 +38,21 @@ impl BuildGradient for TagLinearGradient {
                         from: merge_point(&self.from, &other.from),
                         to: merge_point(&self.to, &other.to),
                         gradient_transform: self.gradient_transform.or(other.gradient_transform),
+                        gradient_units: self.gradient_units.or(other.gradient_units),
+                        spread_method: self.spread_method.or(other.spread_method),
                         stops: select_stops(&self.stops, &other.stops)
-                    }.build(options, opacity)
+                    }.build(options, opacity, bounds)
                 },
+                // only the attributes both kinds of gradients have are inherited
                 Item::RadialGradient(other) => {
                     return PartialLinearGradient {
                         from: self.from,
                         to: self.to,
-                        gradient_transform: self.gradient_transform,
+                        gradient_transform: self.gradient_transform.or(other.gradient_transform),
+                        gradient_units: self.gradient_units.or(other.gradient_units),
+                        spread_method: self.spread_method.or(other.spread_method),
                         stops: select_stops(&self.stops, &other.stops)
-                    }.build(options, opacity)
+                    }.build(options, opacity, bounds)
                 },
                 _ => {}
             }
@@ -52,8 +62,10 @@ impl BuildGradient for TagLinearGradient {
             from: self.from,
             to: self.to,
             gradient_transform: self.gradient_transform,
+            gradient_units: self.gradient_units,
+            spread_method: self.spread_method,
             stops: &self.stops
-        }.build(options, opacity)
+        }.build(options, opacity, bounds)
     }
 }
 
@@ -88,8 +100,8 @@ fn point_or_percent((x, y): (Option<LengthX>, Option<LengthY>), (dx, dy): (f64,
 }
 
 impl BuildGradient for TagRadialGradient {
-    fn build(&self, options: &Options, opacity: f32) -> Gradient {
-        if let Some(item) = self.href.as_ref().and_then(|href| options.ctx.resolve(&href[1..])) {
+    fn build(&self, options: &Options, opacity: f32, bounds: RectF) -> Gradient {
+        if let Some(item) = self.href.as_ref().and_then(|href| options.ctx.resolve_href(&href)) {
             match &**item {
                 Item::RadialGradient(ref other) => {
                     return PartialRadialGradient {
@@ -97,17 +109,21 @@ impl BuildGradient for TagRadialGradient {
                         focus: merge_point(&self.focus, &other.focus),
                         radius: self.radius.or(other.radius),
                         gradient_transform: self.gradient_transform.or(other.gradient_transform),
+                        gradient_units: self.gradient_units.or(other.gradient_units),
+                        spread_method: self.spread_method.or(other.spread_method),
                         stops: select_stops(&self.stops, &other.stops)
-                    }.build(options, opacity)
+                    }.build(options, opacity, bounds)
                 }
                 Item::LinearGradient(ref other) => {
                     return PartialRadialGradient {
                         center: self.center,
                         focus: self.focus,
                         radius: self.radius,
-                        gradient_transform: self.gradient_transform,
+                        gradient_transform: self.gradient_transform.or(other.gradient_transform),
+                        gradient_units: self.gradient_units.or(other.gradient_units),
+                        spread_method: self.spread_method.or(other.spread_method),
                         stops: select_stops(&self.stops, &other.stops)
-                    }.build(options, opacity)
+                    }.build(options, opacity, bounds)
                 }
                 _ => {}
             }
@@ -117,48 +133,303 @@ impl BuildGradient for TagRadialGradient {
             focus: self.focus,
             radius: self.radius,
             gradient_transform: self.gradient_transform,
+            gradient_units: self.gradient_units,
+            spread_method: self.spread_method,
             stops: &self.stops
-        }.build(options, opacity)
+        }.build(options, opacity, bounds)
     }
 }
 
 impl<'a> PartialLinearGradient<'a> {
-    fn build(self, options: &Options, opacity: f32) -> Gradient {
+    fn build(self, options: &Options, opacity: f32, bounds: RectF) -> Gradient {
+        let units = self.gradient_units.unwrap_or(GradientUnits::ObjectBoundingBox);
         let from = point_or_percent(self.from, (0., 0.));
         let to = point_or_percent(self.to, (100., 0.));
         let gradient_transform = self.gradient_transform.unwrap_or_default();
+        let spread = self.spread_method.unwrap_or(SpreadMethod::Pad);
 
-        let mut gradient = Gradient::linear_from_points(
-            from.resolve(options),
-            to.resolve(options),
-        );
-        for stop in self.stops {
-            gradient.add_color_stop(stop.color_u(opacity), stop.offset);
-        }
+        let from = resolve_point(from, units, options);
+        let to = resolve_point(to, units, options);
+        let mut gradient = match spread {
+            SpreadMethod::Reflect => Gradient::linear_from_points(from, from + (to - from) * 2.0),
+            _ => Gradient::linear_from_points(from, to),
+        };
+        add_stops(&mut gradient, self.stops, opacity, spread);
 
-        gradient.apply_transform(options.transform * gradient_transform);
+        gradient.apply_transform(gradient_space(options, units, gradient_transform, bounds));
         gradient
     }
 }
 impl<'a> PartialRadialGradient<'a> {
-    fn build(&self, options: &Options, opacity: f32) -> Gradient {
+    fn build(&self, options: &Options, opacity: f32, bounds: RectF) -> Gradient {
+        let units = self.gradient_units.unwrap_or(GradientUnits::ObjectBoundingBox);
         let center = point_or_percent(self.center, (50., 50.));
         let focus = Vector(self.focus.0.unwrap_or(center.0), self.focus.1.unwrap_or(center.1));
         let radius = length_or_percent(self.radius, 50.);
         let gradient_transform = self.gradient_transform.unwrap_or_default();
+        let spread = self.spread_method.unwrap_or(SpreadMethod::Pad);
 
-        let mut gradient = Gradient::radial(
-            LineSegment2F::new(
-                focus.resolve(options),
-                center.resolve(options)
+        let radius = match units {
+            GradientUnits::ObjectBoundingBox => bbox_fraction(radius),
+            // a percentage without a view box to refer to: like `r="0"`, the area is painted with the last stop
+            GradientUnits::UserSpaceOnUse => options.resolve_length(radius).unwrap_or_else(|| {
+                warn!("radialGradient: can't resolve r={:?} without a view box", radius);
+                0.0
+            }),
+        };
+        let center = resolve_point(center, units, options);
+        let focus = clamp_focus(resolve_point(focus, units, options), center, radius);
+        let mut gradient = match spread {
+            SpreadMethod::Reflect => Gradient::radial(
+                LineSegment2F::new(focus, focus + (center - focus) * 2.0),
+                F32x2::new(0.0, 2.0 * radius)
             ),
-            F32x2::new(0.0, options.resolve_length(radius).unwrap())
-        );
-        for stop in self.stops {
-            gradient.add_color_stop(stop.color_u(opacity), stop.offset);
-        }
+            _ => Gradient::radial(LineSegment2F::new(focus, center), F32x2::new(0.0, radius)),
+        };
+        add_stops(&mut gradient, self.stops, opacity, spread);
 
-        gradient.apply_transform(options.transform * gradient_transform);
+        gradient.apply_transform(gradient_space(options, units, gradient_transform, bounds));
         gradient
     }
-}
\ No newline at end of file
+}
+
+/// a focal point outside of the circle is moved onto its edge, towards the center
+fn clamp_focus(focus: Vector2F, center: Vector2F, radius: f32) -> Vector2F {
+    let offset = focus - center;
+    let distance = offset.length();
+    if distance > radius {
+        center + offset * (radius / distance)
+    } else {
+        focus
+    }
+}
+
+/// Add the color stops and set how the gradient continues past them.
+///
+/// Pathfinder can only clamp or repeat. A reflected gradient is built with twice the length
+/// (the callers double its geometry), the stops in the first half and mirrored in the second, and then repeated.
+fn add_stops(gradient: &mut Gradient, stops: &[TagStop], opacity: f32, spread: SpreadMethod) {
+    match spread {
+        SpreadMethod::Reflect => {
+            for stop in stops {
+                gradient.add_color_stop(stop.color_u(opacity), 0.5 * stop.offset);
+            }
+            for stop in stops.iter().rev() {
+                gradient.add_color_stop(stop.color_u(opacity), 1.0 - 0.5 * stop.offset);
+            }
+        }
+        _ => {
+            for stop in stops {
+                gradient.add_color_stop(stop.color_u(opacity), stop.offset);
+            }
+        }
+    }
+    gradient.wrap = match spread {
+        SpreadMethod::Pad => GradientWrap::Clamp,
+        SpreadMethod::Reflect | SpreadMethod::Repeat => GradientWrap::Repeat,
+    };
+}
+
+/// The transform from gradient coordinates to the device.
+///
+/// Points are first mapped by `gradientTransform`, then (for objectBoundingBox units)
+/// from the unit square onto the bounding box of the element, and finally by the user transform.
+/// Doing the bounding box mapping after `gradientTransform` keeps a rotated gradient aligned
+/// to the shape, even if the shape itself is rotated or not square.
+fn gradient_space(options: &Options, units: GradientUnits, gradient_transform: Transform2F, bounds: RectF) -> Transform2F {
+    let bbox_transform = match units {
+        GradientUnits::ObjectBoundingBox => Transform2F::from_translation(bounds.origin()) * Transform2F::from_scale(bounds.size()),
+        GradientUnits::UserSpaceOnUse => Transform2F::default(),
+    };
+    options.transform * bbox_transform * gradient_transform
+}
+
+fn resolve_point(point: Vector, units: GradientUnits, options: &Options) -> Vector2F {
+    match units {
+        GradientUnits::ObjectBoundingBox => vec2f(bbox_fraction((point.0).0), bbox_fraction((point.1).0)),
+        GradientUnits::UserSpaceOnUse => point.resolve(options),
+    }
+}
+
+/// in objectBoundingBox units, both `0.5` and `50%` are half the bounding box
+pub fn bbox_fraction(length: Length) -> f32 {
+    match length.unit {
+        LengthUnit::Percent => 0.01 * length.num as f32,
+        _ => length.num as f32
+    }
+}
+
+#[test]
+fn test_inherited_gradient_transform() {
+    use pathfinder_content::gradient::GradientGeometry;
+
+    let svg = Svg::from_str(r##"<svg xmlns="http://www.w3.org/2000/svg">
+        <linearGradient id="base" gradientUnits="userSpaceOnUse" gradientTransform="rotate(90)"><stop offset="0"/></linearGradient>
+        <linearGradient id="derived" href="#base" x1="0" y1="0" x2="10" y2="0"/>
+        <linearGradient id="own" href="#base" x1="0" y1="0" x2="10" y2="0" gradientTransform="translate(0 5)"/>
+        <radialGradient id="radial" gradientTransform="translate(5 0)"/>
+        <linearGradient id="from_radial" href="#radial" gradientUnits="userSpaceOnUse" x1="0" y1="0" x2="10" y2="0"/>
+    </svg>"##).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    options.set_transform(Transform2F::default());
+    let line = |id| {
+        let gradient = item!(svg, id, LinearGradient).build(&options, 1.0, RectF::default());
+        match gradient.geometry {
+            GradientGeometry::Linear(line) => line,
+            ref g => panic!("expected a linear gradient, got {:?}", g)
+        }
+    };
+    let close = |a: Vector2F, b: Vector2F| (a - b).length() < 1e-4;
+
+    // the coordinates of the referencing gradient, with the transform of the referenced one
+    assert!(close(line("derived").to(), vec2f(0., 10.)));
+    // its own transform wins
+    assert!(close(line("own").from(), vec2f(0., 5.)) && close(line("own").to(), vec2f(10., 5.)));
+    // the transform is inherited from the other kind of gradient as well
+    assert!(close(line("from_radial").to(), vec2f(15., 0.)));
+}
+
+#[test]
+fn test_radial_percent_radius_without_view_box() {
+    use pathfinder_content::gradient::GradientGeometry;
+
+    let svg = Svg::from_str(r##"<svg xmlns="http://www.w3.org/2000/svg">
+        <radialGradient id="g" gradientUnits="userSpaceOnUse" r="50%"><stop offset="0"/></radialGradient>
+    </svg>"##).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let mut options = Options::new(&ctx);
+    options.set_transform(Transform2F::default());
+    let gradient = item!(svg, "g", RadialGradient).build(&options, 1.0, RectF::default());
+    match gradient.geometry {
+        GradientGeometry::Radial { radii, .. } => assert_eq!(radii.y(), 0.0),
+        ref g => panic!("expected a radial gradient, got {:?}", g)
+    }
+}
+
+#[test]
+fn test_clamp_focus() {
+    let center = vec2f(5., 5.);
+    assert_eq!(clamp_focus(vec2f(6., 5.), center, 2.), vec2f(6., 5.));
+    assert_eq!(clamp_focus(vec2f(5., -5.), center, 2.), vec2f(5., 3.));
+}
+
+#[test]
+fn test_rotated_linear_gradient() {
+    // without the rotation, the color would only change from left to right
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20">
+        <linearGradient id="g" gradientUnits="userSpaceOnUse" x1="0" y1="0" x2="20" y2="0" gradientTransform="rotate(45)">
+            <stop offset="0" stop-color="red"/><stop offset="1" stop-color="blue"/>
+        </linearGradient>
+        <rect width="20" height="20" fill="url(#g)"/>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+
+    let top_right = image.get_pixel(18, 1).0;
+    let bottom_left = image.get_pixel(1, 18).0;
+    for (&a, &b) in top_right.iter().zip(bottom_left.iter()) {
+        assert!((a as i32 - b as i32).abs() <= 2, "{:?} != {:?}", top_right, bottom_left);
+    }
+    assert!(top_right[0] > 30 && top_right[2] > 30, "{:?}", top_right);
+    assert!(image.get_pixel(0, 0).0[0] > 200);
+    assert_eq!(image.get_pixel(19, 19).0, [0, 0, 255, 255]);
+}
+
+#[test]
+fn test_bounding_box_gradient_on_rotated_element() {
+    // the 16 x 8 rect is rotated to stand upright, its gradient from left to right now runs from top to bottom
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20">
+        <linearGradient id="g"><stop offset="0" stop-color="red"/><stop offset="1" stop-color="blue"/></linearGradient>
+        <rect x="-8" y="-4" width="16" height="8" transform="translate(10 10) rotate(90)" fill="url(#g)"/>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+
+    let top = image.get_pixel(10, 2).0;
+    let bottom = image.get_pixel(10, 17).0;
+    assert!(top[0] > 200 && top[2] < 55, "{:?}", top);
+    assert!(bottom[2] > 200 && bottom[0] < 55, "{:?}", bottom);
+    // the color does not change across the width of the rotated rect
+    let (left, right) = (image.get_pixel(7, 10).0, image.get_pixel(12, 10).0);
+    for (&a, &b) in left.iter().zip(right.iter()) {
+        assert!((a as i32 - b as i32).abs() <= 2, "{:?} != {:?}", left, right);
+    }
+}
+
+#[test]
+fn test_gradient_spread_method() {
+    let render = |spread: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 10">
+            <linearGradient id="g" gradientUnits="userSpaceOnUse" x1="0" y1="0" x2="10" y2="0" spreadMethod="{}">
+                <stop offset="0" stop-color="red"/><stop offset="1" stop-color="blue"/>
+            </linearGradient>
+            <rect width="40" height="10" fill="url(#g)"/>
+        </svg>"#, spread)).unwrap();
+        DrawContext::new_without_fonts(&svg).rasterize(None).image
+    };
+    let close = |a: [u8; 4], b: [u8; 4]| a.iter().zip(b.iter()).all(|(&x, &y)| (x as i32 - y as i32).abs() <= 3);
+
+    let pad = render("pad");
+    assert_eq!(pad.get_pixel(30, 5).0, [0, 0, 255, 255]);
+
+    // every 10 units, the bands start over from red
+    let repeat = render("repeat");
+    for &x in &[11, 21, 31] {
+        assert!(close(repeat.get_pixel(1, 5).0, repeat.get_pixel(x, 5).0), "{:?} at {}", repeat.get_pixel(x, 5), x);
+    }
+    assert!(repeat.get_pixel(1, 5).0[0] > repeat.get_pixel(8, 5).0[0]);
+
+    // every other band runs from blue back to red
+    let reflect = render("reflect");
+    assert!(close(reflect.get_pixel(8, 5).0, reflect.get_pixel(11, 5).0));
+    assert!(close(reflect.get_pixel(1, 5).0, reflect.get_pixel(18, 5).0));
+    assert!(close(reflect.get_pixel(1, 5).0, reflect.get_pixel(21, 5).0));
+    assert!(reflect.get_pixel(18, 5).0[0] > reflect.get_pixel(11, 5).0[0]);
+}
+
+#[test]
+fn test_user_space_gradient_across_shapes() {
+    // both rects show their part of the same sweep, instead of each running through all of it
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 10">
+        <linearGradient id="g" gradientUnits="userSpaceOnUse" x1="0" y1="0" x2="40" y2="0">
+            <stop offset="0" stop-color="black"/><stop offset="1" stop-color="red"/>
+        </linearGradient>
+        <rect width="10" height="10" fill="url(#g)"/>
+        <rect x="10" width="30" height="5" fill="url(#g)"/>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    let red: Vec<i32> = (0 .. 40).map(|x| image.get_pixel(x, 2).0[0] as i32).collect();
+    for pair in red.windows(2) {
+        assert!(pair[1] >= pair[0] && pair[1] - pair[0] <= 16, "{:?}", red);
+    }
+    assert!(red[0] < 16 && red[39] > 240, "{:?}", red);
+}
+
+#[test]
+fn test_radial_gradient_focus() {
+    let render = |focus: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 20">
+            <radialGradient id="g" {}>
+                <stop offset="0" stop-color="red"/><stop offset="1" stop-color="black"/>
+            </radialGradient>
+            <rect width="20" height="20" fill="url(#g)"/>
+        </svg>"#, focus)).unwrap();
+        DrawContext::new_without_fonts(&svg).rasterize(None).image
+    };
+    let red = |image: &::image::RgbaImage, x: u32| image.get_pixel(x, 10).0[0] as i32;
+
+    let centered = render("");
+    let focal = render(r#"fx="0.25""#);
+    // the highlight moves to the left: brighter on the left, darker on the right
+    assert!(red(&focal, 5) > red(&centered, 5) + 20);
+    assert!(red(&focal, 14) + 20 < red(&centered, 14));
+    assert!((red(&centered, 5) - red(&centered, 14)).abs() <= 2);
+
+    // a focal point outside of the circle is moved onto its edge
+    let outside = render(r#"fx="-2""#);
+    let edge = render(r#"fx="0""#);
+    for (a, b) in outside.pixels().zip(edge.pixels()) {
+        for (&x, &y) in a.0.iter().zip(b.0.iter()) {
+            assert!((x as i32 - y as i32).abs() <= 1, "{:?} != {:?}", a, b);
+        }
+    }
+}
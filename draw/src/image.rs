@@ -0,0 +1,184 @@
+use crate::prelude::*;
+use pathfinder_content::{
+    fill::FillRule,
+    pattern::{Pattern, Image},
+};
+use pathfinder_renderer::paint::Paint as PaPaint;
+use std::sync::Arc;
+
+/// the box the image is stretched to. `width` and `height` default to the size of the image in pixels.
+fn image_rect(tag: &TagImage, options: &Options, image_size: Vector2F) -> RectF {
+    let x = tag.x.map(|x| x.resolve(options)).unwrap_or(0.0);
+    let y = tag.y.map(|y| y.resolve(options)).unwrap_or(0.0);
+    let width = tag.width.map(|w| w.resolve(options)).unwrap_or(image_size.x());
+    let height = tag.height.map(|h| h.resolve(options)).unwrap_or(image_size.y());
+    RectF::new(vec2f(x, y), vec2f(width, height))
+}
+
+/// decode `data` and multiply its alpha by `opacity`
+#[cfg(feature="image")]
+fn decode_image(data: &[u8], opacity: f32) -> Option<Image> {
+    use pathfinder_color::ColorU;
+    use pathfinder_geometry::vector::vec2i;
+
+    let image = match ::image::load_from_memory(data) {
+        Ok(image) => image.to_rgba(),
+        Err(e) => {
+            warn!("<image>: can't decode: {}", e);
+            return None;
+        }
+    };
+    let (width, height) = image.dimensions();
+    let pixels = image.pixels().map(|p| ColorU::new(p[0], p[1], p[2], (p[3] as f32 * opacity).round() as u8)).collect();
+    Some(Image::new(vec2i(width as i32, height as i32), Arc::new(pixels)))
+}
+#[cfg(not(feature="image"))]
+fn decode_image(_data: &[u8], _opacity: f32) -> Option<Image> {
+    warn!("<image>: decoding requires the `image` feature");
+    None
+}
+
+/// `<image>` loads its `href` with `DrawContext::load_href` and stretches it over its box.
+/// `preserveAspectRatio` is not supported.
+impl DrawItem for TagImage {
+    fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
+        if !self.attrs.display {
+            return;
+        }
+        // the bounding box is only known before the image is loaded if both `width` and `height` are given
+        let options = options.apply_with_bbox(scene, &self.attrs, |options| match (self.width, self.height) {
+            (Some(_), Some(_)) => Some(image_rect(self, options, Vector2F::zero())),
+            _ => None
+        });
+        if !options.visibility.is_visible() {
+            return;
+        }
+        let href = match self.href {
+            Some(ref href) => href,
+            None => return
+        };
+        let data = match options.ctx.load_href(href) {
+            Some(data) => data,
+            None => {
+                warn!("<image>: can't load {:?}", href);
+                return;
+            }
+        };
+        let image = match decode_image(&data, options.opacity) {
+            Some(image) => image,
+            None => return
+        };
+        let image_size = image.size().to_f32();
+        let rect = image_rect(self, &options, image_size);
+        if rect.width() <= 0. || rect.height() <= 0. {
+            return;
+        }
+
+        let mut pattern = Pattern::from_image(image);
+        pattern.apply_transform(
+            *options.get_transform()
+            * Transform2F::from_translation(rect.origin())
+            * Transform2F::from_scale(rect.size() * image_size.recip())
+        );
+        let outline = Outline::from_rect(rect).transformed(options.get_transform());
+        options.push_draw_path(scene, outline, &PaPaint::from_pattern(pattern), FillRule::Winding);
+    }
+    /// only known if both `width` and `height` are given, as the image is not loaded for it
+    fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
+        if !self.attrs.display || self.width.is_none() || self.height.is_none() {
+            return None;
+        }
+        let options = options.apply(&self.attrs);
+        // the image is drawn regardless of `fill` and `stroke`
+        let rect = options.transform * image_rect(self, &options, Vector2F::zero());
+        match options.clip_rect {
+            Some(clip) => rect.intersection(clip),
+            None => Some(rect)
+        }
+    }
+}
+
+#[test]
+fn test_image_href_resolver() {
+    use std::sync::Mutex;
+
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <image href="icon.png" x="2" y="3" width="16" height="16"/>
+        <image href="#local" width="16" height="16"/>
+    </svg>"#).unwrap();
+    let mut ctx = DrawContext::new_without_fonts(&svg);
+    let requested = Arc::new(Mutex::new(vec![]));
+    let log = requested.clone();
+    ctx.set_href_resolver(move |href| {
+        log.lock().unwrap().push(href.to_owned());
+        None
+    });
+    ctx.compose();
+    // references into the document never reach the resolver
+    assert_eq!(*requested.lock().unwrap(), vec!["icon.png".to_owned()]);
+    assert_eq!(ctx.view_box(), Some(RectF::new(vec2f(0., 0.), vec2f(18., 19.))));
+}
+
+#[cfg(feature="image")]
+#[test]
+fn test_image_from_resolver() {
+    let mut png = vec![];
+    let icon = ::image::RgbaImage::from_pixel(2, 2, ::image::Rgba([0, 0, 255, 255]));
+    ::image::DynamicImage::ImageRgba8(icon).write_to(&mut png, ::image::ImageOutputFormat::Png).unwrap();
+
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 8 8">
+        <image href="icon.png" x="2" y="2" width="4" height="4"/>
+    </svg>"#).unwrap();
+    let mut ctx = DrawContext::new_without_fonts(&svg);
+    ctx.set_href_resolver(move |href| match href {
+        "icon.png" => Some(png.clone()),
+        _ => None
+    });
+    let image = ctx.rasterize(None).image;
+    assert_eq!(image.get_pixel(4, 4).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(1, 1).0[3], 0);
+}
+
+#[cfg(all(feature="text", feature="image"))]
+#[test]
+fn test_image_text_clip() {
+    let mut png = vec![];
+    let red = ::image::RgbaImage::from_pixel(1, 1, ::image::Rgba([255, 0, 0, 255]));
+    ::image::DynamicImage::ImageRgba8(red).write_to(&mut png, ::image::ImageOutputFormat::Png).unwrap();
+
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 60 40">
+        <clipPath id="text"><text x="2" y="36" font-size="40">HI</text></clipPath>
+        <image href="red.png" width="60" height="40" clip-path="url(#text)"/>
+    </svg>"#).unwrap();
+    let fonts = crate::text::latin_modern();
+    let mut ctx = DrawContext::new(&svg, &fonts);
+    ctx.set_href_resolver(move |href| match href {
+        "red.png" => Some(png.clone()),
+        _ => None
+    });
+
+    // one contour for each letter
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 1);
+    let clip = &cmds[0].clip[0].0;
+    assert_eq!(clip.contours().len(), 2);
+
+    let image = ctx.rasterize(None).image;
+    let red_in = |columns: std::ops::Range<u32>| columns.flat_map(|x| (0 .. 40).map(move |y| (x, y)))
+        .filter(|&(x, y)| image.get_pixel(x, y).0 == [255, 0, 0, 255])
+        .count();
+    // both letters show the image, the space around them doesn't
+    assert!(red_in(0 .. 30) > 0 && red_in(32 .. 60) > 0);
+    assert!(red_in(0 .. 60) < 60 * 40 / 2);
+    assert!((0 .. 60).all(|x| image.get_pixel(x, 2).0[3] == 0));
+    assert!((46 .. 60).all(|x| image.get_pixel(x, 20).0[3] == 0));
+
+    // a <use> of the same text clips the same way
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 60 40">
+        <defs><text id="word" x="2" y="36" font-size="40">HI</text></defs>
+        <clipPath id="use"><use href="#word"/></clipPath>
+        <rect width="60" height="40" clip-path="url(#use)"/>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new(&svg, &fonts).flatten();
+    assert_eq!(cmds[0].clip[0].0.bounds(), clip.bounds());
+}
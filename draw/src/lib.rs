@@ -19,10 +19,12 @@ mod prelude {
     pub use crate::{
         DrawItem, Resolve, Interpolate, Compose, Shape,
         draw::{Options, DrawContext, BoundsOptions, DrawOptions},
+        flatten::FlatDrawCmd,
     };
     pub use svgtypes::{Length, LengthUnit};
 }
 use std::borrow::Borrow;
+use g::{draw_with_effects, leaf_attrs};
 
 mod path;
 mod rect;
@@ -30,17 +32,33 @@ mod polygon;
 mod ellipse;
 mod attrs;
 mod gradient;
+mod pattern;
 mod resolve;
 mod filter;
+mod transfer;
+mod mask;
+mod opacity;
+mod marker;
 mod g;
 mod draw;
+mod dash;
+mod flatten;
+mod cache;
 mod svg;
+mod image;
 #[cfg(feature="text")]
 mod text;
 mod animate;
 mod paint;
+// always built for the tests, which check what is drawn on the pixels
+#[cfg(any(feature="rasterize", test))]
+mod raster;
 
 pub use prelude::*;
+pub use cache::SceneCache;
+pub use draw::HrefResolver;
+#[cfg(any(feature="rasterize", test))]
+pub use raster::{RasterImage, PixelFormat, ColorSpace, AlphaMode};
 
 #[cfg(feature="text")]
 use svg_text::FontCollection;
@@ -80,13 +98,114 @@ impl<T> Interpolate for Option<T> where T: Interpolate {
     }
 }
 
+/// Without the `text` feature, text is skipped, or drawn as a box if `DrawContext::text_placeholders` is set.
 #[cfg(not(feature="text"))]
 impl DrawItem for TagText {
     fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
+        if !options.ctx.text_placeholders || !self.attrs.display {
+            return;
+        }
+        let mut options = options.apply(scene, &self.attrs);
+        if let Some(clip) = text_clip_rect(self, &options) {
+            options.clip_to_rect(scene, clip);
+        }
+        if let Some(rect) = text_placeholder(self, &options) {
+            options.draw(scene, &Outline::from_rect(rect));
+        }
     }
     fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
-        None
+        if !options.ctx.text_placeholders || !self.attrs.display {
+            return None;
+        }
+        let options = options.apply(&self.attrs);
+        options.bounds(text_placeholder(self, &options)?)
+    }
+}
+
+/// approximate extent of the text: every char is half an em wide, with an ascent of 0.8 em and a descent of 0.2 em.
+/// Only the first position of the text element is used, and `text-anchor` applies to the whole text.
+#[cfg(not(feature="text"))]
+fn text_placeholder(text: &TagText, options: &Options) -> Option<RectF> {
+    let num_chars: usize = text.items.iter().map(|item| text_content(item).chars().count()).sum();
+    if num_chars == 0 {
+        return None;
     }
+    let origin = text_origin(text, options);
+    let em = options.font_size;
+    let width = 0.5 * em * num_chars as f32;
+    let origin = origin - vec2f(options.text_anchor.fraction() * width, 0.8 * em);
+    Some(RectF::new(origin, vec2f(width, em)))
+}
+
+/// the first position of the text
+fn text_origin(text: &TagText, options: &Options) -> Vector2F {
+    let x = text.pos.x.as_ref().and_then(|x| x.as_slice().first()).map(|x| x.resolve(options)).unwrap_or(0.0);
+    let y = text.pos.y.as_ref().and_then(|y| y.as_slice().first()).map(|y| y.resolve(options)).unwrap_or(0.0);
+    vec2f(x, y)
+}
+
+/// the layout box that the glyphs are clipped to, if the text has an `inline-size` and its `overflow` clips.
+///
+/// It starts at the first position of the text and extends `inline-size` in the direction of the text.
+/// Across, it covers the line from 1 em above to half an em below the baseline.
+fn text_clip_rect(text: &TagText, options: &Options) -> Option<RectF> {
+    if !text.overflow?.clips() {
+        return None;
+    }
+    let width = text.inline_size?.try_resolve(options)?;
+    let origin = text_origin(text, options);
+    let em = options.font_size;
+    let x = match options.direction {
+        TextFlow::RightToLeft => origin.x() - width,
+        _ => origin.x(),
+    };
+    Some(RectF::new(vec2f(x, origin.y() - em), vec2f(width, 1.5 * em)))
+}
+
+#[test]
+fn test_text_clip_rect() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <text id="t" x="10" y="20" font-size="10" inline-size="40" overflow="hidden">Supercalifragilistic</text>
+        <text id="visible" inline-size="40">Supercalifragilistic</text>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let clip = |id: &str| {
+        let text = item!(svg, id, Text);
+        text_clip_rect(text, &Options::new(&ctx).apply(&text.attrs))
+    };
+    assert_eq!(clip("t"), Some(RectF::new(vec2f(10., 10.), vec2f(40., 15.))));
+    assert_eq!(clip("visible"), None);
+}
+
+#[cfg(not(feature="text"))]
+#[test]
+fn test_text_placeholder() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <text x="10" y="20" font-size="10">abcd</text>
+    </svg>"#).unwrap();
+    let mut ctx = DrawContext::new_without_fonts(&svg);
+    assert_eq!(ctx.flatten().len(), 0);
+
+    ctx.text_placeholders = true;
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].outline.bounds(), RectF::new(vec2f(10., 12.), vec2f(20., 10.)));
+}
+
+#[cfg(not(feature="text"))]
+#[test]
+fn test_text_placeholder_overflow() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <text x="10" y="20" font-size="10" inline-size="40" overflow="hidden">Supercalifragilistic</text>
+    </svg>"#).unwrap();
+    let mut ctx = DrawContext::new_without_fonts(&svg);
+    ctx.text_placeholders = true;
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 1);
+    // the long word is 100 wide, but cut off after 40
+    assert_eq!(cmds[0].outline.bounds().width(), 100.);
+    assert_eq!(cmds[0].clip.len(), 1);
+    assert_eq!(cmds[0].clip[0].0.bounds().width(), 40.);
 }
 
 pub trait Compose {
@@ -105,10 +224,22 @@ macro_rules! draw_items {
     ($name:ident { $($variant:ident($data:ty), )* }) => {
         impl DrawItem for $name {
             fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
+                // the recorder maps the draw paths back to the element they come from
+                let recorded = match (&options.recorder, self.id()) {
+                    (Some(recorder), Some(id)) => {
+                        recorder.lock().unwrap().push_element(id);
+                        Some(recorder)
+                    }
+                    _ => None
+                };
                 match *self {
-                    $( $name::$variant ( ref tag ) => tag.draw_to(scene, options), )*
+                    _ if is_culled(self, options) => {}
+                    $( $name::$variant ( ref tag ) => draw_with_effects(self, scene, options, |scene, options| tag.draw_to(scene, options)), )*
                     _ => {}
                 }
+                if let Some(recorder) = recorded {
+                    recorder.lock().unwrap().pop_element();
+                }
             }
             fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
                 match *self {
@@ -120,10 +251,33 @@ macro_rules! draw_items {
     }
 }
 
+/// whether `item` is a shape outside of the `cull_region` of `options`, which does not need to be drawn.
+/// Groups are not culled, and neither are shapes with unknown bounds, filters or markers, which can draw outside of their bounds.
+fn is_culled(item: &Item, options: &DrawOptions) -> bool {
+    let region = match options.cull_region {
+        Some(region) => region,
+        None => return false
+    };
+    let attrs = match leaf_attrs(item) {
+        Some(attrs) => attrs,
+        None => return false
+    };
+    let applied = options.common.apply(attrs);
+    if attrs.filter.is_some() || applied.marker_start.is_some() || applied.marker_mid.is_some() || applied.marker_end.is_some() {
+        return false;
+    }
+    match item.bounds(&options.bounds_options()) {
+        Some(bounds) => bounds.intersection(region).is_none(),
+        None => false
+    }
+}
+
 draw_items!(
     Item {
         Path(TagPath),
         G(TagG),
+        A(TagA),
+        Switch(TagSwitch),
         Rect(TagRect),
         Polygon(TagPolygon),
         Polyline(TagPolyline),
@@ -133,6 +287,7 @@ draw_items!(
         Svg(TagSvg),
         Use(TagUse),
         Text(TagText),
+        Image(TagImage),
     }
 );
 
@@ -95,3 +95,25 @@ macro_rules! get_ref_or_return {
         }
     );
 }
+
+/// the element with the given id, which has to be an `Item::$kind`
+#[cfg(test)]
+macro_rules! item {
+    ($svg:expr, $id:expr, $kind:ident) => (
+        match &**$svg.get_item($id).unwrap() {
+            Item::$kind(item) => item,
+            item => panic!("expected {} for {:?}, got {:?}", stringify!($kind), $id, item)
+        }
+    )
+}
+
+/// the attributes of the element with the given id
+#[cfg(test)]
+macro_rules! attrs {
+    ($svg:expr, $id:expr) => (
+        match $svg.get_item($id).unwrap().attrs() {
+            Some(attrs) => attrs,
+            None => panic!("expected an element with attributes for {:?}", $id)
+        }
+    )
+}
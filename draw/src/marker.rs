@@ -0,0 +1,155 @@
+use crate::prelude::*;
+use pathfinder_content::outline::ContourIterFlags;
+use std::f32::consts::PI;
+use crate::draw::view_box_transform;
+
+/// a vertex of a path, with the direction the path arrives and leaves in
+#[derive(Debug, Copy, Clone)]
+pub struct MarkerVertex {
+    pub point: Vector2F,
+    pub incoming: Option<Vector2F>,
+    pub outgoing: Option<Vector2F>,
+}
+impl MarkerVertex {
+    /// the direction of the path at this vertex, bisecting the incoming and outgoing directions
+    pub fn angle(&self) -> f32 {
+        let dir = match (self.incoming, self.outgoing) {
+            (Some(a), Some(b)) => {
+                let sum = normalize(a) + normalize(b);
+                // the path turns back on itself, so there is no bisector
+                if sum.square_length() < 1e-12 { a } else { sum }
+            }
+            (Some(d), None) | (None, Some(d)) => d,
+            (None, None) => Vector2F::zero(),
+        };
+        dir.y().atan2(dir.x())
+    }
+}
+
+fn normalize(v: Vector2F) -> Vector2F {
+    let len = v.length();
+    if len > 0.0 { v * (1.0 / len) } else { v }
+}
+
+/// all vertices of `outline`: the start and end of every contour and the anchors between segments,
+/// whether the segments are lines or curves
+pub fn marker_vertices(outline: &Outline) -> Vec<MarkerVertex> {
+    let mut vertices = vec![];
+    for contour in outline.contours() {
+        let first = vertices.len();
+        for segment in contour.iter(ContourIterFlags::empty()) {
+            let cubic = segment.to_cubic();
+            let (p0, p3) = (cubic.baseline.from(), cubic.baseline.to());
+            let (c1, c2) = (cubic.ctrl.from(), cubic.ctrl.to());
+            let first_nonzero = |candidates: [Vector2F; 3]| candidates.iter().cloned().find(|v| *v != Vector2F::zero());
+            let start_dir = if segment.is_line() { p3 - p0 } else { first_nonzero([c1 - p0, c2 - p0, p3 - p0]).unwrap_or_default() };
+            let end_dir = if segment.is_line() { p3 - p0 } else { first_nonzero([p3 - c2, p3 - c1, p3 - p0]).unwrap_or_default() };
+
+            if vertices.len() > first {
+                vertices.last_mut().unwrap().outgoing = Some(start_dir);
+            } else {
+                vertices.push(MarkerVertex { point: p0, incoming: None, outgoing: Some(start_dir) });
+            }
+            vertices.push(MarkerVertex { point: p3, incoming: Some(end_dir), outgoing: None });
+        }
+    }
+    vertices
+}
+
+impl<'a> DrawOptions<'a> {
+    /// draw `marker-start`, `marker-mid` and `marker-end` on the vertices of `outline` (in user space)
+    pub fn draw_markers(&self, scene: &mut Scene, outline: &Outline) {
+        if self.marker_start.is_none() && self.marker_mid.is_none() && self.marker_end.is_none() {
+            return;
+        }
+        let vertices = marker_vertices(outline);
+        let last = vertices.len().saturating_sub(1);
+        for (i, vertex) in vertices.iter().enumerate() {
+            let (marker, is_start) = match i {
+                0 => (self.marker_start, true),
+                i if i == last => (self.marker_end, false),
+                _ => (self.marker_mid, false),
+            };
+            if let Some(marker) = marker {
+                self.draw_marker(scene, marker, vertex, is_start);
+            }
+        }
+    }
+    fn draw_marker(&self, scene: &mut Scene, marker: &TagMarker, vertex: &MarkerVertex, is_start: bool) {
+        let angle = match marker.orient {
+            MarkerOrient::Angle(angle) => angle,
+            MarkerOrient::Auto => vertex.angle(),
+            MarkerOrient::AutoStartReverse if is_start => vertex.angle() + PI,
+            MarkerOrient::AutoStartReverse => vertex.angle(),
+        };
+        let scale = match marker.marker_units {
+            MarkerUnits::StrokeWidth => self.stroke_style.line_width,
+            MarkerUnits::UserSpaceOnUse => 1.0,
+        };
+        let reference = vec2f(marker.ref_x.resolve(self), marker.ref_y.resolve(self));
+        // the view box is fit into the marker size, and the reference point along with it
+        let fit = match marker.view_box {
+            Some(ref view_box) => {
+                let size = vec2f(marker.marker_width.resolve(self), marker.marker_height.resolve(self));
+                let aspect = marker.preserve_aspect_ratio.unwrap_or_else(default_aspect_ratio);
+                view_box_transform(view_box.resolve(self), RectF::new(Vector2F::zero(), size), &aspect)
+            }
+            None => Transform2F::default()
+        };
+
+        // the content of the marker does not inherit from the referencing element
+        let mut options = DrawOptions {
+            common: Options::new(self.ctx),
+            clip_path: self.clip_path,
+            recorder: self.recorder.clone(),
+            cull_region: self.cull_region,
+        };
+        options.transform = self.transform
+            * Transform2F::from_translation(vertex.point)
+            * Transform2F::from_rotation(angle)
+            * Transform2F::from_scale(Vector2F::splat(scale))
+            * Transform2F::from_translation(-(fit * reference))
+            * fit;
+        options.depth = self.depth + 1;
+        if let Some(ref view_box) = marker.view_box {
+            options.view_box = Some(view_box.resolve(self));
+        }
+        let options = options.apply(scene, &marker.attrs);
+        if options.too_deep() {
+            return;
+        }
+        for item in marker.items.iter() {
+            item.draw_to(scene, &options);
+        }
+    }
+}
+
+#[test]
+fn test_marker_mid_on_curve() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <path id="p" d="M0,0 C0,10 10,10 10,0 C10,-10 20,-10 20,0"/>
+    </svg>"#).unwrap();
+    let outline = &item!(svg, "p", Path).outline;
+    let vertices = marker_vertices(outline);
+    assert_eq!(vertices.len(), 3);
+    let mid = vertices[1];
+    assert_eq!(mid.point, vec2f(10., 0.));
+    // arriving upwards and leaving upwards, so the bisector points up
+    assert!((mid.angle() + 0.5 * PI).abs() < 1e-5);
+}
+
+#[test]
+fn test_marker_view_box() {
+    // the tip of the arrow is at (10, 5) in the view box, which is scaled by 0.6
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="6" markerHeight="6" markerUnits="userSpaceOnUse" orient="auto">
+            <path d="M0,0 L10,5 L0,10 z"/>
+        </marker>
+        <path d="M0,0 L20,0" fill="none" stroke="black" marker-end="url(#arrow)"/>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    let arrow = cmds.last().unwrap().outline.bounds();
+    let expected = RectF::from_points(vec2f(14., -3.), vec2f(20., 3.));
+    assert!((arrow.origin() - expected.origin()).length() < 1e-4, "{:?}", arrow);
+    assert!((arrow.lower_right() - expected.lower_right()).length() < 1e-4, "{:?}", arrow);
+}
@@ -0,0 +1,129 @@
+use crate::prelude::*;
+use pathfinder_renderer::{
+    scene::{RenderTarget, DrawPath},
+    paint::Paint,
+};
+use pathfinder_content::{
+    pattern::Pattern,
+    effects::{PatternFilter, BlendMode},
+    fill::FillRule,
+};
+use pathfinder_color::matrix::ColorMatrix;
+use crate::filter::effect_region;
+
+/// draw the content produced by `f` masked by `mask`.
+///
+/// The content and the mask are rendered into a render target covering the mask region for content with `bounds` (in scene coordinates),
+/// where the luminance (or alpha, depending on `mask-type`) of the mask is multiplied into the alpha of the content.
+/// Nothing is drawn outside of the region.
+/// The result is then drawn with the clip path of `options`, so clipping and masking both apply.
+pub fn apply_mask(mask: &TagMask, scene: &mut Scene, options: &DrawOptions, bounds: RectF, f: impl FnOnce(&mut Scene, &DrawOptions)) {
+    // the bounding box of the content in user space
+    let bbox = options.transform.inverse() * bounds;
+    let region = effect_region(mask.mask_units, mask.x, mask.y, mask.width, mask.height, options, bounds);
+    let bounds = match bounds.intersection(region) {
+        Some(bounds) => bounds.round_out().to_i32(),
+        None => return
+    };
+    if bounds.size().x() <= 0 || bounds.size().y() <= 0 {
+        return;
+    }
+    let to_target = Transform2F::from_translation(-bounds.origin().to_f32());
+
+    let content_target = scene.push_render_target(RenderTarget::new(bounds.size(), String::new()));
+    let mut content_options = options.clone();
+    content_options.transform = to_target * options.transform;
+    // the clip path lives in scene coordinates, it is applied to the result instead
+    content_options.clip_path = None;
+    content_options.recorder = None;
+    f(scene, &content_options);
+
+    // the mask content does not inherit from the masked element
+    let mut mask_options = DrawOptions::new(options.ctx);
+    mask_options.transform = to_target * options.transform;
+    mask_options.depth = options.depth + 1;
+    if mask.mask_content_units == Some(GradientUnits::ObjectBoundingBox) {
+        mask_options.apply_transform(Transform2F::from_translation(bbox.origin()) * Transform2F::from_scale(bbox.size()));
+    }
+    if !mask_options.too_deep() {
+        let mask_target = scene.push_render_target(RenderTarget::new(bounds.size(), String::new()));
+        for item in mask.items.iter() {
+            item.draw_to(scene, &mask_options);
+        }
+        scene.pop_render_target();
+
+        let mut mask_paint = Pattern::from_render_target(mask_target, bounds.size());
+        // only the alpha of the mask is used by `DestIn`. The content is premultiplied,
+        // so its luminance already includes its alpha, as it should.
+        if mask.mask_type == MaskType::Luminance {
+            mask_paint.set_filter(Some(PatternFilter::ColorMatrix(ColorMatrix::luminance_to_alpha())));
+        }
+        let paint_id = scene.push_paint(&Paint::from_pattern(mask_paint));
+        let mut path = DrawPath::new(Outline::from_rect(RectF::new(Vector2F::zero(), bounds.size().to_f32())), paint_id);
+        path.set_blend_mode(BlendMode::DestIn);
+        scene.push_draw_path(path);
+    }
+    scene.pop_render_target();
+
+    let mut content_paint = Pattern::from_render_target(content_target, bounds.size());
+    content_paint.apply_transform(Transform2F::from_translation(bounds.origin().to_f32()));
+    options.push_draw_path(scene, Outline::from_rect(bounds.to_f32()), &Paint::from_pattern(content_paint), FillRule::Winding);
+}
+
+#[test]
+fn test_mask_vignette() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 40">
+        <radialGradient id="g"><stop offset="0" stop-color="white"/><stop offset="1" stop-color="black"/></radialGradient>
+        <mask id="m" maskContentUnits="objectBoundingBox"><rect width="1" height="1" fill="url(#g)"/></mask>
+        <rect width="40" height="40" fill="red" mask="url(#m)"/>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    let alpha: Vec<u8> = (0 .. 21).map(|x| image.get_pixel(x, 20).0[3]).collect();
+    assert!(alpha.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", alpha);
+    assert!(alpha[20] >= 245, "{:?}", alpha);
+    assert!(alpha[10] > 100 && alpha[10] < 170, "{:?}", alpha);
+    // past the radius of the gradient in the corners
+    assert_eq!(image.get_pixel(1, 1).0, [0, 0, 0, 0]);
+    // the content stays red, only its alpha changes
+    let [r, g, b, a] = image.get_pixel(10, 20).0;
+    assert!((r as i32 - a as i32).abs() <= 2 && g == 0 && b == 0);
+}
+
+#[test]
+fn test_mask_region_and_type() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 20">
+        <mask id="left" maskUnits="userSpaceOnUse" x="0" y="0" width="10" height="20">
+            <rect width="40" height="20" fill="white"/>
+        </mask>
+        <mask id="alpha" mask-type="alpha"><rect width="40" height="20"/></mask>
+        <rect width="20" height="20" mask="url(#left)"/>
+        <g mask="url(#alpha)"><rect x="20" width="20" height="20" fill="blue"/></g>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    assert_eq!(image.get_pixel(5, 10).0, [0, 0, 0, 255]);
+    // outside of the mask region, although the content of the mask covers it
+    assert_eq!(image.get_pixel(15, 10).0, [0, 0, 0, 0]);
+    // black, but opaque
+    assert_eq!(image.get_pixel(30, 10).0, [0, 0, 255, 255]);
+}
+
+#[test]
+fn test_clip_and_mask() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 20">
+        <clipPath id="c"><rect width="20" height="20"/></clipPath>
+        <linearGradient id="g"><stop offset="0" stop-color="white"/><stop offset="1" stop-color="black"/></linearGradient>
+        <mask id="m" maskContentUnits="objectBoundingBox"><rect width="1" height="1" fill="url(#g)"/></mask>
+        <rect width="40" height="20" fill="red" clip-path="url(#c)" mask="url(#m)"/>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    // faded by the mask from left to right
+    let alpha: Vec<u8> = (0 .. 20).map(|x| image.get_pixel(x, 10).0[3]).collect();
+    assert!(alpha.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", alpha);
+    assert!(alpha[0] > 230 && alpha[19] > 60 && alpha[19] < 200, "{:?}", alpha);
+    // and cut off by the clip path, although the mask would let the right half show through
+    for x in 20 .. 40 {
+        assert_eq!(image.get_pixel(x, 10).0, [0, 0, 0, 0]);
+    }
+    let [r, g, b, a] = image.get_pixel(10, 10).0;
+    assert!((r as i32 - a as i32).abs() <= 2 && g == 0 && b == 0);
+}
@@ -0,0 +1,142 @@
+use crate::prelude::*;
+use pathfinder_renderer::{
+    scene::RenderTarget,
+    paint::Paint,
+};
+use pathfinder_content::{
+    pattern::Pattern,
+    effects::PatternFilter,
+    fill::FillRule,
+};
+use pathfinder_color::matrix::ColorMatrix;
+use pathfinder_simd::default::F32x4;
+use std::sync::Arc;
+
+/// true if multiplying the opacity of a group into the paint of each of its `items` looks the same
+/// as compositing them as a layer, which is the case if nothing drawn by the group overlaps:
+/// the visible items are shapes with either a fill or a stroke (and no markers), and their bounds are disjoint.
+pub fn opacity_without_layer(items: &[Arc<Item>], options: &DrawOptions) -> bool {
+    let bounds_options = options.bounds_options();
+    let mut drawn: Vec<RectF> = vec![];
+    for item in items {
+        let attrs = match **item {
+            Item::G(_) | Item::A(_) | Item::Switch(_) | Item::Svg(_) | Item::Use(_) | Item::Text(_) | Item::Image(_) => return false,
+            ref item => match item.attrs() {
+                Some(attrs) => attrs,
+                // not drawn
+                None => continue
+            }
+        };
+        if !attrs.display {
+            continue;
+        }
+        if attrs.filter.is_some() || attrs.mask.is_some() {
+            return false;
+        }
+        let child = bounds_options.apply(attrs);
+        if child.has_fill() && child.has_stroke() {
+            return false;
+        }
+        if child.marker_start.is_some() || child.marker_mid.is_some() || child.marker_end.is_some() {
+            return false;
+        }
+        if let Some(bounds) = item.bounds(&bounds_options) {
+            if drawn.iter().any(|other| other.intersects(bounds)) {
+                return false;
+            }
+            drawn.push(bounds);
+        }
+    }
+    true
+}
+
+/// draw the content produced by `f` into a layer covering `bounds` (in scene coordinates),
+/// which is then composited with the opacity of `options`.
+///
+/// The content is drawn with an opacity of 1, so the opacity is applied exactly once.
+pub fn apply_opacity(scene: &mut Scene, options: &DrawOptions, bounds: RectF, f: impl FnOnce(&mut Scene, &DrawOptions)) {
+    let bounds = bounds.round_out().to_i32();
+    if bounds.size().x() <= 0 || bounds.size().y() <= 0 {
+        return;
+    }
+
+    let target = scene.push_render_target(RenderTarget::new(bounds.size(), String::new()));
+    let mut content_options = options.clone();
+    content_options.transform = Transform2F::from_translation(-bounds.origin().to_f32()) * options.transform;
+    // the clip path lives in scene coordinates, it is applied to the layer instead
+    content_options.clip_path = None;
+    content_options.recorder = None;
+    content_options.opacity = 1.0;
+    f(scene, &content_options);
+    scene.pop_render_target();
+
+    // the layer holds premultiplied colors, so all channels are scaled
+    let o = options.opacity;
+    let zero = F32x4::splat(0.0);
+    let alpha = ColorMatrix([
+        F32x4::new(o, 0.0, 0.0, 0.0),
+        F32x4::new(0.0, o, 0.0, 0.0),
+        F32x4::new(0.0, 0.0, o, 0.0),
+        F32x4::new(0.0, 0.0, 0.0, o),
+        zero,
+    ]);
+    let mut paint = Pattern::from_render_target(target, bounds.size());
+    paint.apply_transform(Transform2F::from_translation(bounds.origin().to_f32()));
+    paint.set_filter(Some(PatternFilter::ColorMatrix(alpha)));
+    options.push_draw_path(scene, Outline::from_rect(bounds.to_f32()), &Paint::from_pattern(paint), FillRule::Winding);
+}
+
+#[test]
+fn test_group_opacity_fast_path_matches_layer() {
+    let render = |content: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 8 8">{}</svg>"#, content)).unwrap();
+        DrawContext::new_without_fonts(&svg).rasterize(None).image
+    };
+    // the nested group is not a plain shape, so the outer one is composited as a layer
+    let direct = render(r#"<g opacity="0.5"><rect x="2" y="2" width="4" height="4" fill="blue"/></g>"#);
+    let layered = render(r#"<g opacity="0.5"><g><rect x="2" y="2" width="4" height="4" fill="blue"/></g></g>"#);
+    for (a, b) in direct.pixels().zip(layered.pixels()) {
+        for (&x, &y) in a.0.iter().zip(b.0.iter()) {
+            assert!((x as i32 - y as i32).abs() <= 1, "{:?} != {:?}", a, b);
+        }
+    }
+    assert!(direct.get_pixel(4, 4).0[3] > 0);
+}
+
+#[test]
+fn test_group_opacity_overlap() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 20">
+        <g opacity="0.5">
+            <circle cx="12" cy="10" r="8" fill="black"/>
+            <circle cx="28" cy="10" r="8" fill="red"/>
+            <circle cx="20" cy="10" r="6" fill="black"/>
+        </g>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    let alone = image.get_pixel(8, 10).0;
+    // the circles overlap, so they are composited as one layer: the overlap is as transparent as the rest
+    let overlap = image.get_pixel(16, 10).0;
+    assert!((alone[3] as i32 - 128).abs() <= 1, "{:?}", alone);
+    assert_eq!(overlap, alone);
+    // the black circle on top hides the red one below it
+    assert_eq!(image.get_pixel(24, 10).0, alone);
+}
+
+#[test]
+fn test_nested_opacity() {
+    // the opacities multiply, each of them applied exactly once
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <g opacity="0.5"><rect width="10" height="10" opacity="0.5"/></g>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    assert_eq!(cmds.len(), 1);
+    assert_eq!(cmds[0].paint.base_color().a, 64);
+
+    // the same with the outer group composited as a layer
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 8 8">
+        <g opacity="0.5"><g opacity="0.5"><rect width="8" height="8"/></g></g>
+    </svg>"#).unwrap();
+    let image = DrawContext::new_without_fonts(&svg).rasterize(None).image;
+    let alpha = image.get_pixel(4, 4).0[3];
+    assert!((alpha as i32 - 64).abs() <= 1, "{}", alpha);
+}
@@ -10,6 +10,7 @@ impl Interpolate for Color {
             red: self.red.lerp(to.red, x),
             green: self.green.lerp(to.green, x),
             blue: self.blue.lerp(to.blue, x),
+            alpha: self.alpha.lerp(to.alpha, x),
         }
     }
     fn scale(self, x: f32) -> Self {
@@ -17,6 +18,8 @@ impl Interpolate for Color {
             red: self.red.scale(x),
             green: self.green.scale(x),
             blue: self.blue.scale(x),
+            // a difference of colors has no alpha of its own
+            alpha: self.alpha,
         }
     }
 }
@@ -26,6 +29,7 @@ impl Compose for Color {
             red: self.red + rhs.red,
             green: self.green + rhs.green,
             blue: self.blue + rhs.blue,
+            alpha: self.alpha.min(rhs.alpha),
         }
     }
 }
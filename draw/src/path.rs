@@ -1,19 +1,20 @@
 use pathfinder_content::outline::Outline;
 use crate::prelude::*;
+use crate::flatten::Recorder;
+use crate::g::content_transform;
+use std::sync::{Arc, Mutex};
 
 impl Resolve for TagClipPath {
     type Output = Outline;
+    /// `options` are those of the referencing element, so its transform applies first,
+    /// then the transform of the `<clipPath>` and finally those of the clipping shapes.
     fn resolve(&self, options: &Options) -> Outline {
+        let mut options = options.clone();
+        options.apply_transform(self.transform);
+
         let mut outline = Outline::new();
         for item in &self.items {
-            let o = match item {
-                Item::Path(path) => path.outline(options),
-                Item::Rect(rect) => rect.outline(options),
-                Item::Circle(circle) => circle.outline(options),
-                Item::Polygon(polygon) => polygon.outline(options),
-                _ => None
-            };
-            if let Some(o) = o {
+            if let Some(o) = clip_item_outline(item, &options) {
                 outline.push_outline(o)
             }
         }
@@ -21,6 +22,63 @@ impl Resolve for TagClipPath {
     }
 }
 
+/// the outline of an element inside of a `<clipPath>`. Only its geometry counts, not its paint.
+/// A `<use>` adds the outline of the element it references.
+fn clip_item_outline(item: &Item, options: &Options) -> Option<Outline> {
+    match item {
+        Item::Path(path) => path.outline(options),
+        Item::Rect(rect) => rect.outline(options),
+        Item::Circle(circle) => circle.outline(options),
+        Item::Ellipse(ellipse) => ellipse.outline(options),
+        Item::Polygon(polygon) => polygon.outline(options),
+        Item::Text(text) if text.attrs.display => Some(text_outline(text, options)),
+        Item::Use(tag) if tag.attrs.display => {
+            let mut options = options.apply(&tag.attrs);
+            if options.too_deep() {
+                return None;
+            }
+            let item = &**options.ctx.resolve_href(tag.href.as_ref()?)?;
+            content_transform(tag, &mut options, item);
+            clip_item_outline(item, &options)
+        }
+        _ => None
+    }
+}
+
+/// the glyph outlines of `text` in scene coordinates, recorded while drawing it with a plain fill and no stroke
+fn text_outline(text: &TagText, options: &Options) -> Outline {
+    let recorder = Arc::new(Mutex::new(Recorder::default()));
+    let mut draw_options = DrawOptions::new(options.ctx);
+    draw_options.common = options.clone();
+    draw_options.fill = Paint::Color(Color::black());
+    draw_options.stroke = Paint::None;
+    draw_options.recorder = Some(recorder.clone());
+    text.draw_to(&mut Scene::new(), &draw_options);
+    drop(draw_options);
+
+    let mut outline = Outline::new();
+    for cmd in Recorder::unwrap_shared(recorder).into_cmds() {
+        outline.push_outline(cmd.outline);
+    }
+    outline
+}
+
+/// the outline of `clip` for an element with the bounding box `bbox` (in its user space, which `options` map from).
+///
+/// With `objectBoundingBox` units the unit square is mapped onto `bbox` before the transform of the `<clipPath>`.
+/// Returns `None` if the units are `objectBoundingBox`, but there is no bounding box.
+pub fn clip_outline(clip: &TagClipPath, options: &Options, bbox: Option<RectF>) -> Option<Outline> {
+    match clip.clip_path_units {
+        Some(GradientUnits::ObjectBoundingBox) => {
+            let bbox = bbox?;
+            let mut options = options.clone();
+            options.apply_transform(Transform2F::from_translation(bbox.origin()) * Transform2F::from_scale(bbox.size()));
+            Some(clip.resolve(&options))
+        }
+        Some(GradientUnits::UserSpaceOnUse) | None => Some(clip.resolve(options)),
+    }
+}
+
 impl Shape for TagPath {
     fn outline(&self, options: &Options) -> Option<Outline> {
         let options = options.apply(&self.attrs);
@@ -39,7 +97,8 @@ impl DrawItem for TagPath {
         }
     }
     fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
+        let options = options.apply_with_bbox(scene, &self.attrs, |_| Some(self.outline.bounds()));
         options.draw(scene, &self.outline);
+        options.draw_markers(scene, &self.outline);
     }
 }
\ No newline at end of file
@@ -0,0 +1,100 @@
+use crate::prelude::*;
+use crate::gradient::bbox_fraction;
+use crate::draw::view_box_transform;
+use pathfinder_renderer::{
+    scene::RenderTarget,
+    paint::Paint as PaPaint,
+};
+use pathfinder_content::pattern::Pattern;
+use pathfinder_geometry::vector::vec2i;
+
+pub trait BuildPattern {
+    /// render one tile into a render target and return a paint repeating it.
+    ///
+    /// `bounds` is the bounding box of the painted element in the coordinate system of `options.transform`.
+    /// `opacity` is applied to the content of the tile, like it is multiplied into colors and gradient stops.
+    fn build(&self, scene: &mut Scene, options: &Options, opacity: f32, bounds: RectF) -> Option<PaPaint>;
+}
+
+impl BuildPattern for TagPattern {
+    fn build(&self, scene: &mut Scene, options: &Options, opacity: f32, bounds: RectF) -> Option<PaPaint> {
+        let units = self.pattern_units.unwrap_or(GradientUnits::ObjectBoundingBox);
+        let tile = match units {
+            GradientUnits::ObjectBoundingBox => {
+                let fraction = |l: Option<Length>| l.map(bbox_fraction).unwrap_or(0.0);
+                let origin = vec2f(fraction(self.x.map(|l| l.0)), fraction(self.y.map(|l| l.0)));
+                let size = vec2f(fraction(self.width.map(|l| l.0)), fraction(self.height.map(|l| l.0)));
+                RectF::new(bounds.origin() + bounds.size() * origin, bounds.size() * size)
+            }
+            GradientUnits::UserSpaceOnUse => {
+                let x = self.x.map(|l| l.resolve(options)).unwrap_or(0.0);
+                let y = self.y.map(|l| l.resolve(options)).unwrap_or(0.0);
+                let width = self.width.map(|l| l.resolve(options)).unwrap_or(0.0);
+                let height = self.height.map(|l| l.resolve(options)).unwrap_or(0.0);
+                RectF::new(vec2f(x, y), vec2f(width, height))
+            }
+        };
+        // a zero sized tile disables rendering of the paint
+        if tile.width() <= 0.0 || tile.height() <= 0.0 {
+            return None;
+        }
+
+        let tile_to_scene = options.transform
+            * self.pattern_transform.unwrap_or_default()
+            * Transform2F::from_translation(tile.origin());
+        let size = (tile.size() * tile_to_scene.extract_scale()).ceil().to_i32();
+        let size = vec2i(size.x().max(1), size.y().max(1));
+        let pixels_per_unit = size.to_f32() * tile.size().recip();
+
+        let content_transform = match (self.view_box, self.pattern_content_units) {
+            (Some(ref view_box), _) => {
+                let aspect = self.preserve_aspect_ratio.unwrap_or_else(default_aspect_ratio);
+                view_box_transform(view_box.resolve(options), RectF::new(Vector2F::zero(), tile.size()), &aspect)
+            }
+            (None, Some(GradientUnits::ObjectBoundingBox)) => Transform2F::from_scale(bounds.size()),
+            (None, _) => Transform2F::default(),
+        };
+
+        // the content of the pattern does not inherit from the painted element
+        let mut tile_options = DrawOptions::new(options.ctx);
+        tile_options.transform = Transform2F::from_scale(pixels_per_unit) * content_transform;
+        tile_options.opacity = opacity;
+        tile_options.depth = options.depth + 1;
+        if tile_options.too_deep() {
+            return None;
+        }
+
+        let render_target = scene.push_render_target(RenderTarget::new(size, String::new()));
+        for item in self.items.iter() {
+            item.draw_to(scene, &tile_options);
+        }
+        scene.pop_render_target();
+
+        let mut pattern = Pattern::from_render_target(render_target, size);
+        pattern.set_repeat_x(true);
+        pattern.set_repeat_y(true);
+        pattern.apply_transform(tile_to_scene * Transform2F::from_scale(pixels_per_unit.recip()));
+        Some(PaPaint::from_pattern(pattern))
+    }
+}
+
+#[test]
+fn test_gradient_pattern_opacity() {
+    let render = |content: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 10">
+            <linearGradient id="g"><stop offset="0" stop-color="red"/><stop offset="1" stop-color="blue"/></linearGradient>
+            <pattern id="p" patternUnits="userSpaceOnUse" width="10" height="10"><rect width="10" height="10" fill="url(#g)"/></pattern>
+            {}
+        </svg>"#, content)).unwrap();
+        DrawContext::new_without_fonts(&svg).rasterize(None).image
+    };
+    let patterned = render(r#"<rect width="20" height="10" fill="url(#p)" opacity="0.5"/>"#);
+    let direct = render(r#"<rect width="10" height="10" fill="url(#g)" opacity="0.5"/>"#);
+
+    // the opacity reaches the gradient inside the tile, and is applied once
+    for x in 0 .. 20 {
+        let (a, b) = (patterned.get_pixel(x, 5).0, direct.get_pixel(x % 10, 5).0);
+        assert!((a[3] as i32 - 128).abs() <= 1, "{:?} at {}", a, x);
+        assert!(a.iter().zip(b.iter()).all(|(&p, &q)| (p as i32 - q as i32).abs() <= 2), "{:?} != {:?} at {}", a, b, x);
+    }
+}
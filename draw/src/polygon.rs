@@ -19,8 +19,9 @@ impl DrawItem for TagPolygon {
         }
     }
     fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
+        let options = options.apply_with_bbox(scene, &self.attrs, |_| Some(self.outline.bounds()));
         options.draw(scene, &self.outline);
+        options.draw_markers(scene, &self.outline);
     }
 }
 
@@ -34,8 +35,9 @@ impl DrawItem for TagPolyline {
         }
     }
     fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
+        let options = options.apply_with_bbox(scene, &self.attrs, |_| Some(self.outline.bounds()));
         options.draw(scene, &self.outline);
+        options.draw_markers(scene, &self.outline);
     }
 }
 
@@ -51,7 +53,11 @@ impl DrawItem for TagLine {
         }
     }
     fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
+        let options = options.apply_with_bbox(scene, &self.attrs, |options| {
+            let p1 = self.p1.resolve(options);
+            let p2 = self.p2.resolve(options);
+            Some(RectF::from_points(p1.min(p2), p1.max(p2)))
+        });
         let p1 = self.p1.resolve(&options);
         let p2 = self.p2.resolve(&options);
 
@@ -63,6 +69,7 @@ impl DrawItem for TagLine {
         outline.push_contour(contour);
 
         options.draw(scene, &outline);
+        options.draw_markers(scene, &outline);
     }
 }
 
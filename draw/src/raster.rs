@@ -0,0 +1,200 @@
+use crate::prelude::*;
+use pathfinder_rasterize::Rasterizer;
+use pathfinder_color::ColorF;
+use image::RgbaImage;
+
+/// color space the pixel values are encoded in
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ColorSpace {
+    /// sRGB, gamma encoded (what a PNG without a gAMA/iCCP chunk is assumed to be)
+    Srgb,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AlphaMode {
+    /// color channels are independent of alpha (what PNG expects)
+    Straight,
+    /// color channels are already multiplied by alpha
+    Premultiplied,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PixelFormat {
+    pub color_space: ColorSpace,
+    pub alpha: AlphaMode,
+}
+
+/// a rasterized image together with the format of its pixels
+pub struct RasterImage {
+    pub image: RgbaImage,
+    pub format: PixelFormat,
+}
+impl RasterImage {
+    /// convert to straight alpha, so it can be handed to an encoder as is
+    pub fn into_straight(mut self) -> RgbaImage {
+        if self.format.alpha == AlphaMode::Premultiplied {
+            for pixel in self.image.pixels_mut() {
+                let a = pixel[3] as u32;
+                if a == 0 {
+                    pixel.0 = [0; 4];
+                    continue;
+                }
+                for c in &mut pixel.0[.. 3] {
+                    *c = ((*c as u32 * 255 + a / 2) / a).min(255) as u8;
+                }
+            }
+        }
+        self.image
+    }
+}
+
+fn rasterize_scene(scene: Scene, background: Option<ColorF>) -> RasterImage {
+    let opaque = background.map_or(false, |c| c.a() >= 1.0);
+    let image = Rasterizer::new().rasterize(scene, background);
+    RasterImage {
+        image,
+        format: PixelFormat {
+            color_space: ColorSpace::Srgb,
+            alpha: if opaque { AlphaMode::Straight } else { AlphaMode::Premultiplied },
+        }
+    }
+}
+
+impl<'a> DrawContext<'a> {
+    /// compose and rasterize the document.
+    ///
+    /// The renderer blends premultiplied colors and the pixels are read back unchanged,
+    /// so the output is premultiplied unless an opaque `background` is given (then both modes coincide).
+    pub fn rasterize(&'a self, background: Option<ColorF>) -> RasterImage {
+        rasterize_scene(self.compose(), background)
+    }
+
+    /// rasterize the document into `buffer`, stretching the view box to `width` x `height` pixels.
+    ///
+    /// Rows are `stride` bytes apart and each pixel is 4 bytes RGBA; padding bytes are left untouched.
+    /// Returns the format the pixels were written in (see `rasterize`).
+    ///
+    /// # Panics
+    /// if `stride` is less than `4 * width` or `buffer` is too small for `height` rows.
+    pub fn rasterize_into(&'a self, background: Option<ColorF>, buffer: &mut [u8], width: u32, height: u32, stride: usize) -> PixelFormat {
+        let row_len = 4 * width as usize;
+        assert!(stride >= row_len, "stride {} is too small for {} pixels", stride, width);
+        assert!(height == 0 || buffer.len() >= stride * (height as usize - 1) + row_len, "buffer is too small");
+
+        let size = vec2f(width as f32, height as f32);
+        let mut scene = self.compose_with_transform(self.stretch_transform(size));
+        scene.set_view_box(RectF::new(Vector2F::zero(), size));
+
+        let raster = rasterize_scene(scene, background);
+        let src = raster.image.as_raw();
+        let src_stride = 4 * raster.image.width() as usize;
+        let copy_len = row_len.min(src_stride);
+        for (y, row) in buffer.chunks_mut(stride).take(height as usize).enumerate() {
+            let src_row = match src.get(y * src_stride ..) {
+                Some(r) => r,
+                None => break
+            };
+            row[.. copy_len].copy_from_slice(&src_row[.. copy_len]);
+        }
+        raster.format
+    }
+
+    /// rasterize the document like `rasterize_into`, stretched to `width` x `height` pixels, in tiles of `tile_size` x `tile_size` pixels.
+    /// The tiles in the last column and row are smaller if the size is not a multiple of `tile_size`.
+    ///
+    /// Every tile is composed and rasterized on its own, with the document moved so the tile is at the origin
+    /// and the view box of the scene cut to the tile, so no render target is ever larger than a tile.
+    /// Shapes outside of a tile are skipped when it is composed, like in `compose_region`.
+    /// `f` is called with the position of the tile in the whole image, in pixels, and its pixels, row by row from the top.
+    ///
+    /// # Panics
+    /// if `tile_size` is zero.
+    pub fn render_tiles(&'a self, background: Option<ColorF>, width: u32, height: u32, tile_size: u32, mut f: impl FnMut(u32, u32, RasterImage)) {
+        assert!(tile_size > 0, "tile_size must not be zero");
+        let transform = self.stretch_transform(vec2f(width as f32, height as f32));
+        for y in (0 .. height).step_by(tile_size as usize) {
+            for x in (0 .. width).step_by(tile_size as usize) {
+                let size = vec2f((width - x).min(tile_size) as f32, (height - y).min(tile_size) as f32);
+                let tile_transform = Transform2F::from_translation(-vec2f(x as f32, y as f32)) * transform;
+                let region = tile_transform.inverse() * RectF::new(Vector2F::zero(), size);
+                let mut scene = self.compose_region_with_transform(tile_transform, region);
+                scene.set_view_box(RectF::new(Vector2F::zero(), size));
+                f(x, y, rasterize_scene(scene, background));
+            }
+        }
+    }
+
+    /// the transform that stretches the view box to `size`
+    fn stretch_transform(&'a self, size: Vector2F) -> Transform2F {
+        match self.view_box() {
+            Some(vb) => Transform2F::from_scale(size * vb.size().recip()) * Transform2F::from_translation(-vb.origin()),
+            None => Transform2F::default()
+        }
+    }
+}
+
+#[test]
+fn test_into_straight() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 2 1">
+        <rect width="1" height="1" fill="red" fill-opacity="0.5"/>
+    </svg>"#).unwrap();
+    let raster = DrawContext::new_without_fonts(&svg).rasterize(None);
+    assert_eq!(raster.format.alpha, AlphaMode::Premultiplied);
+    // red at 50% opacity, premultiplied
+    assert_eq!(raster.image.get_pixel(0, 0).0, [128, 0, 0, 128]);
+    let straight = raster.into_straight();
+    assert_eq!(straight.get_pixel(0, 0).0, [255, 0, 0, 128]);
+    assert_eq!(straight.get_pixel(1, 0).0, [0, 0, 0, 0]);
+}
+
+#[test]
+fn test_rasterize_into() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 4 3">
+        <rect width="2" height="3" fill="red"/><rect x="2" width="2" height="2" fill="blue" opacity="0.5"/>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let expected = ctx.rasterize(None);
+
+    // two bytes of padding per row, which must not be touched
+    let stride = 4 * 4 + 2;
+    let mut buffer = vec![7u8; stride * 3];
+    let format = ctx.rasterize_into(None, &mut buffer, 4, 3, stride);
+    assert_eq!(format, expected.format);
+    for y in 0 .. 3 {
+        let row = &buffer[y * stride ..];
+        for x in 0 .. 4 {
+            assert_eq!(&row[4 * x .. 4 * x + 4], &expected.image.get_pixel(x as u32, y as u32).0[..]);
+        }
+        assert_eq!(&row[16 .. 18], &[7, 7]);
+    }
+}
+
+#[test]
+fn test_render_tiles() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 20 15">
+        <linearGradient id="g"><stop offset="0" stop-color="red"/><stop offset="1" stop-color="blue"/></linearGradient>
+        <rect width="20" height="15" fill="url(#g)"/>
+        <circle cx="10" cy="7" r="6" fill="lime" stroke="black"/>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let (width, height) = (40, 30);
+    let mut whole = vec![0; 4 * 40 * 30];
+    ctx.rasterize_into(None, &mut whole, width, height, 4 * width as usize);
+
+    let mut stitched = vec![0; whole.len()];
+    let mut tiles = 0;
+    ctx.render_tiles(None, width, height, 16, |x, y, tile| {
+        tiles += 1;
+        for (ty, row) in tile.image.rows().enumerate() {
+            for (tx, pixel) in row.enumerate() {
+                let i = 4 * ((y as usize + ty) * width as usize + x as usize + tx);
+                stitched[i .. i + 4].copy_from_slice(&pixel.0);
+            }
+        }
+    });
+    // 3 columns and 2 rows, the last of them partial
+    assert_eq!(tiles, 6);
+    for (i, (a, b)) in whole.iter().zip(stitched.iter()).enumerate() {
+        assert!((*a as i32 - *b as i32).abs() <= 1, "pixel {} differs: {} != {}", i / 4, a, b);
+    }
+}
@@ -6,6 +6,7 @@ fn rect_outline<'a>(tag: &TagRect, options: &Options<'a>) -> Option<Outline> {
         return None;
     }
 
+    // percentages of x, width and rx refer to the viewport width, those of y, height and ry to its height
     let size = tag.size.resolve(&options);
     if (size.x() == 0.) || (size.y() == 0.) {
         return None;
@@ -35,7 +36,7 @@ impl Shape for TagRect {
 }
 impl DrawItem for TagRect {
     fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
+        let options = options.apply_with_bbox(scene, &self.attrs, |options| rect_outline(self, options).map(|o| o.bounds()));
         if let Some(outline) = rect_outline(self, &options) {
             options.draw(scene, &outline);
         }
@@ -56,3 +57,16 @@ impl DrawItem for TagRect {
         options.bounds(RectF::new(origin, size))
     }
 }
+
+#[test]
+fn test_percent_size() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 200 100">
+        <rect x="25%" y="50%" width="50%" height="50%"/>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    assert_eq!(cmds.len(), 1);
+    // x and width refer to the width of the viewport, y and height to its height: a quarter of its area
+    let bounds = cmds[0].outline.bounds();
+    assert_eq!(bounds, RectF::new(vec2f(50., 50.), vec2f(100., 50.)));
+    assert_eq!(bounds.width() * bounds.height(), 0.25 * 200. * 100.);
+}
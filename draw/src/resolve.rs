@@ -4,10 +4,21 @@ use std::rc::Rc;
 fn apply_anim<T, U>(animate: &Animate<T>, base: U, options: &Options) -> U
 where T: Resolve, T::Output: Interpolate + Into<U>, U: Compose
 {
-    match (animate.additive, animate.resolve(options)) {
-        (Additive::Sum, Some(val)) => base.compose(val.into()),
-        (Additive::Replace, Some(val)) => val.into(),
-        (_, None) => base,
+    let val: U = match animate.resolve(options) {
+        Some(val) => val.into(),
+        None => return base
+    };
+    // the animation function of from-by starts at `from` instead of zero
+    let val = match animate.mode {
+        AnimationMode::Relative { from: Some(ref from), .. } => {
+            let from: U = from.resolve(options).into();
+            from.compose(val)
+        }
+        _ => val
+    };
+    match animate.additive {
+        Additive::Sum => base.compose(val),
+        Additive::Replace => val,
     }
 }
 impl<T> Resolve for Value<T> where T: Resolve + Parse + Clone, T::Output: Interpolate + Compose {
@@ -55,6 +66,17 @@ impl Resolve for LengthY {
         options.resolve_length_along(self.0, Axis::Y)
     }
 }
+impl Resolve for FontSize {
+    type Output = f32;
+    fn resolve(&self, options: &Options) -> f32 {
+        match *self {
+            // percentages and `em` refer to the font size of the parent, which `options` still holds
+            FontSize::Length(LengthY(length)) if length.unit == LengthUnit::Percent => 0.01 * length.num as f32 * options.font_size,
+            FontSize::Length(length) => length.resolve(options),
+            FontSize::Rem(n) => n * options.ctx.root_font_size,
+        }
+    }
+}
 impl Resolve for Vector {
     type Output = Vector2F;
     fn resolve(&self, options: &Options) -> Self::Output {
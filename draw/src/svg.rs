@@ -6,12 +6,64 @@ impl DrawItem for TagSvg {
         .or_else(|| max_bounds(self.items.iter().flat_map(|item| item.bounds(&options))))
     }
     fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
+        self.draw_filtered(scene, options, |_| true);
+    }
+}
+impl TagSvg {
+    /// draw the children for which `filter` returns true, in the coordinate system of this element
+    pub fn draw_filtered(&self, scene: &mut Scene, options: &DrawOptions, filter: impl Fn(&Item) -> bool) {
         let mut options = options.apply(scene, &self.attrs);
+        if options.too_deep() {
+            return;
+        }
         if let Some(ref view_box) = self.view_box {
-            options.apply_viewbox(self.width, self.height, view_box);
+            // with `slice` the content overflows the viewport and has to be cut off
+            if self.preserve_aspect_ratio.map(|a| a.slice).unwrap_or(false) {
+                let viewport = options.viewport(self.width, self.height, view_box);
+                options.clip_to_rect(scene, viewport);
+            }
+            options.apply_viewbox(self.width, self.height, view_box, self.preserve_aspect_ratio.as_ref());
         }
-        for item in self.items.iter() {
+        for item in self.items.iter().filter(|item| filter(item)) {
             item.draw_to(scene, &options);
         }
     }
 }
+
+#[test]
+fn test_root_clip_path() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" clip-path="url(#c)">
+        <clipPath id="c"><rect width="5" height="5"/></clipPath>
+        <rect width="10" height="10"/>
+        <g><rect x="20" width="10" height="10"/></g>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let cmds = ctx.flatten();
+    assert_eq!(cmds.len(), 2);
+    assert!(cmds.iter().all(|cmd| cmd.clip.len() == 1));
+}
+
+#[test]
+fn test_preserve_aspect_ratio_meet() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50" viewBox="0 0 10 10" preserveAspectRatio="xMidYMid meet">
+        <rect width="10" height="10"/>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    assert_eq!(cmds.len(), 1);
+    // scaled uniformly to fit the height, centered horizontally
+    assert_eq!(cmds[0].outline.bounds(), RectF::new(vec2f(25., 0.), vec2f(50., 50.)));
+    assert!(cmds[0].clip.is_empty());
+}
+
+#[test]
+fn test_preserve_aspect_ratio_slice() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50" viewBox="0 0 10 10" preserveAspectRatio="xMinYMin slice">
+        <rect width="10" height="10"/>
+    </svg>"#).unwrap();
+    let cmds = DrawContext::new_without_fonts(&svg).flatten();
+    assert_eq!(cmds.len(), 1);
+    // scaled uniformly to cover the width, the lower half is cut off by the viewport
+    assert_eq!(cmds[0].outline.bounds(), RectF::new(Vector2F::zero(), vec2f(100., 100.)));
+    assert_eq!(cmds[0].clip.len(), 1);
+    assert_eq!(cmds[0].clip[0].0.bounds(), RectF::new(Vector2F::zero(), vec2f(100., 50.)));
+}
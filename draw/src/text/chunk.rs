@@ -29,11 +29,11 @@ impl Chunk {
             runs
         }
     }
-    pub fn layout(&self, font: &FontCollection, lang: Option<Language>) -> ChunkLayout {
+    pub fn layout(&self, font: &FontCollection, lang: Option<Language>, small_caps: bool) -> ChunkLayout {
         let mut offset = Vector2F::zero();
         let mut parts = Vec::with_capacity(self.runs.len());
         for (level, run) in self.runs.iter() {
-            let layout = font.layout_run(&self.text[run.clone()], level.is_rtl(), lang);
+            let layout = font.layout_run(&self.text[run.clone()], level.is_rtl(), lang, small_caps);
 
             let advance = layout.metrics.advance;
             let (run_offset, next_offset) = match level.is_rtl() {
@@ -44,10 +44,17 @@ impl Chunk {
             offset = next_offset;
         }
 
-        ChunkLayout { parts, advance: offset }
+        let ascent = parts.iter().map(|(_, _, layout)| layout.metrics.ascent).fold(0.0, f32::max);
+        let descent = parts.iter().map(|(_, _, layout)| layout.metrics.descent).fold(0.0, f32::min);
+
+        ChunkLayout { parts, advance: offset, ascent, descent }
     }
 }
 pub struct ChunkLayout {
     pub parts: Vec<(usize, Vector2F, Layout)>,
     pub advance: Vector2F,
+
+    // in em, ascent above and descent below the baseline (negative)
+    pub ascent: f32,
+    pub descent: f32,
 }
\ No newline at end of file
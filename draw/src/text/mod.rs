@@ -8,12 +8,29 @@ use svg_text::{Font, FontCollection};
 use chunk::{Chunk, ChunkLayout};
 use crate::draw_glyph;
 use unic_segment::{WordBounds, GraphemeIndices};
+use isolang::Language;
+use pathfinder_content::{outline::Contour, stroke::LineCap};
+use std::rc::Rc;
+use std::borrow::Cow;
+
+/// thickness of decoration lines in em (the fonts don't provide underline metrics)
+const DECORATION_THICKNESS: f32 = 0.05;
+
+/// the laid out chunks are kept until there are more than this many, then the cache starts over
+const LAYOUT_CACHE_SIZE: usize = 1024;
+
+// text, right-to-left, language, small-caps.
+// The fonts are not part of it, as the cache belongs to the `FontCache` of the fallback fonts, which do all the layout.
+type LayoutKey = (String, bool, Option<Language>, bool);
 
 #[derive(Clone)]
 pub struct FontCache<'a> {
     // TODO: use a lock-free map
     entries: Arc<Mutex<HashMap<String, &'a FontCollection>>>,
     fallback: &'a FontCollection,
+    /// layouts (in em) of the chunks drawn with the fallback fonts, which do not depend on the font size.
+    /// Redrawing the same text (e.g. every frame of an animation) reuses them.
+    layouts: Arc<Mutex<HashMap<LayoutKey, Arc<ChunkLayout>>>>,
 }
 impl<'a> fmt::Debug for FontCache<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -25,20 +42,49 @@ impl<'a> FontCache<'a> {
         FontCache {
             entries: Arc::new(Mutex::new(HashMap::new())),
             fallback,
+            layouts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+    /// identifies the fallback fonts, for the key of the `SceneCache`
+    pub(crate) fn fallback_address(&self) -> usize {
+        self.fallback as *const FontCollection as usize
+    }
+    fn layout(&self, text: &str, direction: TextFlow, lang: Option<Language>, small_caps: bool) -> Arc<ChunkLayout> {
+        let rtl = matches!(direction, TextFlow::RightToLeft);
+        let key = (text.to_owned(), rtl, lang, small_caps);
+        if let Some(layout) = self.layouts.lock().unwrap().get(&key) {
+            return layout.clone();
+        }
+
+        let layout = Arc::new(Chunk::new(text, direction).layout(self.fallback, lang, small_caps));
+        let mut layouts = self.layouts.lock().unwrap();
+        if layouts.len() >= LAYOUT_CACHE_SIZE {
+            layouts.clear();
         }
+        layouts.insert(key, layout.clone());
+        layout
     }
 }
 
 impl DrawItem for TagText {
     fn draw_to(&self, scene: &mut Scene, options: &DrawOptions) {
-        let options = options.apply(scene, &self.attrs);
+        let mut options = options.apply(scene, &self.attrs);
+        if let Some(clip) = crate::text_clip_rect(self, &options) {
+            options.clip_to_rect(scene, clip);
+        }
         let state = TextState {
             pos: Vector2F::zero(),
-            rot: 0.0
+            rot: 0.0,
+            // white space at the start of the text is dropped
+            after_space: true,
         };
 
         if let Some(ref font_cache) = options.ctx.font_cache {
-            draw_items(scene, &options, font_cache, &self.pos, &self.items, state, 0, None);
+            // the advances of the anchored chunks are measured first, in a pass that draws nothing
+            let mut anchors = Anchors::new(options.text_anchor);
+            let (end, _) = draw_items(&mut Scene::new(), &options, font_cache, &self.pos, &self.items, state, 0, None, &mut anchors);
+            let mut anchors = anchors.finish(end.pos);
+            draw_items(scene, &options, font_cache, &self.pos, &self.items, state, 0, None, &mut anchors);
         }
     }
     fn bounds(&self, options: &BoundsOptions) -> Option<RectF> {
@@ -50,6 +96,8 @@ impl DrawItem for TagText {
 struct TextState {
     pos: Vector2F,
     rot: f32,
+    /// the last character was a collapsible space, so white space that follows it collapses into it
+    after_space: bool,
 }
 impl TextState {
     fn apply_move(self, m: Move) -> TextState {
@@ -58,45 +106,149 @@ impl TextState {
         let rot = m.rot.unwrap_or(self.rot);
         TextState {
             pos: vec2f(x, y) + m.rel,
-            rot
+            rot,
+            .. self
+        }
+    }
+}
+
+/// `text-anchor` applies to text chunks: the text from one absolute position to the next, across elements.
+/// The first pass over the text only measures the advance of every chunk (in user space, so right-to-left text
+/// has a negative one), the second one draws each chunk shifted by the anchored part of its advance.
+#[derive(Debug)]
+struct Anchors {
+    measuring: bool,
+    /// the shift of every chunk, filled in by the first pass
+    shifts: Vec<Vector2F>,
+    /// the chunk the text is in
+    index: usize,
+    /// no text has been laid out in the current chunk yet
+    empty: bool,
+    /// where the current chunk starts, and the anchor of its first character
+    start: Vector2F,
+    anchor: TextAnchor,
+}
+impl Anchors {
+    fn new(anchor: TextAnchor) -> Anchors {
+        Anchors {
+            measuring: true,
+            shifts: vec![],
+            index: 0,
+            empty: true,
+            start: Vector2F::zero(),
+            anchor,
+        }
+    }
+    /// the text moved to an absolute position, which starts a new chunk
+    fn move_to(&mut self, pos: Vector2F, end: Vector2F, anchor: TextAnchor) {
+        if !self.empty {
+            if self.measuring {
+                self.shifts.push((self.start - end) * self.anchor.fraction());
+            }
+            self.index += 1;
         }
+        self.empty = true;
+        self.start = pos;
+        self.anchor = anchor;
+    }
+    /// end the measuring pass at `end` and prepare the drawing pass
+    fn finish(mut self, end: Vector2F) -> Anchors {
+        let anchor = self.anchor;
+        self.move_to(Vector2F::zero(), end, anchor);
+        Anchors {
+            measuring: false,
+            shifts: self.shifts,
+            .. Anchors::new(anchor)
+        }
+    }
+    fn shift(&self) -> Vector2F {
+        self.shifts.get(self.index).cloned().unwrap_or(Vector2F::zero())
     }
 }
 
-fn chunk(scene: &mut Scene, options: &DrawOptions, s: &str, state: TextState, font_collection: &FontCollection) -> Vector2F {
+fn chunk(scene: &mut Scene, options: &DrawOptions, s: &str, mut state: TextState, font_cache: &FontCache, anchors: &mut Anchors) -> Vector2F {
     debug!("{} {:?}", s, state);
-    let layout = Chunk::new(s, options.direction).layout(font_collection, options.lang);
-    draw_layout(font_collection, &layout, scene, &options, state)
+    let small_caps = options.font_variant == FontVariant::SmallCaps;
+    let layout = font_cache.layout(s, options.direction, options.lang, small_caps);
+    anchors.empty = false;
+    if anchors.measuring {
+        return layout.advance * options.font_size;
+    }
+    state.pos = state.pos + anchors.shift();
+    let baseline = options.alignment_baseline.unwrap_or(options.dominant_baseline);
+    let baseline = vec2f(0.0, baseline_shift(&layout, baseline));
+    draw_layout(font_cache.fallback, &layout, scene, &options, state, baseline)
+}
+
+/// how far (in em, downwards) the alphabetic baseline has to move so that the requested baseline sits at the text position.
+/// The fonts only provide ascent and descent, so the x-height (for `middle`) and the mathematical baseline are estimated from the ascent.
+fn baseline_shift(layout: &ChunkLayout, baseline: DominantBaseline) -> f32 {
+    let (ascent, descent) = (layout.ascent, layout.descent);
+    match baseline {
+        DominantBaseline::Auto | DominantBaseline::Alphabetic => 0.0,
+        DominantBaseline::Ideographic | DominantBaseline::TextAfterEdge => descent,
+        DominantBaseline::Middle => 0.25 * ascent,
+        DominantBaseline::Central => 0.5 * (ascent + descent),
+        DominantBaseline::Mathematical => 0.5 * ascent,
+        DominantBaseline::Hanging | DominantBaseline::TextBeforeEdge => ascent,
+    }
 }
 
-fn draw_items(scene: &mut Scene, options: &DrawOptions, font_cache: &FontCache, pos: &GlyphPos, items: &[Arc<Item>], mut state: TextState, mut char_idx: usize, parent_moves: Option<&Moves>) -> (TextState, usize) {
-    let fallback = &font_cache.fallback;
+fn draw_items(scene: &mut Scene, options: &DrawOptions, font_cache: &FontCache, pos: &GlyphPos, items: &[Arc<Item>], mut state: TextState, mut char_idx: usize, parent_moves: Option<&Moves>, anchors: &mut Anchors) -> (TextState, usize) {
     let moves = Moves::new(pos, char_idx, parent_moves);
 
     for item in items.iter() {
         match **item {
             Item::String(ref s) if s.len() > 0 => {
+                let collapsed = collapse_white_space(s, options.white_space, &mut state.after_space);
+                let s = &*collapsed;
+                if s.is_empty() {
+                    continue;
+                }
                 let mut start = 0;
+                // every grapheme with an entry in the position lists starts a new chunk,
+                // the graphemes after the last entry flow from there
                 for (idx, grapheme) in GraphemeIndices::new(s) {
                     let num_chars = grapheme.chars().count();
                     if let Some(next_move) = moves.get(&options, num_chars, char_idx) {
-                        if idx > 0 {
-                            state.pos = state.pos + chunk(scene, options, &s[start .. idx], state, fallback);
+                        if idx > start {
+                            state.pos = state.pos + chunk(scene, options, &s[start .. idx], state, font_cache, anchors);
                         }
                         start = idx;
+                        let end = state.pos;
+                        let absolute = next_move.abs_x.is_some() || next_move.abs_y.is_some();
                         state = state.apply_move(next_move);
-                        char_idx += num_chars;
+                        if absolute {
+                            anchors.move_to(state.pos, end, options.text_anchor);
+                        }
                     }
+                    char_idx += num_chars;
                 }
 
                 let part = &s[start ..];
-                let num_chars = part.chars().count();
-                state.pos = state.pos + chunk(scene, options, part, state, fallback);
-                char_idx += num_chars;
+                state.pos = state.pos + chunk(scene, options, part, state, font_cache, anchors);
             },
             Item::TSpan(ref span) => {
                 let options = options.apply(scene, &span.attrs);
-                let (new_state, new_idx) = draw_items(scene, &options, font_cache, &span.pos, &span.items, state, char_idx, Some(&moves));
+                let (new_state, new_idx) = draw_items(scene, &options, font_cache, &span.pos, &span.items, state, char_idx, Some(&moves), anchors);
+                state = new_state;
+                char_idx = new_idx;
+            }
+            Item::TRef(ref tref) => {
+                let href = match tref.href {
+                    Some(ref href) => href,
+                    None => continue
+                };
+                let referenced = match options.ctx.resolve_href(href) {
+                    Some(item) => item,
+                    None => {
+                        warn!("can't resolve <tref href={:?}>", href);
+                        continue;
+                    }
+                };
+                let items = [Arc::new(Item::String(text_content(referenced)))];
+                let options = options.apply(scene, &tref.attrs);
+                let (new_state, new_idx) = draw_items(scene, &options, font_cache, &tref.pos, &items, state, char_idx, Some(&moves), anchors);
                 state = new_state;
                 char_idx = new_idx;
             }
@@ -107,24 +259,143 @@ fn draw_items(scene: &mut Scene, options: &DrawOptions, font_cache: &FontCache,
     (state, char_idx)
 }
 
-fn draw_layout(font_collection: &FontCollection, layout: &ChunkLayout, scene: &mut Scene, options: &DrawOptions, state: TextState) -> Vector2F {
-    for &(_, offset, ref sublayout) in &layout.parts {
-        for glyph in &sublayout.glyphs {
-            let chunk_tr = Transform2F::from_translation(state.pos) * Transform2F::from_rotation(deg2rad(state.rot))
-                * Transform2F::from_scale(options.font_size)
-                * Transform2F::from_translation(offset + glyph.offset);
-            let tr = chunk_tr * glyph.transform;
-            let font = &font_collection[glyph.font_idx];
-            if let Some(ref svg) = font.svg_glyph(glyph.gid) {
-                draw_glyph(svg, scene, tr);
-            } else {
-                options.draw_transformed(scene, &font.glyph(glyph.gid).unwrap().path, tr);
+/// apply `white-space` to the text `s`. Line breaks and tabs become spaces, as lines are not broken.
+/// With `normal` and `nowrap`, runs of spaces collapse into one, also across elements (which `after_space` carries over).
+/// `pre` and `pre-wrap` keep every space.
+fn collapse_white_space<'s>(s: &'s str, white_space: WhiteSpace, after_space: &mut bool) -> Cow<'s, str> {
+    let is_space = |c: char| matches!(c, ' ' | '\t' | '\n' | '\r');
+    if !white_space.collapses() {
+        *after_space = false;
+        return match s.contains(|c: char| matches!(c, '\t' | '\n' | '\r')) {
+            true => Cow::Owned(s.chars().map(|c| if is_space(c) { ' ' } else { c }).collect()),
+            false => Cow::Borrowed(s)
+        };
+    }
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if is_space(c) {
+            if !*after_space {
+                out.push(' ');
+            }
+            *after_space = true;
+        } else {
+            out.push(c);
+            *after_space = false;
+        }
+    }
+    Cow::Owned(out)
+}
+
+fn draw_layout(font_collection: &FontCollection, layout: &ChunkLayout, scene: &mut Scene, options: &DrawOptions, state: TextState, baseline: Vector2F) -> Vector2F {
+    let chunk_tr = Transform2F::from_translation(state.pos) * Transform2F::from_rotation(deg2rad(state.rot))
+        * Transform2F::from_scale(options.font_size)
+        * Transform2F::from_translation(baseline);
+    // the fill and the stroke are painted for all glyphs at once, in `paint-order`,
+    // otherwise the fill of a glyph would cover the stroke of the glyph before it
+    let mut fill = options.clone();
+    fill.stroke = Paint::None;
+    let mut stroke = options.clone();
+    stroke.fill = Paint::None;
+    let layers = match options.paint_order.stroke_below_fill() {
+        false => [fill, stroke],
+        true => [stroke, fill],
+    };
+    for (i, layer) in layers.iter().enumerate() {
+        for &(_, offset, ref sublayout) in &layout.parts {
+            for glyph in &sublayout.glyphs {
+                let tr = chunk_tr * Transform2F::from_translation(offset + glyph.offset) * glyph.transform;
+                let font = &font_collection[glyph.font_idx];
+                if let Some(ref svg) = font.svg_glyph(glyph.gid) {
+                    // color glyphs bring their own paint
+                    if i == 0 {
+                        draw_glyph(svg, scene, tr);
+                    }
+                } else {
+                    layer.draw_transformed(scene, &font.glyph(glyph.gid).unwrap().path, tr);
+                }
             }
         }
     }
+    draw_decoration(scene, options, layout.ascent, layout.advance.x(), chunk_tr);
     layout.advance * options.font_size
 }
 
+/// draw the `text-decoration` lines of a chunk that is `advance` em wide and `ascent` em high.
+/// `transform` maps em (relative to the baseline at the start of the chunk) to user space.
+/// The lines are stroked with the fill of the text, so dashes and dots use the regular dash machinery,
+/// in user space like any other stroke.
+fn draw_decoration(scene: &mut Scene, options: &DrawOptions, ascent: f32, advance: f32, transform: Transform2F) {
+    let decoration = options.text_decoration;
+    if decoration.is_none() || advance == 0.0 {
+        return;
+    }
+    let t = DECORATION_THICKNESS;
+    let (x0, x1) = (advance.min(0.0), advance.max(0.0));
+    let line = |y: f32| {
+        let mut contour = Contour::new();
+        contour.push_endpoint(vec2f(x0, y));
+        contour.push_endpoint(vec2f(x1, y));
+        contour
+    };
+
+    let mut outline = Outline::new();
+    // y is in em, downwards from the baseline. line-through uses the same x-height estimate as `baseline_shift`
+    let lines = [
+        (decoration.underline, 2.0 * t),
+        (decoration.overline, -ascent),
+        (decoration.line_through, -0.25 * ascent),
+    ];
+    for &(_, y) in lines.iter().filter(|&&(enabled, _)| enabled) {
+        match options.text_decoration_style {
+            TextDecorationStyle::Double => {
+                outline.push_contour(line(y - t));
+                outline.push_contour(line(y + t));
+            }
+            TextDecorationStyle::Wavy => outline.push_contour(wavy_line(x0, x1, y, t)),
+            _ => outline.push_contour(line(y)),
+        }
+    }
+
+    let mut options = options.clone();
+    options.stroke = options.fill.clone();
+    options.stroke_opacity = options.fill_opacity;
+    options.fill = Paint::None;
+    // the stroke is in user space, not in em
+    let t = t * options.font_size;
+    options.stroke_style.line_width = t;
+    options.stroke_style.line_cap = LineCap::Butt;
+    // the decoration styles bring their own pattern, otherwise a `stroke-dasharray` of the text applies
+    match options.text_decoration_style {
+        TextDecorationStyle::Dashed => {
+            options.stroke_dasharray = Some(Rc::from(&[3.0 * t, 3.0 * t][..]));
+            options.stroke_dashoffset = 0.0;
+        }
+        TextDecorationStyle::Dotted => {
+            options.stroke_dasharray = Some(Rc::from(&[t, t][..]));
+            options.stroke_dashoffset = 0.0;
+        }
+        _ => {}
+    }
+    options.draw_transformed(scene, &outline, transform);
+}
+
+/// an approximation of a sine wave from `x0` to `x1` around `y`, made of quadratic arcs
+fn wavy_line(x0: f32, x1: f32, y: f32, amplitude: f32) -> Contour {
+    let half_period = 3.0 * amplitude;
+    let mut contour = Contour::new();
+    contour.push_endpoint(vec2f(x0, y));
+    let mut x = x0;
+    let mut sign = -1.0;
+    while x < x1 {
+        let next = (x + half_period).min(x1);
+        // the curve peaks at half the height of its control point
+        contour.push_quadratic(vec2f(0.5 * (x + next), y + sign * 2.0 * amplitude), vec2f(next, y));
+        x = next;
+        sign = -sign;
+    }
+    contour
+}
+
 fn slice<T>(o: &Option<OneOrMany<T>>) -> &[T] {
     o.as_ref().map(|l| l.as_slice()).unwrap_or(&[])
 }
@@ -199,4 +470,314 @@ struct Move {
     abs_y: Option<f32>,
     rel: Vector2F,
     rot: Option<f32>
-}
\ No newline at end of file
+}
+#[cfg(test)]
+pub(crate) fn latin_modern() -> FontCollection {
+    FontCollection::from_fonts(vec![
+        Font::load(include_bytes!("../../../resources/latinmodern-math.otf")),
+    ])
+}
+
+/// the union of the bounds of everything drawn for `content`, placed in an `<svg>`
+#[cfg(test)]
+fn text_bounds(fonts: &FontCollection, content: &str) -> RectF {
+    let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg">{}</svg>"#, content)).unwrap();
+    let cmds = DrawContext::new(&svg, fonts).flatten();
+    max_bounds(cmds.iter().map(|cmd| cmd.outline.bounds())).unwrap()
+}
+
+#[test]
+fn test_text_position_list() {
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg"><text id="t" x="0 10 20">ABCD</text></svg>"#).unwrap();
+    let text = item!(svg, "t", Text);
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let options = DrawOptions::new(&ctx);
+    let moves = Moves::new(&text.pos, 0, None);
+    let xs: Vec<_> = (0 .. 4).map(|idx| moves.get(&options, 1, idx).and_then(|m| m.abs_x)).collect();
+    assert_eq!(xs, [Some(0.0), Some(10.0), Some(20.0), None]);
+
+    // without a position of its own, the "D" follows the "C" at 20
+    let fonts = latin_modern();
+    let glyphs = |content: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg">{}</svg>"#, content)).unwrap();
+        DrawContext::new(&svg, &fonts).flatten().iter().map(|cmd| cmd.outline.bounds()).collect::<Vec<_>>()
+    };
+    let listed = glyphs(r#"<text x="0 10 20" font-size="20">ABCD</text>"#);
+    let continued = glyphs(r#"<text x="20" font-size="20">CD</text>"#);
+    assert_eq!(listed.len(), 4);
+    assert!((listed[2].min_x() - continued[0].min_x()).abs() < 1e-3, "{:?} {:?}", listed, continued);
+    assert!((listed[3].min_x() - continued[1].min_x()).abs() < 1e-3, "{:?} {:?}", listed, continued);
+    assert!(listed[3].min_x() > listed[2].max_x() - 1.);
+}
+
+#[test]
+fn test_dashed_underline() {
+    use crate::flatten::Recorder;
+
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <text id="t" text-decoration="underline" text-decoration-style="dashed">A</text>
+    </svg>"#).unwrap();
+    let text = item!(svg, "t", Text);
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let recorder = Arc::new(Mutex::new(Recorder::default()));
+    let mut options = DrawOptions::new(&ctx);
+    options.recorder = Some(recorder.clone());
+    let mut scene = Scene::new();
+    let options = options.apply(&mut scene, &text.attrs);
+    draw_decoration(&mut scene, &options, 0.8, 10.0, Transform2F::from_scale(options.font_size));
+    drop(options);
+
+    let cmds = Recorder::unwrap_shared(recorder).into_cmds();
+    assert_eq!(cmds.len(), 1);
+    // 10 em with a period of 0.3 em, every dash is a contour of its own
+    assert!(cmds[0].outline.contours().len() > 30);
+}
+
+#[test]
+fn test_dash_stroke_and_underline() {
+    use crate::flatten::Recorder;
+
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <text id="t" text-decoration="underline" stroke-dasharray="4 2" font-size="20">A</text>
+    </svg>"#).unwrap();
+    let text = item!(svg, "t", Text);
+    let ctx = DrawContext::new_without_fonts(&svg);
+    let recorder = Arc::new(Mutex::new(Recorder::default()));
+    let mut options = DrawOptions::new(&ctx);
+    options.recorder = Some(recorder.clone());
+    let mut scene = Scene::new();
+    let options = options.apply(&mut scene, &text.attrs);
+
+    // a 60 units long underline, and a stroke of the same length
+    draw_decoration(&mut scene, &options, 0.8, 3.0, Transform2F::from_scale(options.font_size));
+    let mut line = Contour::new();
+    line.push_endpoint(vec2f(0.0, 0.0));
+    line.push_endpoint(vec2f(60.0, 0.0));
+    let mut outline = Outline::new();
+    outline.push_contour(line);
+    let mut stroke = options.clone();
+    stroke.stroke = stroke.fill.clone();
+    stroke.fill = Paint::None;
+    stroke.draw(&mut scene, &outline);
+    drop(options);
+    drop(stroke);
+
+    let cmds = Recorder::unwrap_shared(recorder).into_cmds();
+    assert_eq!(cmds.len(), 2);
+    let dashes = |outline: &Outline| -> Vec<(f32, f32)> {
+        outline.contours().iter().map(|c| (c.bounds().min_x(), c.bounds().max_x())).collect()
+    };
+    let (underline, stroke) = (dashes(&cmds[0].outline), dashes(&cmds[1].outline));
+    assert_eq!(underline.len(), 10);
+    assert_eq!(underline.len(), stroke.len());
+    for (a, b) in underline.iter().zip(stroke.iter()) {
+        assert!((a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3, "{:?} != {:?}", underline, stroke);
+    }
+}
+
+#[test]
+fn test_text_transform() {
+    let fonts = latin_modern();
+    let bounds = |content: &str| text_bounds(&fonts, content);
+    let close = |a: RectF, b: RectF| (a.origin() - b.origin()).length() < 1e-2 && (a.size() - b.size()).length() < 1e-2;
+
+    let upright = bounds(r#"<text font-size="20">HHHH</text>"#);
+    let rotated = bounds(r#"<text font-size="20" transform="rotate(90)">HHHH</text>"#);
+    assert!(upright.width() > upright.height());
+    // rotating by 90 degrees maps (x, y) to (-y, x)
+    let expected = RectF::from_points(vec2f(-upright.max_y(), upright.min_x()), vec2f(-upright.min_y(), upright.max_x()));
+    assert!(close(rotated, expected), "{:?} != {:?}", rotated, expected);
+
+    // only the run of the tspan moves
+    let moved = bounds(r#"<text font-size="20">HH<tspan transform="translate(0 100)">HH</tspan></text>"#);
+    assert!((moved.min_y() - upright.min_y()).abs() < 1e-2, "{:?}", moved);
+    assert!((moved.max_y() - (upright.max_y() + 100.)).abs() < 1e-2, "{:?}", moved);
+}
+
+#[test]
+fn test_text_paint_order() {
+    use pathfinder_color::ColorU;
+
+    let fonts = latin_modern();
+    let paints = |paint_order: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <text font-size="20" fill="white" stroke="red" stroke-width="4" paint-order="{}">HI</text>
+        </svg>"#, paint_order)).unwrap();
+        let cmds = DrawContext::new(&svg, &fonts).flatten();
+        cmds.iter().map(|cmd| cmd.paint.base_color()).collect::<Vec<_>>()
+    };
+    let (white, red) = (ColorU::white(), ColorU::new(255, 0, 0, 255));
+
+    // by default the strokes of all glyphs are painted over the fills
+    assert_eq!(paints("normal"), [white, white, red, red]);
+    // the fill over the stroke leaves a red outline around the white glyphs
+    assert_eq!(paints("stroke"), [red, red, white, white]);
+}
+
+#[test]
+fn test_white_space() {
+    let collapse = |s, white_space| collapse_white_space(s, white_space, &mut true).into_owned();
+    assert_eq!(collapse("  a \n\t b  ", WhiteSpace::Normal), "a b ");
+    assert_eq!(collapse("  a \n\t b  ", WhiteSpace::Nowrap), "a b ");
+    assert_eq!(collapse("  a \n\t b  ", WhiteSpace::Pre), "  a    b  ");
+
+    let fonts = latin_modern();
+    let width = |content: &str| text_bounds(&fonts, content).width();
+    let single = width(r#"<text font-size="20">H H</text>"#);
+    // the spaces collapse, also across the tspan
+    assert!((width(r#"<text font-size="20">H   <tspan> H</tspan></text>"#) - single).abs() < 1e-3);
+    assert!(width(r#"<text font-size="20" style="white-space: pre">H   H</text>"#) > single + 10.);
+    assert!(width(r#"<text font-size="20" xml:space="preserve">H   H</text>"#) > single + 10.);
+}
+
+#[test]
+fn test_lang_forms() {
+    let fonts = FontCollection::from_fonts(vec![
+        Font::load(include_bytes!("../../../resources/NotoNaskhArabic-Regular.ttf")),
+    ]);
+    let outline = |attrs: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg"><text font-size="100" {}>&#x6F4;</text></svg>"#, attrs)).unwrap();
+        let cmds = DrawContext::new(&svg, &fonts).flatten();
+        assert_eq!(cmds.len(), 1);
+        format!("{:?}", cmds[0].outline)
+    };
+    // Urdu has its own form of the digit four, Persian uses the default one
+    let persian = outline(r#"lang="fa""#);
+    let urdu = outline(r#"lang="ur""#);
+    assert_ne!(persian, urdu);
+    assert_eq!(outline(r#"xml:lang="ur""#), urdu);
+}
+
+#[test]
+fn test_text_anchor() {
+    let fonts = latin_modern();
+    let bounds = |content: &str| text_bounds(&fonts, content);
+    let close = |a: RectF, b: RectF| (a.origin() - b.origin()).length() < 1e-3 && (a.size() - b.size()).length() < 1e-3;
+
+    let start = bounds(r#"<text x="100" y="50" font-size="20">HH</text>"#);
+    let middle = bounds(r#"<text x="100" y="50" font-size="20" text-anchor="middle">HH</text>"#);
+    let end = bounds(r#"<text x="100" y="50" font-size="20" text-anchor="end">HH</text>"#);
+    let advance = start.min_x() - end.min_x();
+    assert!(advance >= start.width() - 1e-3);
+    assert!(close(middle, Transform2F::from_translation(vec2f(-0.5 * advance, 0.)) * start));
+    assert!(close(end, Transform2F::from_translation(vec2f(-advance, 0.)) * start));
+
+    // the anchor of the first character applies to the whole chunk, also across a tspan
+    assert!(close(bounds(r#"<text x="100" y="50" font-size="20" text-anchor="middle">H<tspan text-anchor="end">H</tspan></text>"#), middle));
+    // every absolute position starts a chunk of its own
+    let single = bounds(r#"<text x="100" y="50" font-size="20" text-anchor="end">H</text>"#);
+    let stacked = bounds(r#"<text x="100 100" y="50 80" font-size="20" text-anchor="end">HH</text>"#);
+    assert!((stacked.min_x() - single.min_x()).abs() < 1e-3 && (stacked.max_x() - single.max_x()).abs() < 1e-3);
+}
+
+#[test]
+fn test_baseline_middle() {
+    let fonts = latin_modern();
+    let glyphs = |content: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg">{}</svg>"#, content)).unwrap();
+        DrawContext::new(&svg, &fonts).flatten().iter().map(|cmd| cmd.outline.bounds()).collect::<Vec<_>>()
+    };
+    let center_y = |rect: RectF| 0.5 * (rect.min_y() + rect.max_y());
+
+    // the x-height is estimated from the ascent, so the center of the `x` is only close to the position
+    let middle = glyphs(r#"<text y="50" font-size="100" dominant-baseline="middle">x</text>"#);
+    assert!((center_y(middle[0]) - 50.).abs() < 10.);
+    let alphabetic = glyphs(r#"<text y="50" font-size="100">x</text>"#);
+    assert!((center_y(alphabetic[0]) - 50.).abs() > 15.);
+
+    // alignment-baseline only applies to the element itself
+    let aligned = glyphs(r#"<text y="50" font-size="100">x<tspan alignment-baseline="middle">x<tspan>x</tspan></tspan></text>"#);
+    assert_eq!(aligned.len(), 3);
+    assert!((center_y(aligned[0]) - center_y(alphabetic[0])).abs() < 1e-3);
+    assert!((center_y(aligned[1]) - center_y(middle[0])).abs() < 1e-3);
+    assert!((center_y(aligned[2]) - center_y(alphabetic[0])).abs() < 1e-3);
+}
+
+#[test]
+fn test_small_caps() {
+    // the font has no `smcp` feature, so the small capitals are synthesized
+    let fonts = latin_modern();
+    let bounds = |content: &str| text_bounds(&fonts, content);
+
+    let capitals = bounds(r#"<text y="100" font-size="100">XX</text>"#);
+    let lowercase = bounds(r#"<text y="100" font-size="100">xx</text>"#);
+    let small_caps = bounds(r#"<text y="100" font-size="100" font-variant="small-caps">xx</text>"#);
+    // scaled down capitals on the baseline, not the lowercase letters
+    let ratio = small_caps.height() / capitals.height();
+    assert!((ratio - 0.75).abs() < 0.02, "{}", ratio);
+    assert!((small_caps.max_y() - capitals.max_y()).abs() < 1.);
+    assert!((small_caps.height() - lowercase.height()).abs() > 5.);
+}
+
+#[test]
+fn test_layout_cache() {
+    let fonts = latin_modern();
+    let cache = FontCache::new(&fonts);
+    let layout = |text: &str, small_caps| cache.layout(text, TextFlow::LeftToRight, None, small_caps);
+
+    // repeated layouts hit the cache
+    let hello = layout("Hello", false);
+    assert!(Arc::ptr_eq(&hello, &layout("Hello", false)));
+    assert!(!Arc::ptr_eq(&hello, &layout("Hello", true)));
+    assert!(!Arc::ptr_eq(&hello, &cache.layout("Hello", TextFlow::RightToLeft, None, false)));
+
+    // it starts over once full
+    for i in 0 .. LAYOUT_CACHE_SIZE {
+        layout(&i.to_string(), false);
+    }
+    assert!(!Arc::ptr_eq(&hello, &layout("Hello", false)));
+
+    // the layouts are in em, so a different font size in the same document scales the cached one
+    let svg = Svg::from_str(r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <text font-size="20">Hello</text>
+        <text y="100" font-size="40">Hello</text>
+    </svg>"#).unwrap();
+    let ctx = DrawContext::new(&svg, &fonts);
+    let width = |cmds: &[FlatDrawCmd]| max_bounds(cmds.iter().map(|cmd| cmd.outline.bounds())).unwrap().width();
+    let cmds = ctx.flatten();
+    let (small, large) = cmds.split_at(cmds.len() / 2);
+    assert!((width(large) - 2. * width(small)).abs() < 1e-2, "{} {}", width(small), width(large));
+
+    // other fonts have a cache of their own
+    let other = latin_modern();
+    let other_cache = FontCache::new(&other);
+    assert!(!Arc::ptr_eq(&layout("Hello", false), &other_cache.layout("Hello", TextFlow::LeftToRight, None, false)));
+}
+
+#[test]
+fn test_baseline_inherited() {
+    let fonts = latin_modern();
+    let glyphs = |content: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg">{}</svg>"#, content)).unwrap();
+        DrawContext::new(&svg, &fonts).flatten().iter().map(|cmd| cmd.outline.bounds()).collect::<Vec<_>>()
+    };
+    let center_y = |rect: RectF| 0.5 * (rect.min_y() + rect.max_y());
+
+    let central = glyphs(r#"<text y="50" font-size="100" dominant-baseline="central">x</text>"#);
+    let alphabetic = glyphs(r#"<text y="50" font-size="100">x</text>"#);
+    assert!((center_y(central[0]) - center_y(alphabetic[0])).abs() > 10.);
+
+    // dominant-baseline is inherited by the tspans, unless they set their own
+    let nested = glyphs(r#"<text y="50" font-size="100" dominant-baseline="central">x<tspan>x<tspan>x</tspan></tspan><tspan dominant-baseline="alphabetic">x</tspan></text>"#);
+    assert_eq!(nested.len(), 4);
+    for glyph in &nested[.. 3] {
+        assert!((center_y(*glyph) - center_y(central[0])).abs() < 1e-3, "{:?}", nested);
+    }
+    assert!((center_y(nested[3]) - center_y(alphabetic[0])).abs() < 1e-3, "{:?}", nested);
+}
+
+#[test]
+fn test_tref() {
+    let fonts = latin_modern();
+    let outlines = |content: &str| {
+        let svg = Svg::from_str(&format!(r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+            <defs><text id="source">Hello</text></defs>
+            {}
+        </svg>"#, content)).unwrap();
+        DrawContext::new(&svg, &fonts).flatten().iter().map(|cmd| format!("{:?}", cmd.outline)).collect::<Vec<_>>()
+    };
+    // the referenced character data is drawn like inline text
+    let inline = outlines(r#"<text y="20" font-size="20">Hello</text>"#);
+    assert_eq!(inline.len(), 5);
+    assert_eq!(outlines(r#"<text y="20" font-size="20"><tref xlink:href="#source"/></text>"#), inline);
+}
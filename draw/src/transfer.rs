@@ -0,0 +1,246 @@
+use crate::prelude::*;
+use pathfinder_color::matrix::ColorMatrix;
+use pathfinder_simd::default::F32x4;
+
+/// number of segments a smooth function is sampled with
+const CURVE_SEGMENTS: usize = 32;
+
+/// width of the steep segment that stands in for a jump of a `discrete` function.
+/// For up to 16 steps, no 8 bit value falls onto it.
+const JUMP_WIDTH: f32 = 1.0 / 4096.0;
+
+/// round to the precision of the render targets
+fn quantize(v: f32) -> f32 {
+    (v.max(0.0).min(1.0) * 255.0).round() / 255.0
+}
+
+/// A function from 0 ..= 1 to 0 ..= 1 that is linear between its nodes `(x, y)`.
+///
+/// The first node is at `x = 0` and the last one at `x = 1`, `x` increases from one node to the next,
+/// and every `y` is a multiple of 1/255, so adding up the segments in a render target does not round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve {
+    nodes: Vec<(f32, f32)>,
+}
+impl Curve {
+    pub fn identity() -> Curve {
+        Curve { nodes: vec![(0.0, 0.0), (1.0, 1.0)] }
+    }
+    pub fn is_identity(&self) -> bool {
+        *self == Curve::identity()
+    }
+    fn from_nodes(nodes: impl IntoIterator<Item=(f32, f32)>) -> Curve {
+        Curve { nodes: nodes.into_iter().map(|(x, y)| (x, quantize(y))).collect() }
+    }
+    /// `f` sampled at `x = (i / CURVE_SEGMENTS)^power`.
+    /// A power above 1 places more nodes near 0, for functions that are steep there.
+    pub fn sampled(f: impl Fn(f32) -> f32, power: i32) -> Curve {
+        Curve::from_nodes((0 ..= CURVE_SEGMENTS).map(|i| {
+            let x = (i as f32 / CURVE_SEGMENTS as f32).powi(power);
+            (x, f(x))
+        }))
+    }
+    pub fn srgb_to_linear() -> Curve {
+        Curve::sampled(srgb_to_linear, 1)
+    }
+    pub fn linear_to_srgb() -> Curve {
+        Curve::sampled(linear_to_srgb, 3)
+    }
+    /// `table`, `discrete` and `linear` functions are exact, `gamma` is sampled.
+    pub fn from_transfer(f: &TransferFunction) -> Curve {
+        match *f {
+            TransferFunction::Identity => Curve::identity(),
+            TransferFunction::Table(ref values) => match values.len() {
+                0 => Curve::identity(),
+                1 => Curve::from_nodes(vec![(0.0, values[0]), (1.0, values[0])]),
+                n => Curve::from_nodes(values.iter().enumerate().map(|(k, &v)| (k as f32 / (n - 1) as f32, v))),
+            },
+            TransferFunction::Discrete(ref values) => match values.len() {
+                0 => Curve::identity(),
+                n => {
+                    let mut nodes = vec![(0.0, values[0])];
+                    for k in 1 .. n {
+                        let x = k as f32 / n as f32;
+                        nodes.push((x - JUMP_WIDTH, values[k - 1]));
+                        nodes.push((x, values[k]));
+                    }
+                    nodes.push((1.0, values[n - 1]));
+                    Curve::from_nodes(nodes)
+                }
+            },
+            TransferFunction::Linear { slope, intercept } => {
+                // the line is clamped where it leaves 0 ..= 1, which needs a node of its own
+                let mut xs = vec![0.0, 1.0];
+                if slope != 0.0 {
+                    xs.extend([-intercept / slope, (1.0 - intercept) / slope].iter().copied().filter(|&x| x > 0.0 && x < 1.0));
+                }
+                xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                Curve::from_nodes(xs.into_iter().map(|x| (x, f.apply(x))))
+            }
+            TransferFunction::Gamma { exponent, .. } => Curve::sampled(|x| f.apply(x), if exponent < 1.0 { 3 } else { 1 }),
+        }
+    }
+}
+
+/// an sRGB value in linearRGB
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// a linearRGB value in sRGB
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// One segment for each of the color channels: `weight * clamp((c - start) * scale, 0, 1)`.
+/// A channel without a segment here has all of them zero.
+#[derive(Debug, Copy, Clone)]
+pub struct Ramp {
+    pub start: [f32; 3],
+    pub scale: [f32; 3],
+    pub weight: [f32; 3],
+}
+impl Ramp {
+    /// the matrix computing the (unweighted) ramps of an opaque layer, keeping it opaque
+    pub fn matrix(&self) -> ColorMatrix {
+        let [sr, sg, sb] = self.scale;
+        let [xr, xg, xb] = self.start;
+        ColorMatrix([
+            F32x4::new(sr, 0.0, 0.0, 0.0),
+            F32x4::new(0.0, sg, 0.0, 0.0),
+            F32x4::new(0.0, 0.0, sb, 0.0),
+            F32x4::splat(0.0),
+            F32x4::new(-xr * sr, -xg * sg, -xb * sb, 1.0),
+        ])
+    }
+}
+
+/// the matrix multiplying the color channels of an opaque layer by `weights`, keeping it opaque
+pub fn weight_matrix(weights: [f32; 3]) -> ColorMatrix {
+    let [wr, wg, wb] = weights;
+    ColorMatrix([
+        F32x4::new(wr, 0.0, 0.0, 0.0),
+        F32x4::new(0.0, wg, 0.0, 0.0),
+        F32x4::new(0.0, 0.0, wb, 0.0),
+        F32x4::splat(0.0),
+        F32x4::new(0.0, 0.0, 0.0, 1.0),
+    ])
+}
+
+/// Three curves, one for each color channel, as `f(c) = initial + Σ ramp(c)`, with one ramp for each segment.
+///
+/// The rising segments (positive weights) and the falling ones are added up separately, so neither sum
+/// has to go below 0. They have to stay within 1 though, which holds for every function
+/// that rises and falls by at most 1 in total, like all monotonic ones.
+pub struct RampSum {
+    pub initial: [f32; 3],
+    pub ramps: Vec<Ramp>,
+}
+impl RampSum {
+    pub fn new(curves: [&Curve; 3]) -> RampSum {
+        let segments = curves.iter().map(|c| c.nodes.len() - 1).max().unwrap_or(0);
+        let ramps = (0 .. segments).map(|i| {
+            let mut ramp = Ramp { start: [0.0; 3], scale: [0.0; 3], weight: [0.0; 3] };
+            for (c, curve) in curves.iter().enumerate() {
+                if let Some(&[(x0, y0), (x1, y1)]) = curve.nodes.get(i .. i + 2) {
+                    ramp.start[c] = x0;
+                    ramp.scale[c] = 1.0 / (x1 - x0);
+                    ramp.weight[c] = y1 - y0;
+                }
+            }
+            ramp
+        }).collect();
+        let initial = [curves[0].nodes[0].1, curves[1].nodes[0].1, curves[2].nodes[0].1];
+        let sum = RampSum { initial, ramps };
+        for c in 0 .. 3 {
+            if sum.initial[c] + sum.total(c, 1.0) > 1.0 + 1e-3 || sum.total(c, -1.0) > 1.0 + 1e-3 {
+                warn!("transfer function {:?} rises or falls by more than 1 in total, the result is clipped", curves[c]);
+            }
+        }
+        sum
+    }
+    /// the sum of the rising (`sign = 1`) or falling (`sign = -1`) segments of channel `c`
+    fn total(&self, c: usize, sign: f32) -> f32 {
+        self.ramps.iter().map(|r| (sign * r.weight[c]).max(0.0)).sum()
+    }
+    /// the weights of the rising (`sign = 1`) or falling (`sign = -1`) segments, zero for the others
+    pub fn weights(&self, sign: f32) -> impl Iterator<Item=[f32; 3]> + '_ {
+        self.ramps.iter().map(move |r| {
+            let w = |c: usize| (sign * r.weight[c]).max(0.0);
+            [w(0), w(1), w(2)]
+        })
+    }
+    /// the result for the 8 bit value `c` in `channel`, rounded to 8 bits after every step like the render targets
+    #[cfg(test)]
+    fn emulate(&self, channel: usize, c: u8) -> u8 {
+        let x = c as f32 / 255.0;
+        let (mut rising, mut falling) = (quantize(self.initial[channel]), 0.0);
+        for ramp in &self.ramps {
+            let r = quantize((x - ramp.start[channel]) * ramp.scale[channel]);
+            let w = ramp.weight[channel];
+            if w > 0.0 {
+                rising = quantize(rising + w * r);
+            } else if w < 0.0 {
+                falling = quantize(falling - w * r);
+            }
+        }
+        ((rising - falling).abs() * 255.0).round() as u8
+    }
+}
+
+#[cfg(test)]
+fn assert_emulated(f: &TransferFunction, tolerance: i32) {
+    assert_curve(&Curve::from_transfer(f), |c| f.apply(c), tolerance);
+}
+
+#[cfg(test)]
+fn assert_curve(curve: &Curve, f: impl Fn(f32) -> f32, tolerance: i32) {
+    let sum = RampSum::new([curve, &Curve::identity(), curve]);
+    for c in 0 ..= 255u8 {
+        let expected = (f(c as f32 / 255.0) * 255.0).round() as i32;
+        for &channel in &[0, 2] {
+            let out = sum.emulate(channel, c) as i32;
+            assert!((out - expected).abs() <= tolerance, "{:?} of {}: {} != {}", curve, c, out, expected);
+        }
+        assert_eq!(sum.emulate(1, c), c);
+    }
+}
+
+#[test]
+fn test_gamma() {
+    assert_emulated(&TransferFunction::Gamma { amplitude: 1.0, exponent: 2.2, offset: 0.0 }, 1);
+    assert_emulated(&TransferFunction::Gamma { amplitude: 1.0, exponent: 0.5, offset: 0.0 }, 1);
+    assert_emulated(&TransferFunction::Gamma { amplitude: 0.5, exponent: 3.0, offset: 0.25 }, 1);
+}
+
+#[test]
+fn test_table_and_discrete() {
+    // falling, and rising and then falling again
+    assert_emulated(&TransferFunction::Table(vec![1.0, 0.0]), 1);
+    assert_emulated(&TransferFunction::Table(vec![0.0, 1.0, 0.2]), 1);
+    // the steps are exact
+    assert_emulated(&TransferFunction::Discrete(vec![0.0, 0.5, 1.0]), 0);
+    assert_emulated(&TransferFunction::Discrete(vec![0.6, 0.1, 0.5, 0.3]), 0);
+}
+
+#[test]
+fn test_linear() {
+    // clamped on both ends
+    assert_emulated(&TransferFunction::Linear { slope: 2.0, intercept: -0.5 }, 1);
+    assert_emulated(&TransferFunction::Linear { slope: -1.0, intercept: 0.75 }, 1);
+    assert_eq!(Curve::from_transfer(&TransferFunction::Linear { slope: 1.0, intercept: 0.0 }), Curve::identity());
+}
+
+#[test]
+fn test_color_space_conversion() {
+    assert_curve(&Curve::srgb_to_linear(), srgb_to_linear, 1);
+    assert_curve(&Curve::linear_to_srgb(), linear_to_srgb, 1);
+}
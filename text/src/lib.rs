@@ -184,7 +184,10 @@ fn sub_pass<F, G>(gsub: &GSub, lang: &LanguageSystem, meta: &[MetaGlyph], gids:
     }
 }
 
-fn process_chunk(font: &Font, font_idx: usize, language: Option<Tag>, rtl: bool, meta: &[MetaGlyph], state: &mut State) {
+/// height of synthesized small capitals relative to real capitals
+const SMALL_CAPS_SCALE: f32 = 0.75;
+
+fn process_chunk(font: &Font, font_idx: usize, language: Option<Tag>, rtl: bool, small_caps: bool, meta: &[MetaGlyph], state: &mut State) {
     if let Some(fm) = font.vmetrics() {
         let s = font.font_matrix().m22();
         let vm = VMetrics {
@@ -200,22 +203,49 @@ fn process_chunk(font: &Font, font_idx: usize, language: Option<Tag>, rtl: bool,
     for g in meta {
         debug!("[\u{2068}{}\u{2069} 0x{:x}]", g.codepoint, g.codepoint as u32);
     }
+    let otf = font.downcast::<OpenTypeFont>();
+    let gsub = otf.and_then(|f| f.gsub.as_ref());
+    let gdef = otf.and_then(|f| f.gdef.as_ref());
+    let gpos = otf.and_then(|f| f.gpos.as_ref());
+    let gsub_lang = gsub.and_then(|gsub| language.and_then(|s| gsub.language(s)).or(gsub.default_language()));
+
+    // use the font's small capitals if it has them, otherwise draw scaled down capitals
+    let smcp = Tag(*b"smcp");
+    let has_smcp = match (gsub, gsub_lang) {
+        (Some(gsub), Some(lang)) => gsub.subs(lang, |tag| tag == smcp).next().is_some(),
+        _ => false
+    };
+    let synthesize_small_caps = small_caps && !has_smcp;
+    let mut synthetic = vec![];
+
     // (codepoint idx, glyph id)
     let mut gids: Vec<(usize, GlyphId)> = meta.iter()
         .filter(|&m| match m.category {
             GeneralCategory::Format => false,
             _ => true
         })
-        .map(|m| (m.idx, font.gid_for_unicode_codepoint(m.codepoint as u32).unwrap()))
+        .map(|m| {
+            if synthesize_small_caps && m.codepoint.is_lowercase() {
+                let mut upper = m.codepoint.to_uppercase();
+                if let (Some(c), None) = (upper.next(), upper.next()) {
+                    if let Some(gid) = font.gid_for_unicode_codepoint(c as u32) {
+                        synthetic.push(m.idx);
+                        return (m.idx, gid);
+                    }
+                }
+            }
+            (m.idx, font.gid_for_unicode_codepoint(m.codepoint as u32).unwrap())
+        })
         .collect();
 
-    let otf = font.downcast::<OpenTypeFont>();
-    let gsub = otf.and_then(|f| f.gsub.as_ref());
-    let gdef = otf.and_then(|f| f.gdef.as_ref());
-    let gpos = otf.and_then(|f| f.gpos.as_ref());
-
     if let Some(gsub) = gsub {
-        if let Some(lang) = language.and_then(|s| gsub.language(s)).or(gsub.default_language()) {
+        if let Some(lang) = gsub_lang {
+            // the forms of the language come first, the other features apply to them
+            let locl = Tag(*b"locl");
+            sub_pass(gsub, lang, meta, &mut gids, |_| move |tag| tag == locl);
+            if small_caps && has_smcp {
+                sub_pass(gsub, lang, meta, &mut gids, |_| move |tag| tag == smcp);
+            }
             sub_pass(gsub, lang, meta, &mut gids, |m| {
                 let arabic_tag = match m.location {
                     GlyphLocation::Isolated => Tag(*b"isol"),
@@ -239,10 +269,14 @@ fn process_chunk(font: &Font, font_idx: usize, language: Option<Tag>, rtl: bool,
                 _ => None
             };
 
+            let scale = match synthetic.contains(&index) {
+                true => SMALL_CAPS_SCALE,
+                false => 1.0
+            };
             let (advance, offset) = match mark {
                 None => {
                     let kerning = vec2f(last_gid.replace(gid).map(|left| font.kerning(left, gid)).unwrap_or_default(), 0.0);
-                    let advance = font.font_matrix() * (vec2f(glyph.metrics.advance, 0.0) + kerning);
+                    let advance = font.font_matrix() * (vec2f(glyph.metrics.advance, 0.0) + kerning) * scale;
                     match rtl {
                         false => (advance, state.offset + kerning),
                         true => (advance * vec2f(-1.0, 1.0), state.offset - advance)
@@ -254,7 +288,7 @@ fn process_chunk(font: &Font, font_idx: usize, language: Option<Tag>, rtl: bool,
                 }
             };
 
-            let transform = Transform2F::from_scale(vec2f(1.0, -1.0)) * font.font_matrix();
+            let transform = Transform2F::from_scale(vec2f(scale, -scale)) * font.font_matrix();
             state.offset += advance;
             state.glyphs.push(LayoutGlyph { gid, transform, offset, index, font_idx });
         }
@@ -286,8 +320,11 @@ struct State {
     vmetrics: Option<VMetrics>,
 }
 
-fn font_for_text<'a>(fonts: &'a [Font], text: &str, meta: &[MetaGlyph]) -> Option<(usize, &'a Font)> {
-    fonts.iter().enumerate()
+/// the first font that has glyphs for all of `text`.
+/// If there are several, one with forms for `language` (a language system in its GSUB table) is preferred,
+/// as for the different regional forms of CJK ideographs.
+fn font_for_text<'a>(fonts: &'a [Font], text: &str, meta: &[MetaGlyph], language: Option<Tag>) -> Option<(usize, &'a Font)> {
+    let candidates = || fonts.iter().enumerate()
         .filter(|(_, font)|
             text.chars().zip(meta).all(|(c, m)| {
                 match m.category {
@@ -295,11 +332,20 @@ fn font_for_text<'a>(fonts: &'a [Font], text: &str, meta: &[MetaGlyph]) -> Optio
                     _ => font.gid_for_unicode_codepoint(c as u32).is_some()
                 }
             })
-        ).next()
+        );
+    let has_language = |font: &Font| match language {
+        Some(language) => font.downcast::<OpenTypeFont>()
+            .and_then(|f| f.gsub.as_ref())
+            .and_then(|gsub| gsub.language(language))
+            .is_some(),
+        None => false
+    };
+    candidates().find(|(_, font)| has_language(font)).or_else(|| candidates().next())
 }
 
 impl FontCollection {
-    pub fn layout_run(&self, string: &str, rtl: bool, lang: Option<Language>) -> Layout {
+    /// `small_caps` requests the `smcp` feature, which is synthesized for fonts that lack it.
+    pub fn layout_run(&self, string: &str, rtl: bool, lang: Option<Language>, small_caps: bool) -> Layout {
         let lang = lang.and_then(tags::lang_to_tag).or_else(|| guess_lang(string));
 
         let fonts = &*self.fonts;
@@ -320,18 +366,18 @@ impl FontCollection {
             compute_joining(&mut meta);
             
             // try to find a font that has all glyphs
-            if let Some((font_idx, font)) = font_for_text(fonts, word, &meta) {
-                process_chunk(font, font_idx, lang, rtl, &meta, &mut state);
+            if let Some((font_idx, font)) = font_for_text(fonts, word, &meta, lang) {
+                process_chunk(font, font_idx, lang, rtl, small_caps, &meta, &mut state);
             } else {
                 let mut start = 0;
                 let mut meta_idx = 0;
                 let mut current_font = None;
                 for (idx, grapheme) in GraphemeIndices::new(word) {
                     let meta_len = grapheme.chars().count();
-                    if let Some((font_idx, font)) = font_for_text(fonts, grapheme, &meta[meta_idx .. meta_idx + meta_len]) {
+                    if let Some((font_idx, font)) = font_for_text(fonts, grapheme, &meta[meta_idx .. meta_idx + meta_len], lang) {
                         if Some(font_idx) != current_font.map(|(i, _)| i) && idx > 0 {
                             // flush so fart.0
-                            process_chunk(font, font_idx, lang, rtl, &meta[start .. idx], &mut state);
+                            process_chunk(font, font_idx, lang, rtl, small_caps, &meta[start .. idx], &mut state);
                             start = idx;
                         }
                         current_font = Some((font_idx, font));
@@ -342,7 +388,7 @@ impl FontCollection {
                     meta_idx += meta_len;
                 }
                 if let Some((font_idx, font)) = current_font {
-                    process_chunk(font, font_idx, lang, rtl, &meta[meta_idx ..], &mut state);
+                    process_chunk(font, font_idx, lang, rtl, small_caps, &meta[meta_idx ..], &mut state);
                 }
             }
         }
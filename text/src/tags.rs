@@ -22,7 +22,7 @@ pub fn lang_to_tag(lang: Language) -> Option<Tag> {
         Rus => tag(b"RUS "),
 
         // 普通话 (Mandarin)
-        Cmn => None,
+        Cmn | Zho => tag(b"ZHS "),
 
         // Español (Spanish)
         Spa => tag(b"ESP "),
@@ -175,7 +175,7 @@ pub fn lang_to_tag(lang: Language) -> Option<Tag> {
         Tel => tag(b"TEL "),
 
         // فارسی (Persian)
-        Pes => tag(b"FAR "),
+        Pes | Fas => tag(b"FAR "),
 
         // മലയാളം (Malayalam)
         Mal => tag(b"MAL "),